@@ -0,0 +1,73 @@
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#ifndef RUST_IOS_HELLO_H
+#define RUST_IOS_HELLO_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The part of [`GuiState`] visible to the host.
+typedef struct RustGuiState {
+  int32_t click_count;
+  bool button_state;
+} RustGuiState;
+
+// Called with the new state and the `user_data` given at registration.
+typedef void (*RustGuiStateCallback)(struct RustGuiState state, void *user_data);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+int32_t rust_gui_click_count(void);
+
+bool rust_gui_button_state(void);
+
+struct RustGuiState rust_gui_state(void);
+
+// Setters and reset take effect, and show in the getters and callbacks, on
+// the app's next frame.
+void rust_gui_set_click_count(int32_t click_count);
+
+void rust_gui_set_button_state(bool button_state);
+
+void rust_gui_reset(void);
+
+// Returns a token for [`rust_gui_unregister_state_callback`], or 0 if
+// `callback` is null.
+uint64_t rust_gui_register_state_callback(RustGuiStateCallback callback, void *user_data);
+
+// Returns whether a callback was registered under `token`.
+bool rust_gui_unregister_state_callback(uint64_t token);
+
+// Starts the GUI rendering into `ui_view`, a `UIView *`. Sizes are in
+// pixels. Returns `false` if the view is null or the GUI is already running.
+//
+// iOS only. Call this and the embedding functions below from the main
+// thread.
+bool rust_gui_start(void *ui_view,
+                    uint32_t physical_width,
+                    uint32_t physical_height,
+                    float scale_factor);
+
+// Runs one frame. Returns `false` if nothing is running or the app asked to
+// exit.
+bool rust_gui_pump(void);
+
+void rust_gui_pause(void);
+
+void rust_gui_resume(void);
+
+void rust_gui_resize(uint32_t physical_width, uint32_t physical_height, float scale_factor);
+
+// Stops and drops the running app. Returns its exit code, or 0 if nothing
+// was running.
+int32_t rust_gui_shutdown(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RUST_IOS_HELLO_H */
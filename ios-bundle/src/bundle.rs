@@ -0,0 +1,302 @@
+//! Assembling the `.app` directory.
+//!
+//! An iOS app bundle is a flat directory: the executable and `Info.plist` at
+//! the top, resources next to them. [`assemble`] lays it out from an already
+//! built binary, reporting each step as it goes, so it runs after
+//! compilation instead of from inside the build of the binary it copies.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::icons::{app_icons, check_icon_source, launch_image, write_icons, LAUNCH_IMAGE};
+use crate::metadata::IosMetadata;
+use crate::pattern::selected;
+use crate::plist::to_xml;
+use crate::png::Image;
+use crate::stamp::{is_up_to_date, write_if_stale, write_stamp, StampOutcome};
+
+/// A file or directory copied into the bundle.
+///
+/// Directories are mirrored: files that are no longer in the source, or no
+/// longer selected by the patterns, are removed from the bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resource {
+    pub source: PathBuf,
+    /// Path inside the bundle.
+    pub destination: PathBuf,
+    /// Patterns selecting the files of a directory to copy, see
+    /// [`matches`](crate::pattern::matches). Empty copies every file.
+    pub include: Vec<String>,
+    /// Patterns for files not to copy, even if included.
+    pub exclude: Vec<String>,
+}
+
+/// What goes into the bundle.
+#[derive(Debug, Clone)]
+pub struct BundleSpec {
+    pub metadata: IosMetadata,
+    /// Directory of the manifest the metadata came from; its paths are
+    /// relative to this.
+    pub manifest_dir: PathBuf,
+    /// The compiled binary, copied in as `metadata.executable`.
+    pub executable: PathBuf,
+    pub resources: Vec<Resource>,
+}
+
+/// One completed step of [`assemble`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub name: &'static str,
+    pub detail: String,
+}
+
+#[derive(Debug)]
+pub struct BundleError {
+    pub step: &'static str,
+    pub path: PathBuf,
+    pub source: io::Error,
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.step, self.path.display(), self.source)
+    }
+}
+
+impl std::error::Error for BundleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+fn failed(step: &'static str, path: &Path) -> impl FnOnce(io::Error) -> BundleError {
+    let path = path.to_path_buf();
+    move |source| BundleError { step, path, source }
+}
+
+/// Creates or updates the bundle at `bundle_dir`. `stamp_dir` keeps the
+/// stamps deciding whether generated files are stale; it should be outside
+/// the bundle. `report` is called after each step.
+pub fn assemble(
+    spec: &BundleSpec,
+    bundle_dir: &Path,
+    stamp_dir: &Path,
+    report: &mut dyn FnMut(&Step),
+) -> Result<(), BundleError> {
+    const CREATE: &str = "create bundle";
+    fs::create_dir_all(bundle_dir).map_err(failed(CREATE, bundle_dir))?;
+    report(&Step {
+        name: CREATE,
+        detail: bundle_dir.display().to_string(),
+    });
+
+    const EXECUTABLE: &str = "copy executable";
+    let executable = bundle_dir.join(&spec.metadata.executable);
+    fs::copy(&spec.executable, &executable).map_err(failed(EXECUTABLE, &spec.executable))?;
+    make_executable(&executable).map_err(failed(EXECUTABLE, &executable))?;
+    report(&Step {
+        name: EXECUTABLE,
+        detail: format!("{} -> {}", spec.executable.display(), executable.display()),
+    });
+
+    const PLIST: &str = "write Info.plist";
+    let plist_path = bundle_dir.join("Info.plist");
+    let plist = to_xml(&spec.metadata.info_plist())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        .map_err(failed(PLIST, &plist_path))?;
+    let outcome = write_if_stale(
+        &plist_path,
+        &stamp_dir.join("Info.plist.stamp"),
+        &[plist.as_bytes()],
+        plist.as_bytes(),
+    )
+    .map_err(failed(PLIST, &plist_path))?;
+    report(&Step {
+        name: PLIST,
+        detail: describe(outcome),
+    });
+
+    if let Some(icon) = &spec.metadata.icon {
+        const ICONS: &str = "generate icons";
+        let source_path = spec.manifest_dir.join(icon);
+        let source = fs::read(&source_path).map_err(failed(ICONS, &source_path))?;
+        let icons = app_icons(&spec.metadata.device_family);
+        let outputs: Vec<PathBuf> = icons
+            .iter()
+            .map(|icon| bundle_dir.join(icon.file_name()))
+            .collect();
+        let outputs: Vec<&Path> = outputs.iter().map(PathBuf::as_path).collect();
+        let names = icons
+            .iter()
+            .map(|icon| icon.file_name())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let inputs = [source.as_slice(), names.as_bytes()];
+        let stamp = stamp_dir.join("icons.stamp");
+
+        let detail = if is_up_to_date(&outputs, &stamp, &inputs) {
+            "up to date".to_string()
+        } else {
+            let image = Image::decode(&source)
+                .map_err(|e| e.to_string())
+                .and_then(|image| check_icon_source(&image).map(|()| image))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+                .map_err(failed(ICONS, &source_path))?;
+            write_icons(&image, &icons, bundle_dir).map_err(failed(ICONS, bundle_dir))?;
+            write_stamp(&stamp, &inputs).map_err(failed(ICONS, &stamp))?;
+            format!("{} -> {} icons", source_path.display(), icons.len())
+        };
+        report(&Step {
+            name: ICONS,
+            detail,
+        });
+    }
+
+    if let Some(background) = spec.metadata.launch_background {
+        const LAUNCH: &str = "write launch screen";
+        let path = bundle_dir.join(format!("{}.png", LAUNCH_IMAGE));
+        let stamp = stamp_dir.join("launch.stamp");
+        let outcome = if is_up_to_date(&[&path], &stamp, &[&background]) {
+            StampOutcome::UpToDate
+        } else {
+            let existed = path.exists();
+            fs::write(&path, launch_image(background).encode()).map_err(failed(LAUNCH, &path))?;
+            write_stamp(&stamp, &[&background]).map_err(failed(LAUNCH, &stamp))?;
+            if existed {
+                StampOutcome::Replaced
+            } else {
+                StampOutcome::Created
+            }
+        };
+        report(&Step {
+            name: LAUNCH,
+            detail: describe(outcome),
+        });
+    }
+
+    const RESOURCES: &str = "copy resources";
+    for resource in &spec.resources {
+        let destination = bundle_dir.join(&resource.destination);
+        let (copied, removed) =
+            mirror(resource, &destination).map_err(failed(RESOURCES, &resource.source))?;
+        let removed = match removed {
+            0 => String::new(),
+            removed => format!(", {} removed", removed),
+        };
+        report(&Step {
+            name: RESOURCES,
+            detail: format!(
+                "{} -> {} ({} files{})",
+                resource.source.display(),
+                resource.destination.display(),
+                copied,
+                removed
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+fn describe(outcome: StampOutcome) -> String {
+    match outcome {
+        StampOutcome::Created => "created",
+        StampOutcome::Replaced => "regenerated",
+        StampOutcome::UpToDate => "up to date",
+    }
+    .to_string()
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Copies `resource` to `destination` and removes whatever else is there.
+/// Hidden files such as `.DS_Store` are never copied. Returns the number of
+/// files copied and removed.
+fn mirror(resource: &Resource, destination: &Path) -> io::Result<(usize, usize)> {
+    if !resource.source.is_dir() {
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&resource.source, destination)?;
+        return Ok((1, 0));
+    }
+
+    let mut copied = HashSet::new();
+    copy_selected(resource, &resource.source, destination, "", &mut copied)?;
+    // Nothing may have been copied, if the directory is empty or every file
+    // is excluded.
+    fs::create_dir_all(destination)?;
+    let removed = remove_unlisted(destination, "", &copied)?;
+    Ok((copied.len(), removed))
+}
+
+fn copy_selected(
+    resource: &Resource,
+    source: &Path,
+    destination: &Path,
+    relative: &str,
+    copied: &mut HashSet<String>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        let path = join(relative, &name);
+        if entry.file_type()?.is_dir() {
+            copy_selected(
+                resource,
+                &entry.path(),
+                &destination.join(&name),
+                &path,
+                copied,
+            )?;
+        } else if selected(&path, &resource.include, &resource.exclude) {
+            fs::create_dir_all(destination)?;
+            fs::copy(entry.path(), destination.join(&name))?;
+            copied.insert(path);
+        }
+    }
+    Ok(())
+}
+
+/// Deletes files below `dir` that aren't in `keep`, then directories left
+/// empty. Returns the number of files deleted.
+fn remove_unlisted(dir: &Path, relative: &str, keep: &HashSet<String>) -> io::Result<usize> {
+    let mut removed = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = join(relative, &entry.file_name().to_string_lossy());
+        if entry.file_type()?.is_dir() {
+            removed += remove_unlisted(&entry.path(), &path, keep)?;
+            if fs::read_dir(entry.path())?.next().is_none() {
+                fs::remove_dir(entry.path())?;
+            }
+        } else if !keep.contains(&path) {
+            fs::remove_file(entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+fn join(relative: &str, name: &str) -> String {
+    if relative.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", relative, name)
+    }
+}
@@ -0,0 +1,319 @@
+//! Reading the backtraces out of iOS crash reports and writing them back
+//! with Rust functions, files and lines.
+//!
+//! Two formats are read: the JSON `.ips` files iOS 15 and later write (and
+//! Xcode's Devices window exports), and the older plain text `.crash`
+//! format. Either way each frame becomes an image, found by its build UUID
+//! in the symbol archive, and an address inside it.
+
+use crate::symbols::{BuildUuid, Frame, SymbolError, Symbolicator};
+
+/// A binary loaded in the crashed process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryImage {
+    pub name: String,
+    pub uuid: Option<BuildUuid>,
+    /// Where its `__TEXT` segment was loaded.
+    pub load_address: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashFrame {
+    /// Index into [`CrashReport::images`], if the image is listed.
+    pub image: Option<usize>,
+    pub address: u64,
+    /// What the report already says about the frame, e.g. `abort + 180`.
+    pub symbol: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashThread {
+    /// `Thread 0 Crashed`, `Thread 3`, ...
+    pub title: String,
+    pub crashed: bool,
+    pub frames: Vec<CrashFrame>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashReport {
+    pub images: Vec<BinaryImage>,
+    pub threads: Vec<CrashThread>,
+}
+
+impl CrashReport {
+    /// Reads a `.ips` or `.crash` report.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let report = if text.trim_start().starts_with('{') {
+            Self::parse_ips(text)?
+        } else {
+            Self::parse_text(text)
+        };
+        if report.threads.is_empty() {
+            return Err("no backtraces found in the crash report".to_string());
+        }
+        Ok(report)
+    }
+
+    /// An `.ips` file is a one-line JSON header followed by the JSON report;
+    /// some tools save the report alone.
+    fn parse_ips(text: &str) -> Result<Self, String> {
+        let text = text.trim_start();
+        let body: serde_json::Value = match text.split_once('\n') {
+            Some((header, body))
+                if serde_json::from_str::<serde_json::Value>(header).is_ok()
+                    && !body.trim().is_empty() =>
+            {
+                serde_json::from_str(body)
+            }
+            _ => serde_json::from_str(text),
+        }
+        .map_err(|e| format!("not a crash report: {}", e))?;
+
+        let images = body["usedImages"]
+            .as_array()
+            .ok_or("crash report has no usedImages")?
+            .iter()
+            .map(|image| BinaryImage {
+                name: image["name"]
+                    .as_str()
+                    .or_else(|| image["path"].as_str().and_then(|p| p.rsplit('/').next()))
+                    .unwrap_or("???")
+                    .to_string(),
+                uuid: image["uuid"].as_str().and_then(BuildUuid::parse),
+                load_address: image["base"].as_u64().unwrap_or(0),
+            })
+            .collect::<Vec<_>>();
+
+        let threads = body["threads"]
+            .as_array()
+            .ok_or("crash report has no threads")?
+            .iter()
+            .enumerate()
+            .map(|(i, thread)| {
+                let crashed = thread["triggered"].as_bool().unwrap_or(false);
+                let frames = thread["frames"]
+                    .as_array()
+                    .map(Vec::as_slice)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|frame| {
+                        let image = frame["imageIndex"]
+                            .as_u64()
+                            .map(|index| index as usize)
+                            .filter(|&index| index < images.len());
+                        let offset = frame["imageOffset"].as_u64().unwrap_or(0);
+                        let base = image.map_or(0, |index| images[index].load_address);
+                        let symbol = frame["symbol"].as_str().map(|symbol| {
+                            match frame["symbolLocation"].as_u64() {
+                                Some(location) => format!("{} + {}", symbol, location),
+                                None => symbol.to_string(),
+                            }
+                        });
+                        // A frame past the end of the address space can't
+                        // be symbolicated; the report is malformed there.
+                        Some(CrashFrame {
+                            image,
+                            address: base.checked_add(offset)?,
+                            symbol,
+                        })
+                    })
+                    .collect();
+                CrashThread {
+                    title: format!("Thread {}{}", i, if crashed { " Crashed" } else { "" }),
+                    crashed,
+                    frames,
+                }
+            })
+            .collect();
+        Ok(Self { images, threads })
+    }
+
+    /// The text format: `Thread N[ Crashed]:` followed by frames like
+    /// `0   rust-ios-test   0x0000000100a2c018 0x100a28000 + 16408`, and a
+    /// `Binary Images:` list at the end.
+    fn parse_text(text: &str) -> Self {
+        let mut images = Vec::new();
+        let mut threads: Vec<CrashThread> = Vec::new();
+        // Frames name their image; they are matched up once all are read.
+        let mut names: Vec<Vec<String>> = Vec::new();
+        let mut in_thread = false;
+        let mut in_images = false;
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                in_thread = false;
+                continue;
+            }
+            if line == "Binary Images:" {
+                in_images = true;
+                continue;
+            }
+            if in_images {
+                if let Some(image) = parse_image_line(line) {
+                    images.push(image);
+                }
+                continue;
+            }
+            if let Some(title) = line
+                .strip_suffix(':')
+                .filter(|title| is_thread_title(title))
+            {
+                threads.push(CrashThread {
+                    title: title.to_string(),
+                    crashed: title.ends_with("Crashed"),
+                    frames: Vec::new(),
+                });
+                names.push(Vec::new());
+                in_thread = true;
+                continue;
+            }
+            if in_thread {
+                if let (Some(thread), Some((name, frame))) =
+                    (threads.last_mut(), parse_frame_line(line))
+                {
+                    thread.frames.push(frame);
+                    names.last_mut().expect("one list per thread").push(name);
+                }
+            }
+        }
+
+        for (thread, names) in threads.iter_mut().zip(&names) {
+            for (frame, name) in thread.frames.iter_mut().zip(names) {
+                frame.image = images
+                    .iter()
+                    .position(|image: &BinaryImage| &image.name == name);
+            }
+        }
+        Self { images, threads }
+    }
+}
+
+/// `Thread 0`, `Thread 0 Crashed`; not `Thread 0 crashed with ARM Thread
+/// State (64-bit)`, which starts the register dump.
+fn is_thread_title(title: &str) -> bool {
+    let mut words = title.split_whitespace();
+    words.next() == Some("Thread")
+        && words
+            .next()
+            .is_some_and(|n| n.bytes().all(|b| b.is_ascii_digit()))
+        && matches!(words.next(), None | Some("Crashed"))
+        && words.next().is_none()
+}
+
+fn parse_hex(text: &str) -> Option<u64> {
+    u64::from_str_radix(text.strip_prefix("0x")?, 16).ok()
+}
+
+/// `3   rust-ios-test   0x0000000104a2c050 0x104a28000 + 16464`
+fn parse_frame_line(line: &str) -> Option<(String, CrashFrame)> {
+    let (index, rest) = line.split_once(char::is_whitespace)?;
+    index.parse::<u32>().ok()?;
+    // The name is padded with spaces and then a tab, so the address is the
+    // first `0x` that follows any whitespace.
+    let at = rest
+        .match_indices("0x")
+        .map(|(at, _)| at)
+        .find(|&at| rest[..at].ends_with(char::is_whitespace))?;
+    let name = rest[..at].trim().to_string();
+    let mut after = rest[at..].splitn(2, char::is_whitespace);
+    let address = parse_hex(after.next()?)?;
+    // Unsymbolicated frames read `0x104a28000 + 16412`.
+    let symbol = after
+        .next()
+        .map(str::trim)
+        .filter(|symbol| !symbol.is_empty() && !symbol.starts_with("0x"))
+        .map(str::to_string);
+    Some((
+        name,
+        CrashFrame {
+            image: None,
+            address,
+            symbol,
+        },
+    ))
+}
+
+/// `0x104a28000 - 0x104b2bfff rust-ios-test arm64  <8b3c...> /private/...`
+fn parse_image_line(line: &str) -> Option<BinaryImage> {
+    let mut words = line.split_whitespace();
+    let load_address = parse_hex(words.next()?)?;
+    if words.next()? != "-" {
+        return None;
+    }
+    parse_hex(words.next()?)?;
+    let rest: Vec<&str> = words.collect();
+    let uuid_at = rest.iter().position(|word| word.starts_with('<'));
+    // The architecture follows the name when there is a UUID after it.
+    let name_words = match uuid_at {
+        Some(at) if at >= 2 => &rest[..at - 1],
+        Some(at) => &rest[..at],
+        None => &rest[..rest.len().min(1)],
+    };
+    Some(BinaryImage {
+        name: name_words.join(" ").trim_start_matches('+').to_string(),
+        uuid: uuid_at.and_then(|at| BuildUuid::parse(rest[at])),
+        load_address,
+    })
+}
+
+/// The report's backtraces with every frame the archive has symbols for
+/// replaced by its Rust function, file and line. Inlined calls get a line
+/// each, marked `[inlined]`, under the same frame number.
+///
+/// Only the first frame of a thread is where execution stopped; the others
+/// are return addresses, so the call they made is looked up one byte
+/// earlier.
+pub fn symbolicate_report(
+    report: &CrashReport,
+    symbolicator: &mut Symbolicator,
+) -> Result<String, SymbolError> {
+    let mut out = String::new();
+    for (i, thread) in report.threads.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(&format!("{}:\n", thread.title));
+        for (number, frame) in thread.frames.iter().enumerate() {
+            let image = frame.image.map(|index| &report.images[index]);
+            let name = image.map_or("???", |image| image.name.as_str());
+            let lookup = if number == 0 {
+                frame.address
+            } else {
+                frame.address.saturating_sub(1)
+            };
+            let frames = match image.and_then(|image| image.uuid.map(|uuid| (image, uuid))) {
+                Some((image, uuid)) => symbolicator
+                    .symbolicate(uuid, image.load_address, lookup)?
+                    .unwrap_or_default(),
+                None => Vec::new(),
+            };
+
+            let prefix = format!("{:<4}{:<30} {:#018x}", number, name, frame.address);
+            if frames.is_empty() {
+                let original = match (&frame.symbol, image) {
+                    (Some(symbol), _) => symbol.clone(),
+                    (None, Some(image)) => format!(
+                        "{:#x} + {}",
+                        image.load_address,
+                        frame.address.wrapping_sub(image.load_address)
+                    ),
+                    (None, None) => String::new(),
+                };
+                out.push_str(&format!("{} {}\n", prefix, original));
+            }
+            for symbolicated in &frames {
+                out.push_str(&format!("{} {}\n", prefix, describe(symbolicated)));
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn describe(frame: &Frame) -> String {
+    if frame.inlined {
+        format!("{} [inlined]", frame)
+    } else {
+        frame.to_string()
+    }
+}
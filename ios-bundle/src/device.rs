@@ -0,0 +1,411 @@
+//! Signing bundles for and installing them on physical devices.
+//!
+//! iOS only runs signed code on a device. [`sign_bundle`] embeds the
+//! provisioning profile as `embedded.mobileprovision`, writes entitlements
+//! derived from it and runs `codesign` with the configured identity.
+//! [`install_and_launch`] then uses `devicectl` (Xcode 15 and newer) or
+//! `ios-deploy`. Both go through a [`CommandRunner`].
+//!
+//! The identity, profile and device are read from `[package.metadata.ios]`:
+//!
+//! ```toml
+//! signing-identity = "Apple Development: Jane Doe (AB12CD34EF)"
+//! provisioning-profile = "signing/development.mobileprovision"
+//! device = "Jane's iPhone"
+//! ```
+//!
+//! and can be overridden with `IOS_SIGNING_IDENTITY`,
+//! `IOS_PROVISIONING_PROFILE` and `IOS_DEVICE`.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use plist::{Dictionary, Value};
+
+use crate::bundle::Step;
+use crate::metadata::IosMetadata;
+use crate::plist::{from_xml, to_xml};
+use crate::runner::{io_error, run_checked, CommandError, CommandRunner};
+
+pub const SIGNING_IDENTITY_ENV: &str = "IOS_SIGNING_IDENTITY";
+pub const PROVISIONING_PROFILE_ENV: &str = "IOS_PROVISIONING_PROFILE";
+pub const DEVICE_ENV: &str = "IOS_DEVICE";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceError {
+    /// Signing or deployment is not configured well enough to run.
+    Config(String),
+    /// The provisioning profile is unreadable or doesn't fit the app.
+    Profile(String),
+    Command(CommandError),
+}
+
+impl fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviceError::Config(message) => write!(f, "{}", message),
+            DeviceError::Profile(message) => write!(f, "provisioning profile: {}", message),
+            DeviceError::Command(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for DeviceError {}
+
+impl From<CommandError> for DeviceError {
+    fn from(error: CommandError) -> Self {
+        DeviceError::Command(error)
+    }
+}
+
+/// The parts of a `.mobileprovision` that matter for signing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProvisioningProfile {
+    pub name: String,
+    pub uuid: String,
+    pub team_id: String,
+    /// The `application-identifier` entitlement, e.g. `AB12CD34EF.com.example.*`.
+    pub application_identifier: String,
+    pub entitlements: Dictionary,
+    pub expiration_date: plist::Date,
+    /// UDIDs of the devices the profile allows, or `None` if it allows all.
+    pub devices: Option<Vec<String>>,
+}
+
+impl ProvisioningProfile {
+    /// Reads a profile: an XML plist inside a CMS signature. The signature
+    /// is not checked here; `codesign` and the device do that.
+    pub fn parse(bytes: &[u8]) -> Result<Self, DeviceError> {
+        let invalid = |message: &str| DeviceError::Profile(message.to_string());
+        let start = find(bytes, b"<?xml").ok_or_else(|| invalid("no property list found"))?;
+        let end = find(&bytes[start..], b"</plist>")
+            .map(|at| start + at + "</plist>".len())
+            .ok_or_else(|| invalid("unterminated property list"))?;
+        let xml = std::str::from_utf8(&bytes[start..end])
+            .map_err(|_| invalid("property list is not UTF-8"))?;
+        let plist = from_xml(xml).map_err(|e| DeviceError::Profile(e.to_string()))?;
+        let plist = plist
+            .as_dictionary()
+            .ok_or_else(|| invalid("property list is not a dictionary"))?;
+
+        let string = |key: &str| {
+            plist
+                .get(key)
+                .and_then(Value::as_string)
+                .map(str::to_string)
+                .ok_or_else(|| DeviceError::Profile(format!("missing {}", key)))
+        };
+        let entitlements = plist
+            .get("Entitlements")
+            .and_then(Value::as_dictionary)
+            .cloned()
+            .ok_or_else(|| invalid("missing Entitlements"))?;
+        let application_identifier = entitlements
+            .get("application-identifier")
+            .and_then(Value::as_string)
+            .map(str::to_string)
+            .ok_or_else(|| invalid("missing application-identifier entitlement"))?;
+        let team_id = plist
+            .get("TeamIdentifier")
+            .and_then(Value::as_array)
+            .and_then(|teams| teams.first())
+            .and_then(Value::as_string)
+            .map(str::to_string)
+            .ok_or_else(|| invalid("missing TeamIdentifier"))?;
+        let devices = plist.get("ProvisionedDevices").map(|devices| {
+            devices
+                .as_array()
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(Value::as_string)
+                .map(str::to_string)
+                .collect()
+        });
+
+        Ok(Self {
+            name: string("Name")?,
+            uuid: string("UUID")?,
+            team_id,
+            application_identifier,
+            entitlements,
+            expiration_date: plist
+                .get("ExpirationDate")
+                .and_then(Value::as_date)
+                .ok_or_else(|| invalid("missing ExpirationDate"))?,
+            devices,
+        })
+    }
+
+    /// Whether the profile's app ID, possibly a wildcard, covers `bundle_id`.
+    pub fn allows_bundle_id(&self, bundle_id: &str) -> bool {
+        let pattern = self
+            .application_identifier
+            .strip_prefix(&format!("{}.", self.team_id))
+            .unwrap_or(&self.application_identifier);
+        match pattern.strip_suffix('*') {
+            Some(prefix) => bundle_id.starts_with(prefix),
+            None => pattern == bundle_id,
+        }
+    }
+
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        SystemTime::from(self.expiration_date) <= now
+    }
+
+    /// The entitlements to sign `bundle_id` with: the ones every app gets,
+    /// with `get-task-allow` (debugging) as the profile allows.
+    pub fn entitlements_for(&self, bundle_id: &str) -> Dictionary {
+        let app_id = format!("{}.{}", self.team_id, bundle_id);
+        let mut entitlements = Dictionary::new();
+        entitlements.insert("application-identifier".to_string(), app_id.clone().into());
+        entitlements.insert(
+            "com.apple.developer.team-identifier".to_string(),
+            self.team_id.clone().into(),
+        );
+        entitlements.insert(
+            "get-task-allow".to_string(),
+            self.entitlements
+                .get("get-task-allow")
+                .and_then(Value::as_boolean)
+                .unwrap_or(false)
+                .into(),
+        );
+        entitlements.insert(
+            "keychain-access-groups".to_string(),
+            vec![app_id.into()].into(),
+        );
+        entitlements
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// What device builds are signed with and deployed to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningConfig {
+    /// A `codesign` identity: a certificate name or SHA-1 hash.
+    pub identity: String,
+    pub provisioning_profile: PathBuf,
+    pub device: Option<String>,
+}
+
+impl SigningConfig {
+    /// Reads the configuration from the metadata, letting the environment
+    /// override it. A relative profile path in the manifest is resolved
+    /// against `manifest_dir`. `env` looks up a variable; pass
+    /// `|key| std::env::var(key).ok()`.
+    pub fn new(
+        metadata: &IosMetadata,
+        manifest_dir: &Path,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, DeviceError> {
+        let non_empty = |key: &str| env(key).filter(|v| !v.trim().is_empty());
+        let identity =
+            non_empty(SIGNING_IDENTITY_ENV).or_else(|| metadata.signing_identity.clone());
+        let profile = non_empty(PROVISIONING_PROFILE_ENV)
+            .map(PathBuf::from)
+            .or_else(|| {
+                metadata
+                    .provisioning_profile
+                    .as_ref()
+                    .map(|path| manifest_dir.join(path))
+            });
+
+        match (identity, profile) {
+            (Some(identity), Some(provisioning_profile)) => Ok(Self {
+                identity,
+                provisioning_profile,
+                device: non_empty(DEVICE_ENV).or_else(|| metadata.device.clone()),
+            }),
+            (identity, _) => {
+                let (key, variable) = match identity {
+                    None => ("signing-identity", SIGNING_IDENTITY_ENV),
+                    Some(_) => ("provisioning-profile", PROVISIONING_PROFILE_ENV),
+                };
+                Err(DeviceError::Config(format!(
+                    "device builds need package.metadata.ios.{} or {}",
+                    key, variable
+                )))
+            }
+        }
+    }
+}
+
+/// Checks the profile against `bundle_id`, embeds it in `bundle_dir`, writes
+/// the entitlements to `work_dir` and signs the bundle. `now` is used to
+/// reject expired profiles.
+pub fn sign_bundle(
+    runner: &mut dyn CommandRunner,
+    config: &SigningConfig,
+    bundle_id: &str,
+    bundle_dir: &Path,
+    work_dir: &Path,
+    now: SystemTime,
+    report: &mut dyn FnMut(&Step),
+) -> Result<(), DeviceError> {
+    let bytes =
+        fs::read(&config.provisioning_profile).map_err(io_error(&config.provisioning_profile))?;
+    let profile = ProvisioningProfile::parse(&bytes)?;
+    if !profile.allows_bundle_id(bundle_id) {
+        return Err(DeviceError::Profile(format!(
+            "{:?} is for {}, not {}",
+            profile.name, profile.application_identifier, bundle_id
+        )));
+    }
+    if profile.is_expired(now) {
+        return Err(DeviceError::Profile(format!(
+            "{:?} expired on {}",
+            profile.name,
+            profile.expiration_date.to_xml_format()
+        )));
+    }
+    report(&Step {
+        name: "check profile",
+        detail: format!(
+            "{} ({}), team {}, expires {}",
+            profile.name,
+            profile.uuid,
+            profile.team_id,
+            profile.expiration_date.to_xml_format()
+        ),
+    });
+
+    let embedded = bundle_dir.join("embedded.mobileprovision");
+    fs::write(&embedded, &bytes).map_err(io_error(&embedded))?;
+    report(&Step {
+        name: "embed profile",
+        detail: embedded.display().to_string(),
+    });
+
+    let entitlements_path = work_dir.join("entitlements.plist");
+    let entitlements = to_xml(&profile.entitlements_for(bundle_id).into())
+        .map_err(|e| DeviceError::Profile(e.to_string()))?;
+    fs::create_dir_all(work_dir).map_err(io_error(work_dir))?;
+    fs::write(&entitlements_path, entitlements).map_err(io_error(&entitlements_path))?;
+    report(&Step {
+        name: "write entitlements",
+        detail: entitlements_path.display().to_string(),
+    });
+
+    run_checked(
+        runner,
+        "codesign",
+        &[
+            "--force",
+            "--sign",
+            &config.identity,
+            "--entitlements",
+            &entitlements_path.to_string_lossy(),
+            "--generate-entitlement-der",
+            "--timestamp=none",
+            &bundle_dir.to_string_lossy(),
+        ],
+    )?;
+    report(&Step {
+        name: "codesign",
+        detail: config.identity.clone(),
+    });
+    Ok(())
+}
+
+/// The tool used to talk to devices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceTool {
+    /// `xcrun devicectl`, part of Xcode 15 and newer.
+    Devicectl,
+    /// `ios-deploy`, installed separately.
+    IosDeploy,
+}
+
+impl DeviceTool {
+    /// `devicectl` if Xcode has it, otherwise `ios-deploy` if it is installed.
+    pub fn detect(runner: &mut dyn CommandRunner) -> Result<Self, DeviceError> {
+        if run_checked(runner, "xcrun", &["--find", "devicectl"]).is_ok() {
+            return Ok(DeviceTool::Devicectl);
+        }
+        if run_checked(runner, "ios-deploy", &["--version"]).is_ok() {
+            return Ok(DeviceTool::IosDeploy);
+        }
+        Err(DeviceError::Config(
+            "neither `xcrun devicectl` (Xcode 15+) nor ios-deploy is available".to_string(),
+        ))
+    }
+}
+
+/// Installs the signed bundle on `device` and launches it. `devicectl` needs
+/// a device; `ios-deploy` picks the first connected one without.
+pub fn install_and_launch(
+    runner: &mut dyn CommandRunner,
+    tool: DeviceTool,
+    device: Option<&str>,
+    bundle_dir: &Path,
+    bundle_id: &str,
+    report: &mut dyn FnMut(&Step),
+) -> Result<(), DeviceError> {
+    let bundle = bundle_dir.to_string_lossy();
+    match tool {
+        DeviceTool::Devicectl => {
+            let device = device.ok_or_else(|| {
+                DeviceError::Config(format!(
+                    "set {} or package.metadata.ios.device to a device from \
+                     `xcrun devicectl list devices`",
+                    DEVICE_ENV
+                ))
+            })?;
+            run_checked(
+                runner,
+                "xcrun",
+                &[
+                    "devicectl",
+                    "device",
+                    "install",
+                    "app",
+                    "--device",
+                    device,
+                    &bundle,
+                ],
+            )?;
+            report(&Step {
+                name: "install",
+                detail: format!("devicectl on {}", device),
+            });
+            run_checked(
+                runner,
+                "xcrun",
+                &[
+                    "devicectl",
+                    "device",
+                    "process",
+                    "launch",
+                    "--device",
+                    device,
+                    "--terminate-existing",
+                    bundle_id,
+                ],
+            )?;
+        }
+        DeviceTool::IosDeploy => {
+            let mut args = vec!["--bundle", &bundle, "--justlaunch"];
+            if let Some(device) = device {
+                args.extend(["--id", device]);
+            }
+            run_checked(runner, "ios-deploy", &args)?;
+            report(&Step {
+                name: "install",
+                detail: format!("ios-deploy on {}", device.unwrap_or("the first device")),
+            });
+        }
+    }
+    report(&Step {
+        name: "launch",
+        detail: bundle_id.to_string(),
+    });
+    Ok(())
+}
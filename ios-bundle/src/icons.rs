@@ -0,0 +1,173 @@
+//! App icons and the launch screen, generated from one source image.
+//!
+//! Asset catalogs can only be compiled by Xcode's `actool`, so icons go in
+//! the old way: loose PNGs at the top of the bundle named
+//! `<base>@<scale>x[~ipad].png`, listed by base name under `CFBundleIcons`.
+//! iOS picks the file matching the device's scale and idiom.
+//!
+//! The launch screen is a `UILaunchScreen` dictionary. Its background color
+//! would need an asset catalog too, so the color is drawn into an image big
+//! enough to cover the largest screen instead.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use plist::{Dictionary, Value};
+
+use crate::metadata::DeviceFamily;
+use crate::png::Image;
+
+/// One icon file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AppIcon {
+    /// Name listed in `CFBundleIconFiles`, e.g. `AppIcon60x60`.
+    pub base_name: &'static str,
+    pub scale: u32,
+    /// Width and height of the file.
+    pub pixels: u32,
+    pub family: DeviceFamily,
+}
+
+impl AppIcon {
+    /// File name inside the bundle, e.g. `AppIcon76x76@2x~ipad.png`.
+    pub fn file_name(&self) -> String {
+        let scale = match self.scale {
+            1 => String::new(),
+            scale => format!("@{}x", scale),
+        };
+        let idiom = match self.family {
+            DeviceFamily::IPhone => "",
+            DeviceFamily::IPad => "~ipad",
+        };
+        format!("{}{}{}.png", self.base_name, scale, idiom)
+    }
+}
+
+const fn icon(base_name: &'static str, scale: u32, pixels: u32, family: DeviceFamily) -> AppIcon {
+    AppIcon {
+        base_name,
+        scale,
+        pixels,
+        family,
+    }
+}
+
+/// Notification, settings, spotlight and home screen icons, the set Xcode
+/// asks for.
+const ICONS: &[AppIcon] = &[
+    icon("AppIcon20x20", 2, 40, DeviceFamily::IPhone),
+    icon("AppIcon20x20", 3, 60, DeviceFamily::IPhone),
+    icon("AppIcon29x29", 2, 58, DeviceFamily::IPhone),
+    icon("AppIcon29x29", 3, 87, DeviceFamily::IPhone),
+    icon("AppIcon40x40", 2, 80, DeviceFamily::IPhone),
+    icon("AppIcon40x40", 3, 120, DeviceFamily::IPhone),
+    icon("AppIcon60x60", 2, 120, DeviceFamily::IPhone),
+    icon("AppIcon60x60", 3, 180, DeviceFamily::IPhone),
+    icon("AppIcon20x20", 1, 20, DeviceFamily::IPad),
+    icon("AppIcon20x20", 2, 40, DeviceFamily::IPad),
+    icon("AppIcon29x29", 1, 29, DeviceFamily::IPad),
+    icon("AppIcon29x29", 2, 58, DeviceFamily::IPad),
+    icon("AppIcon40x40", 1, 40, DeviceFamily::IPad),
+    icon("AppIcon40x40", 2, 80, DeviceFamily::IPad),
+    icon("AppIcon76x76", 1, 76, DeviceFamily::IPad),
+    icon("AppIcon76x76", 2, 152, DeviceFamily::IPad),
+    icon("AppIcon83.5x83.5", 2, 167, DeviceFamily::IPad),
+];
+
+/// Source images smaller than this would have to be scaled up. 1024 is what
+/// the App Store asks for, and what Xcode's single-size icons use.
+pub const MINIMUM_ICON_SOURCE: u32 = 1024;
+
+/// Base name of the launch screen image, `LaunchBackground.png`.
+pub const LAUNCH_IMAGE: &str = "LaunchBackground";
+
+/// The launch image is shown centered at one pixel per point, so it has to
+/// be as large as the longest screen side: the 12.9" iPad Pro's 1366 points.
+const LAUNCH_IMAGE_SIZE: u32 = 1366;
+
+/// Every icon the given device families need.
+pub fn app_icons(families: &[DeviceFamily]) -> Vec<AppIcon> {
+    ICONS
+        .iter()
+        .filter(|icon| families.contains(&icon.family))
+        .copied()
+        .collect()
+}
+
+/// The `CFBundleIcons` and `CFBundleIcons~ipad` entries for the icons of
+/// [`app_icons`].
+pub fn icons_plist(families: &[DeviceFamily]) -> Dictionary {
+    let mut dict = Dictionary::new();
+    for (family, key) in [
+        (DeviceFamily::IPhone, "CFBundleIcons"),
+        (DeviceFamily::IPad, "CFBundleIcons~ipad"),
+    ] {
+        if !families.contains(&family) {
+            continue;
+        }
+        let mut names: Vec<Value> = Vec::new();
+        for icon in ICONS.iter().filter(|icon| icon.family == family) {
+            if !names.contains(&icon.base_name.into()) {
+                names.push(icon.base_name.into());
+            }
+        }
+        let mut primary = Dictionary::new();
+        primary.insert("CFBundleIconFiles".to_string(), names.into());
+        let mut icons = Dictionary::new();
+        icons.insert(
+            "CFBundlePrimaryIcon".to_string(),
+            Value::Dictionary(primary),
+        );
+        dict.insert(key.to_string(), Value::Dictionary(icons));
+    }
+    dict
+}
+
+/// The `UILaunchScreen` dictionary. Even an empty one matters: without a
+/// launch screen iOS runs the app letterboxed at an old iPhone's size.
+pub fn launch_screen_plist(background: Option<[u8; 3]>) -> Dictionary {
+    let mut dict = Dictionary::new();
+    if background.is_some() {
+        dict.insert("UIImageName".to_string(), LAUNCH_IMAGE.into());
+        dict.insert("UIImageRespectsSafeAreaInsets".to_string(), false.into());
+    }
+    dict
+}
+
+/// Checks that `source` can be scaled down to every icon.
+pub fn check_icon_source(source: &Image) -> Result<(), String> {
+    if source.width != source.height {
+        return Err(format!(
+            "icon must be square, not {}x{}",
+            source.width, source.height
+        ));
+    }
+    if source.width < MINIMUM_ICON_SOURCE {
+        return Err(format!(
+            "icon must be at least {0}x{0}, not {1}x{1}",
+            MINIMUM_ICON_SOURCE, source.width
+        ));
+    }
+    Ok(())
+}
+
+/// Scales `source` to each of `icons` and writes them into `dir`.
+pub fn write_icons(source: &Image, icons: &[AppIcon], dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    for icon in icons {
+        let scaled = source.resize(icon.pixels, icon.pixels);
+        fs::write(dir.join(icon.file_name()), scaled.encode())?;
+    }
+    Ok(())
+}
+
+/// The launch screen image in one color.
+pub fn launch_image(background: [u8; 3]) -> Image {
+    let [red, green, blue] = background;
+    Image::filled(
+        LAUNCH_IMAGE_SIZE,
+        LAUNCH_IMAGE_SIZE,
+        [red, green, blue, 255],
+    )
+}
@@ -0,0 +1,51 @@
+//! Helpers for turning the `rust_ios_hello` build into an iOS app bundle or
+//! an XCFramework, and for symbolicating its crash reports.
+//!
+//! Used by the `xtask` packaging tool, so everything here must build for the
+//! host and stay free of Bevy.
+
+mod bundle;
+mod crash;
+mod device;
+mod icons;
+mod macho;
+mod metadata;
+pub mod pattern;
+pub mod plist;
+mod png;
+mod runner;
+mod simctl;
+mod stamp;
+mod symbols;
+mod xcframework;
+
+pub use bundle::{assemble, BundleError, BundleSpec, Resource, Step};
+pub use crash::{symbolicate_report, BinaryImage, CrashFrame, CrashReport, CrashThread};
+pub use device::{
+    install_and_launch, sign_bundle, DeviceError, DeviceTool, ProvisioningProfile, SigningConfig,
+    DEVICE_ENV, PROVISIONING_PROFILE_ENV, SIGNING_IDENTITY_ENV,
+};
+pub use icons::{
+    app_icons, check_icon_source, icons_plist, launch_image, launch_screen_plist, write_icons,
+    AppIcon, LAUNCH_IMAGE, MINIMUM_ICON_SOURCE,
+};
+pub use metadata::{
+    DeviceFamily, IosMetadata, MetadataError, Orientation, DEFAULT_ASSETS, DEFAULT_MINIMUM_OS,
+};
+pub use plist::PlistError;
+pub use png::{Image, PngError};
+pub use runner::{CommandError, CommandOutput, CommandRunner, FakeRunner, SystemRunner};
+pub use simctl::{
+    deploy, deploy_requested, deploy_step, DeployReport, Deployment, DeviceList, DeviceQuery,
+    DeviceState, Runtime, SimDevice, Simctl, SimctlError, DEPLOY_ENV, SIMULATOR_ENV,
+    SIMULATOR_RUNTIME_ENV,
+};
+pub use stamp::{fingerprint, is_up_to_date, write_if_stale, write_stamp, StampOutcome};
+pub use symbols::{
+    archive_dsym, build_uuids, demangle, dsym_dwarf_files, find_or_extract_dsym, find_symbols,
+    ArchivedSymbols, BuildUuid, DebugInfo, Frame, SymbolError, Symbolicator,
+};
+pub use xcframework::{
+    create_xcframework, is_simulator_target, lipo, module_map, xcframework_info_plist, AppleTarget,
+    LibrarySlice, XcframeworkError, XcframeworkSpec,
+};
@@ -0,0 +1,145 @@
+//! The parts of Mach-O files that symbolication needs, read with `object`:
+//! the build UUID, where `__TEXT` is meant to be loaded, the symbol table
+//! and, in a dSYM, the `__DWARF` sections. Universal files are split into
+//! their architectures.
+
+use std::sync::Arc;
+
+use gimli::{EndianArcSlice, RunTimeEndian};
+use object::macho::{
+    CPU_SUBTYPE_ARM64E, CPU_SUBTYPE_MASK, CPU_TYPE_ARM64, CPU_TYPE_X86_64,
+    S_ATTR_PURE_INSTRUCTIONS, S_ATTR_SOME_INSTRUCTIONS,
+};
+use object::read::macho::{FatArch, MachHeader, MachOFatFile32, MachOFatFile64, MachOFile64};
+use object::{
+    Endianness, FileKind, Object, ObjectSection, ObjectSegment, ObjectSymbol, SectionFlags,
+};
+
+use crate::symbols::{BuildUuid, SymbolError};
+
+/// How `gimli` reads the DWARF copied out of a slice.
+pub(crate) type DwarfReader = EndianArcSlice<RunTimeEndian>;
+
+/// One architecture of a Mach-O file.
+pub(crate) struct Slice<'a> {
+    pub architecture: String,
+    pub uuid: Option<BuildUuid>,
+    /// Start and end of the `__TEXT` segment, as linked.
+    pub text: (u64, u64),
+    /// Start and end of each section holding code.
+    pub code: Vec<(u64, u64)>,
+    /// Defined symbols, sorted by address.
+    pub symbols: Vec<(u64, String)>,
+    file: MachOFile64<'a, Endianness>,
+}
+
+impl Slice<'_> {
+    /// The `__DWARF` sections, copied so they outlive the file's bytes.
+    /// Missing ones are empty.
+    pub fn dwarf(&self) -> Result<gimli::Dwarf<DwarfReader>, SymbolError> {
+        let endian = if self.file.is_little_endian() {
+            RunTimeEndian::Little
+        } else {
+            RunTimeEndian::Big
+        };
+        // `object` maps `.debug_info` to `__debug_info`, and knows Mach-O
+        // section names stop at 16 characters.
+        gimli::Dwarf::load(|id| {
+            let data = match self.file.section_by_name(id.name()) {
+                Some(section) => section.data().map_err(corrupt)?,
+                None => &[],
+            };
+            Ok(EndianArcSlice::new(Arc::from(data), endian))
+        })
+    }
+}
+
+fn corrupt(error: object::Error) -> SymbolError {
+    SymbolError::Corrupt(format!("Mach-O file: {}", error))
+}
+
+/// The name Apple's tools use for a CPU type, e.g. `arm64`.
+fn architecture_name(cpu_type: u32, cpu_subtype: u32) -> String {
+    match cpu_type {
+        CPU_TYPE_ARM64 if cpu_subtype & !CPU_SUBTYPE_MASK == CPU_SUBTYPE_ARM64E => {
+            "arm64e".to_string()
+        }
+        CPU_TYPE_ARM64 => "arm64".to_string(),
+        CPU_TYPE_X86_64 => "x86_64".to_string(),
+        other => format!("cpu {:#x}", other),
+    }
+}
+
+/// Every architecture in `bytes`, a thin or universal Mach-O file.
+pub(crate) fn slices(bytes: &[u8]) -> Result<Vec<Slice<'_>>, SymbolError> {
+    match FileKind::parse(bytes).map_err(|_| SymbolError::NotMachO)? {
+        FileKind::MachO64 => Ok(vec![slice(bytes)?]),
+        FileKind::MachO32 => Err(SymbolError::Unsupported("32-bit Mach-O files".to_string())),
+        FileKind::MachOFat32 => {
+            let fat = MachOFatFile32::parse(bytes).map_err(|_| SymbolError::NotMachO)?;
+            fat_slices(fat.arches(), bytes)
+        }
+        FileKind::MachOFat64 => {
+            let fat = MachOFatFile64::parse(bytes).map_err(|_| SymbolError::NotMachO)?;
+            fat_slices(fat.arches(), bytes)
+        }
+        _ => Err(SymbolError::NotMachO),
+    }
+}
+
+fn fat_slices<'a>(arches: &[impl FatArch], bytes: &'a [u8]) -> Result<Vec<Slice<'a>>, SymbolError> {
+    // Java class files share the magic number; their "count" is a version
+    // number far above any real number of architectures.
+    if arches.len() > 32 {
+        return Err(SymbolError::NotMachO);
+    }
+    arches
+        .iter()
+        .map(|arch| slice(arch.data(bytes).map_err(corrupt)?))
+        .collect()
+}
+
+fn slice(data: &[u8]) -> Result<Slice<'_>, SymbolError> {
+    let file = MachOFile64::<Endianness>::parse(data).map_err(corrupt)?;
+    let endian = file.endian();
+    let header = file.macho_header();
+
+    let text = file
+        .segments()
+        .find(|segment| segment.name() == Ok(Some("__TEXT")))
+        .map_or((0, 0), |segment| {
+            (segment.address(), segment.address() + segment.size())
+        });
+    let code = file
+        .sections()
+        .filter(|section| {
+            section.segment_name() == Ok(Some("__TEXT"))
+                && matches!(
+                    section.flags(),
+                    SectionFlags::MachO { flags }
+                        if flags & (S_ATTR_PURE_INSTRUCTIONS | S_ATTR_SOME_INSTRUCTIONS) != 0
+                )
+        })
+        .map(|section| (section.address(), section.address() + section.size()))
+        .collect();
+    let mut symbols = file
+        .symbols()
+        .filter(|symbol| symbol.section_index().is_some())
+        .map(|symbol| {
+            Ok((
+                symbol.address(),
+                symbol.name().map_err(corrupt)?.to_string(),
+            ))
+        })
+        .collect::<Result<Vec<_>, SymbolError>>()?;
+    symbols.sort();
+
+    Ok(Slice {
+        architecture: architecture_name(header.cputype(endian), header.cpusubtype(endian)),
+        uuid: file.mach_uuid().map_err(corrupt)?.map(BuildUuid),
+        text,
+        code,
+        symbols,
+        file,
+    })
+}
@@ -0,0 +1,470 @@
+//! The `[package.metadata.ios]` table of a Cargo manifest.
+//!
+//! ```toml
+//! [package.metadata.ios]
+//! bundle-id = "com.example.rustiostest"
+//! display-name = "Rust GUI"
+//! build-number = "1"
+//! orientations = ["portrait", "landscape-left"]
+//! device-family = ["iphone", "ipad"]
+//! minimum-os = "12.0"
+//! icon = "ios/AppIcon.png"
+//! launch-background = "#262626"
+//! assets = "assets"
+//! assets-exclude = ["*.psd", "drafts"]
+//! simulator = "iPhone 15 Pro"
+//! signing-identity = "Apple Development: Jane Doe (AB12CD34EF)"
+//! provisioning-profile = "signing/development.mobileprovision"
+//!
+//! [package.metadata.ios.info-plist]
+//! UIRequiredDeviceCapabilities = ["arm64"]
+//! ```
+//!
+//! Only `bundle-id` is required. Keys under `info-plist` are copied into the
+//! generated `Info.plist` as they are and win over the generated ones.
+
+use plist::{Dictionary, Value};
+use std::fmt;
+use std::path::Path;
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::bundle::Resource;
+use crate::icons::{icons_plist, launch_screen_plist};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Portrait,
+    PortraitUpsideDown,
+    LandscapeLeft,
+    LandscapeRight,
+}
+
+impl Orientation {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "portrait" => Some(Orientation::Portrait),
+            "portrait-upside-down" => Some(Orientation::PortraitUpsideDown),
+            "landscape-left" => Some(Orientation::LandscapeLeft),
+            "landscape-right" => Some(Orientation::LandscapeRight),
+            _ => None,
+        }
+    }
+
+    /// The `UIInterfaceOrientation` constant name.
+    pub fn plist_name(self) -> &'static str {
+        match self {
+            Orientation::Portrait => "UIInterfaceOrientationPortrait",
+            Orientation::PortraitUpsideDown => "UIInterfaceOrientationPortraitUpsideDown",
+            Orientation::LandscapeLeft => "UIInterfaceOrientationLandscapeLeft",
+            Orientation::LandscapeRight => "UIInterfaceOrientationLandscapeRight",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceFamily {
+    IPhone,
+    IPad,
+}
+
+impl DeviceFamily {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "iphone" => Some(DeviceFamily::IPhone),
+            "ipad" => Some(DeviceFamily::IPad),
+            _ => None,
+        }
+    }
+
+    /// The `UIDeviceFamily` number.
+    pub fn plist_value(self) -> i64 {
+        match self {
+            DeviceFamily::IPhone => 1,
+            DeviceFamily::IPad => 2,
+        }
+    }
+}
+
+/// Everything needed to write an app's `Info.plist`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IosMetadata {
+    pub bundle_id: String,
+    /// Name under the icon. Defaults to the package name.
+    pub display_name: String,
+    /// `CFBundleName`. Defaults to the package name.
+    pub bundle_name: String,
+    /// File name of the binary inside the bundle. Defaults to the package name.
+    pub executable: String,
+    /// `CFBundleShortVersionString`, the package version.
+    pub version: String,
+    /// `CFBundleVersion`. Defaults to the version.
+    pub build_number: String,
+    /// Defaults to portrait only.
+    pub orientations: Vec<Orientation>,
+    /// Defaults to iPhone only.
+    pub device_family: Vec<DeviceFamily>,
+    pub minimum_os: String,
+    /// Square PNG of at least 1024x1024 pixels the app icons are scaled
+    /// from, relative to the manifest.
+    pub icon: Option<String>,
+    /// Launch screen color. Without one the launch screen is the system
+    /// background.
+    pub launch_background: Option<[u8; 3]>,
+    /// Directory copied into the bundle as `assets/`, relative to the
+    /// manifest. Defaults to `assets`.
+    pub assets: String,
+    /// Patterns selecting the files of `assets` to bundle, see
+    /// [`pattern`](crate::pattern). Defaults to all of them.
+    pub assets_include: Vec<String>,
+    /// Patterns for files of `assets` to leave out.
+    pub assets_exclude: Vec<String>,
+    /// Simulator name or UDID to deploy to, see [`DeviceQuery`](crate::DeviceQuery).
+    pub simulator: Option<String>,
+    /// Simulator runtime to deploy to, e.g. `iOS 17.2`.
+    pub simulator_runtime: Option<String>,
+    /// `codesign` identity for device builds, see [`SigningConfig`](crate::SigningConfig).
+    pub signing_identity: Option<String>,
+    /// Path of the `.mobileprovision` to embed, relative to the manifest.
+    pub provisioning_profile: Option<String>,
+    /// Name, UDID or ECID of the device to deploy to.
+    pub device: Option<String>,
+    /// Additional `Info.plist` entries.
+    pub extra: Dictionary,
+}
+
+pub const DEFAULT_MINIMUM_OS: &str = "12.0";
+
+/// The asset directory, both in the package and inside the bundle. Bevy's
+/// `AssetPlugin` looks for `assets` by default.
+pub const DEFAULT_ASSETS: &str = "assets";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataError {
+    Toml(String),
+    Missing(&'static str),
+    /// `key` is the dotted path below `package.metadata.ios`.
+    Invalid {
+        key: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataError::Toml(message) => write!(f, "invalid Cargo.toml: {}", message),
+            MetadataError::Missing(key) => {
+                write!(f, "missing package.metadata.ios.{}", key)
+            }
+            MetadataError::Invalid { key, message } => {
+                write!(f, "package.metadata.ios.{}: {}", key, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MetadataError {}
+
+fn invalid(key: &str, message: impl Into<String>) -> MetadataError {
+    MetadataError::Invalid {
+        key: key.to_string(),
+        message: message.into(),
+    }
+}
+
+impl IosMetadata {
+    /// Reads the metadata from the text of a `Cargo.toml`. `package_name` and
+    /// `version` are what Cargo passes as `CARGO_PKG_NAME` and
+    /// `CARGO_PKG_VERSION`.
+    pub fn from_manifest(
+        manifest: &str,
+        package_name: &str,
+        version: &str,
+    ) -> Result<Self, MetadataError> {
+        let document: DocumentMut = manifest
+            .parse()
+            .map_err(|e: toml_edit::TomlError| MetadataError::Toml(e.to_string()))?;
+        let ios = document
+            .get("package")
+            .and_then(|package| package.get("metadata"))
+            .and_then(|metadata| metadata.get("ios"))
+            .and_then(Item::as_table_like)
+            .ok_or(MetadataError::Missing("bundle-id"))?;
+
+        let string = |key: &'static str| -> Result<Option<String>, MetadataError> {
+            match ios.get(key) {
+                None => Ok(None),
+                Some(item) => item
+                    .as_str()
+                    .map(|s| Some(s.to_string()))
+                    .ok_or_else(|| invalid(key, "expected a string")),
+            }
+        };
+        let names = |key: &'static str| -> Result<Option<Vec<String>>, MetadataError> {
+            let Some(item) = ios.get(key) else {
+                return Ok(None);
+            };
+            let array = item
+                .as_array()
+                .ok_or_else(|| invalid(key, "expected an array of strings"))?;
+            array
+                .iter()
+                .map(|value| {
+                    value
+                        .as_str()
+                        .map(str::to_string)
+                        .ok_or_else(|| invalid(key, "expected an array of strings"))
+                })
+                .collect::<Result<_, _>>()
+                .map(Some)
+        };
+
+        for (key, _) in ios.iter() {
+            if !KNOWN_KEYS.contains(&key) {
+                return Err(invalid(key, "unknown key"));
+            }
+        }
+
+        let bundle_id = string("bundle-id")?.ok_or(MetadataError::Missing("bundle-id"))?;
+        if !is_valid_bundle_id(&bundle_id) {
+            return Err(invalid(
+                "bundle-id",
+                format!(
+                    "{:?} may only contain letters, digits, '-' and '.'",
+                    bundle_id
+                ),
+            ));
+        }
+
+        let orientations = match names("orientations")? {
+            None => vec![Orientation::Portrait],
+            Some(names) => names
+                .iter()
+                .map(|name| {
+                    Orientation::parse(name)
+                        .ok_or_else(|| invalid("orientations", format!("unknown {:?}", name)))
+                })
+                .collect::<Result<_, _>>()?,
+        };
+        let device_family = match names("device-family")? {
+            None => vec![DeviceFamily::IPhone],
+            Some(names) => names
+                .iter()
+                .map(|name| {
+                    DeviceFamily::parse(name)
+                        .ok_or_else(|| invalid("device-family", format!("unknown {:?}", name)))
+                })
+                .collect::<Result<_, _>>()?,
+        };
+
+        let launch_background = match string("launch-background")? {
+            None => None,
+            Some(color) => Some(parse_color(&color).ok_or_else(|| {
+                invalid(
+                    "launch-background",
+                    format!("{:?} is not a \"#RRGGBB\" color", color),
+                )
+            })?),
+        };
+
+        let extra = match ios.get("info-plist") {
+            None => Dictionary::new(),
+            Some(item) => match item_to_plist("info-plist", item)? {
+                Value::Dictionary(dict) => dict,
+                _ => return Err(invalid("info-plist", "expected a table")),
+            },
+        };
+
+        Ok(Self {
+            bundle_id,
+            display_name: string("display-name")?.unwrap_or_else(|| package_name.to_string()),
+            bundle_name: string("bundle-name")?.unwrap_or_else(|| package_name.to_string()),
+            executable: string("executable")?.unwrap_or_else(|| package_name.to_string()),
+            version: version.to_string(),
+            build_number: string("build-number")?.unwrap_or_else(|| version.to_string()),
+            orientations,
+            device_family,
+            minimum_os: string("minimum-os")?.unwrap_or_else(|| DEFAULT_MINIMUM_OS.to_string()),
+            icon: string("icon")?,
+            launch_background,
+            assets: string("assets")?.unwrap_or_else(|| DEFAULT_ASSETS.to_string()),
+            assets_include: names("assets-include")?.unwrap_or_default(),
+            assets_exclude: names("assets-exclude")?.unwrap_or_default(),
+            simulator: string("simulator")?,
+            simulator_runtime: string("simulator-runtime")?,
+            signing_identity: string("signing-identity")?,
+            provisioning_profile: string("provisioning-profile")?,
+            device: string("device")?,
+            extra,
+        })
+    }
+
+    /// Like [`from_manifest`](Self::from_manifest), taking the package name
+    /// and version from the manifest itself. For tools running outside of a
+    /// Cargo build script.
+    pub fn from_package_manifest(manifest: &str) -> Result<Self, MetadataError> {
+        let document: DocumentMut = manifest
+            .parse()
+            .map_err(|e: toml_edit::TomlError| MetadataError::Toml(e.to_string()))?;
+        let field = |key: &'static str| {
+            document
+                .get("package")
+                .and_then(|package| package.get(key))
+                .and_then(Item::as_str)
+                .ok_or_else(|| MetadataError::Toml(format!("missing package.{}", key)))
+        };
+        Self::from_manifest(manifest, field("name")?, field("version")?)
+    }
+
+    /// The asset directory as a bundle resource. It always lands in
+    /// `assets/` inside the bundle, where the app looks for it.
+    pub fn assets_resource(&self, manifest_dir: &Path) -> Resource {
+        Resource {
+            source: manifest_dir.join(&self.assets),
+            destination: DEFAULT_ASSETS.into(),
+            include: self.assets_include.clone(),
+            exclude: self.assets_exclude.clone(),
+        }
+    }
+
+    /// The `Info.plist` dictionary for the app.
+    pub fn info_plist(&self) -> Value {
+        let mut dict = Dictionary::new();
+        let mut set = |key: &str, value: Value| {
+            dict.insert(key.to_string(), value);
+        };
+        set("CFBundleDevelopmentRegion", "en".into());
+        set("CFBundleExecutable", self.executable.as_str().into());
+        set("CFBundleIdentifier", self.bundle_id.as_str().into());
+        set("CFBundleInfoDictionaryVersion", "6.0".into());
+        set("CFBundleName", self.bundle_name.as_str().into());
+        set("CFBundleDisplayName", self.display_name.as_str().into());
+        set("CFBundlePackageType", "APPL".into());
+        set("CFBundleShortVersionString", self.version.as_str().into());
+        set("CFBundleVersion", self.build_number.as_str().into());
+        set(
+            "CFBundleSupportedPlatforms",
+            vec!["iPhoneOS".into(), "iPhoneSimulator".into()].into(),
+        );
+        set("LSRequiresIPhoneOS", true.into());
+        set("MinimumOSVersion", self.minimum_os.as_str().into());
+        set(
+            "UIDeviceFamily",
+            self.device_family
+                .iter()
+                .map(|family| family.plist_value().into())
+                .collect::<Vec<_>>()
+                .into(),
+        );
+        set(
+            "UISupportedInterfaceOrientations",
+            self.orientations
+                .iter()
+                .map(|orientation| orientation.plist_name().into())
+                .collect::<Vec<_>>()
+                .into(),
+        );
+        if self.icon.is_some() {
+            for (key, value) in icons_plist(&self.device_family) {
+                set(&key, value);
+            }
+        }
+        set(
+            "UILaunchScreen",
+            Value::Dictionary(launch_screen_plist(self.launch_background)),
+        );
+        for (key, value) in &self.extra {
+            set(key, value.clone());
+        }
+        Value::Dictionary(dict)
+    }
+}
+
+const KNOWN_KEYS: &[&str] = &[
+    "bundle-id",
+    "display-name",
+    "bundle-name",
+    "executable",
+    "build-number",
+    "orientations",
+    "device-family",
+    "minimum-os",
+    "icon",
+    "launch-background",
+    "assets",
+    "assets-include",
+    "assets-exclude",
+    "simulator",
+    "simulator-runtime",
+    "signing-identity",
+    "provisioning-profile",
+    "device",
+    "info-plist",
+];
+
+/// Parses `#RRGGBB`.
+fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+fn is_valid_bundle_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.split('.').all(|part| {
+            !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+fn item_to_plist(key: &str, item: &Item) -> Result<Value, MetadataError> {
+    match item {
+        Item::Value(value) => value_to_plist(key, value),
+        Item::Table(table) => table_to_plist(key, table),
+        Item::ArrayOfTables(tables) => tables
+            .iter()
+            .map(|table| table_to_plist(key, table))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        Item::None => Err(invalid(key, "missing value")),
+    }
+}
+
+fn table_to_plist(key: &str, table: &Table) -> Result<Value, MetadataError> {
+    table
+        .iter()
+        .map(|(child, item)| {
+            item_to_plist(&format!("{}.{}", key, child), item).map(|v| (child.to_string(), v))
+        })
+        .collect::<Result<Dictionary, _>>()
+        .map(Value::Dictionary)
+}
+
+fn value_to_plist(key: &str, value: &toml_edit::Value) -> Result<Value, MetadataError> {
+    use toml_edit::Value as Toml;
+
+    Ok(match value {
+        Toml::String(s) => Value::String(s.value().clone()),
+        Toml::Integer(i) => Value::Integer((*i.value()).into()),
+        Toml::Float(f) => Value::Real(*f.value()),
+        Toml::Boolean(b) => Value::Boolean(*b.value()),
+        Toml::Array(array) => Value::Array(
+            array
+                .iter()
+                .map(|value| value_to_plist(key, value))
+                .collect::<Result<_, _>>()?,
+        ),
+        Toml::InlineTable(table) => Value::Dictionary(
+            table
+                .iter()
+                .map(|(child, value)| {
+                    value_to_plist(&format!("{}.{}", key, child), value)
+                        .map(|v| (child.to_string(), v))
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        Toml::Datetime(_) => return Err(invalid(key, "dates are not supported")),
+    })
+}
@@ -0,0 +1,48 @@
+//! Include and exclude patterns for resources copied into the bundle.
+//!
+//! Patterns are matched against paths relative to the resource, with `/`
+//! between components. `*` matches within one component, `?` matches one
+//! character and `**` matches any number of components. As in `.gitignore`,
+//! a pattern without a `/` matches a file or directory name at any depth,
+//! and a pattern matching a directory matches everything below it.
+
+/// Whether `pattern` matches `path` or one of its parent directories.
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_start_matches('/').trim_end_matches('/');
+    let pattern: Vec<&str> = if pattern.contains('/') {
+        pattern.split('/').collect()
+    } else {
+        vec!["**", pattern]
+    };
+    let path: Vec<&str> = path.split('/').collect();
+    (1..=path.len()).any(|end| components_match(&pattern, &path[..end]))
+}
+
+/// Whether a file at `path` is selected: matched by one of `include` (or
+/// `include` is empty) and by none of `exclude`.
+pub fn selected(path: &str, include: &[String], exclude: &[String]) -> bool {
+    (include.is_empty() || include.iter().any(|pattern| matches(pattern, path)))
+        && !exclude.iter().any(|pattern| matches(pattern, path))
+}
+
+fn components_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| components_match(rest, &path[skip..])),
+        Some((first, rest)) => match path.split_first() {
+            Some((name, path)) => {
+                name_matches(first.as_bytes(), name.as_bytes()) && components_match(rest, path)
+            }
+            None => false,
+        },
+    }
+}
+
+fn name_matches(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| name_matches(rest, &name[skip..])),
+        Some((b'?', rest)) => !name.is_empty() && name_matches(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && name_matches(rest, &name[1..]),
+    }
+}
@@ -0,0 +1,77 @@
+//! XML property lists.
+//!
+//! The rest of the crate builds [`plist::Value`] trees directly. [`to_xml`]
+//! writes them the way Xcode does, with dictionary keys sorted, after
+//! checking they hold nothing XML can't carry; [`from_xml`] reads them.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlistError {
+    /// XML 1.0 cannot carry most control characters, escaped or not.
+    UnrepresentableCharacter(char),
+    /// A real that is NaN or infinite.
+    UnrepresentableReal(f64),
+    /// Input to [`from_xml`] that is not a valid XML plist.
+    Syntax(String),
+}
+
+impl fmt::Display for PlistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlistError::UnrepresentableCharacter(c) => {
+                write!(f, "character {:?} cannot be stored in a plist", c)
+            }
+            PlistError::UnrepresentableReal(r) => {
+                write!(f, "real {} cannot be stored in a plist", r)
+            }
+            PlistError::Syntax(message) => write!(f, "invalid plist: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for PlistError {}
+
+/// Serializes `value` as a complete XML plist document.
+pub fn to_xml(value: &plist::Value) -> Result<String, PlistError> {
+    let mut value = value.clone();
+    prepare(&mut value)?;
+    let mut out = Vec::new();
+    value
+        .to_writer_xml(&mut out)
+        .map_err(|e| PlistError::Syntax(e.to_string()))?;
+    out.push(b'\n');
+    Ok(String::from_utf8(out).expect("the plist crate writes UTF-8"))
+}
+
+/// Parses an XML plist document.
+pub fn from_xml(source: &str) -> Result<plist::Value, PlistError> {
+    plist::Value::from_reader_xml(source.as_bytes()).map_err(|e| PlistError::Syntax(e.to_string()))
+}
+
+/// Sorts dictionary keys like Xcode and rejects what the `plist` crate
+/// would write as-is, producing a document no XML parser accepts.
+fn prepare(value: &mut plist::Value) -> Result<(), PlistError> {
+    match value {
+        plist::Value::String(s) => checked(s),
+        plist::Value::Real(r) if !r.is_finite() => Err(PlistError::UnrepresentableReal(*r)),
+        plist::Value::Array(values) => values.iter_mut().try_for_each(prepare),
+        plist::Value::Dictionary(dict) => {
+            dict.sort_keys();
+            dict.iter_mut().try_for_each(|(key, value)| {
+                checked(key)?;
+                prepare(value)
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+fn checked(text: &str) -> Result<(), PlistError> {
+    match text.chars().find(|&c| {
+        (c < '\u{20}' && !matches!(c, '\t' | '\n' | '\r')) || c == '\u{FFFE}' || c == '\u{FFFF}'
+    }) {
+        Some(c) => Err(PlistError::UnrepresentableCharacter(c)),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,229 @@
+//! Just enough PNG to turn one icon into many.
+//!
+//! Decoding and encoding go through the `png` crate: any PNG it reads comes
+//! out as 8-bit RGBA, and images are written as 8-bit RGB or RGBA. Resizing
+//! is an area average over premultiplied pixels, which is what downscaling
+//! an icon needs and runs the same on Linux as on macOS.
+
+use std::fmt;
+use std::io::Cursor;
+
+use png::{BitDepth, ColorType, DecodingError, Transformations};
+
+const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// An 8-bit RGBA image, rows top to bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// `width * height * 4` bytes, not premultiplied.
+    pub rgba: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PngError {
+    NotPng,
+    /// A chunk or the image data is cut short or inconsistent.
+    Corrupt(String),
+    Unsupported(String),
+}
+
+impl fmt::Display for PngError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PngError::NotPng => write!(f, "not a PNG file"),
+            PngError::Corrupt(message) => write!(f, "corrupt PNG: {}", message),
+            PngError::Unsupported(message) => write!(f, "unsupported PNG: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for PngError {}
+
+impl From<DecodingError> for PngError {
+    fn from(error: DecodingError) -> Self {
+        match error {
+            DecodingError::LimitsExceeded => {
+                PngError::Unsupported("image is too large".to_string())
+            }
+            other => PngError::Corrupt(other.to_string()),
+        }
+    }
+}
+
+impl Image {
+    /// An image filled with one color.
+    pub fn filled(width: u32, height: u32, rgba: [u8; 4]) -> Self {
+        Self {
+            width,
+            height,
+            rgba: rgba.repeat(width as usize * height as usize),
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        [
+            self.rgba[i],
+            self.rgba[i + 1],
+            self.rgba[i + 2],
+            self.rgba[i + 3],
+        ]
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        self.rgba.chunks_exact(4).all(|pixel| pixel[3] == 255)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, PngError> {
+        if !bytes.starts_with(SIGNATURE) {
+            return Err(PngError::NotPng);
+        }
+        let mut decoder = png::Decoder::new(Cursor::new(bytes));
+        // Palettes, transparency and low bit depths expand to 8-bit gray or
+        // color, always with alpha.
+        decoder
+            .set_transformations(Transformations::normalize_to_color8() | Transformations::ALPHA);
+        let mut reader = decoder.read_info()?;
+        let size = reader
+            .output_buffer_size()
+            .ok_or_else(|| PngError::Unsupported("image is too large".to_string()))?;
+        let mut buffer = vec![0; size];
+        let info = reader.next_frame(&mut buffer)?;
+        buffer.truncate(info.buffer_size());
+
+        let rgba = match info.color_type {
+            ColorType::Rgba => buffer,
+            ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+                .collect(),
+            other => {
+                return Err(PngError::Unsupported(format!(
+                    "{:?} output from the decoder",
+                    other
+                )))
+            }
+        };
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            rgba,
+        })
+    }
+
+    /// Encodes as 8-bit RGBA, or RGB when every pixel is opaque.
+    pub fn encode(&self) -> Vec<u8> {
+        let opaque = self.is_opaque();
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+        encoder.set_color(if opaque {
+            ColorType::Rgb
+        } else {
+            ColorType::Rgba
+        });
+        encoder.set_depth(BitDepth::Eight);
+        let mut writer = encoder
+            .write_header()
+            .expect("an in-memory PNG header can be written");
+        let data = if opaque {
+            self.rgba
+                .chunks_exact(4)
+                .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+                .collect()
+        } else {
+            self.rgba.clone()
+        };
+        writer
+            .write_image_data(&data)
+            .expect("the pixels match the header");
+        writer.finish().expect("an in-memory PNG can be finished");
+        png
+    }
+
+    /// Scales to `width` x `height`. Each output pixel is the average of the
+    /// source area it covers, weighted by alpha so transparent edges don't
+    /// darken.
+    pub fn resize(&self, width: u32, height: u32) -> Self {
+        let premultiplied: Vec<f32> = self
+            .rgba
+            .chunks_exact(4)
+            .flat_map(|pixel| {
+                let alpha = f32::from(pixel[3]) / 255.0;
+                [
+                    f32::from(pixel[0]) * alpha,
+                    f32::from(pixel[1]) * alpha,
+                    f32::from(pixel[2]) * alpha,
+                    f32::from(pixel[3]),
+                ]
+            })
+            .collect();
+
+        let columns = weights(self.width, width);
+        let mut horizontal = vec![0.0; width as usize * self.height as usize * 4];
+        for y in 0..self.height as usize {
+            let source = &premultiplied[y * self.width as usize * 4..];
+            for (x, taps) in columns.iter().enumerate() {
+                let out = (y * width as usize + x) * 4;
+                for &(from, weight) in taps {
+                    for c in 0..4 {
+                        horizontal[out + c] += source[from * 4 + c] * weight;
+                    }
+                }
+            }
+        }
+
+        let rows = weights(self.height, height);
+        let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+        for taps in &rows {
+            for x in 0..width as usize {
+                let mut sum = [0.0f32; 4];
+                for &(from, weight) in taps {
+                    let at = (from * width as usize + x) * 4;
+                    for c in 0..4 {
+                        sum[c] += horizontal[at + c] * weight;
+                    }
+                }
+                let alpha = sum[3];
+                let unpremultiply = if alpha > 0.0 { 255.0 / alpha } else { 0.0 };
+                for value in &sum[..3] {
+                    rgba.push((value * unpremultiply).round().clamp(0.0, 255.0) as u8);
+                }
+                rgba.push(alpha.round().clamp(0.0, 255.0) as u8);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            rgba,
+        }
+    }
+}
+
+/// For each of the `to` output positions, the source positions it covers and
+/// how much of each, summing to one.
+fn weights(from: u32, to: u32) -> Vec<Vec<(usize, f32)>> {
+    let scale = f64::from(from) / f64::from(to);
+    (0..to)
+        .map(|i| {
+            let start = f64::from(i) * scale;
+            let end = start + scale;
+            // When enlarging, the covered area is narrower than one source
+            // pixel; take the one its center falls in.
+            if scale < 1.0 {
+                let center = ((start + end) / 2.0) as usize;
+                return vec![(center.min(from as usize - 1), 1.0)];
+            }
+            let taps: Vec<(usize, f32)> = (start.floor() as usize
+                ..(end.ceil() as usize).min(from as usize))
+                .filter_map(|s| {
+                    let covered = end.min(s as f64 + 1.0) - start.max(s as f64);
+                    (covered > 1e-9).then_some((s, (covered / scale) as f32))
+                })
+                .collect();
+            taps
+        })
+        .collect()
+}
@@ -0,0 +1,165 @@
+//! Running external tools such as `xcrun`.
+//!
+//! Everything that shells out goes through [`CommandRunner`], so the argument
+//! construction around it can be tested on machines without Xcode by
+//! swapping in a [`FakeRunner`].
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// What a finished command printed and whether it succeeded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    pub fn success(stdout: impl Into<String>) -> Self {
+        Self {
+            success: true,
+            stdout: stdout.into(),
+            stderr: String::new(),
+        }
+    }
+
+    pub fn failure(stderr: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            stdout: String::new(),
+            stderr: stderr.into(),
+        }
+    }
+}
+
+pub trait CommandRunner {
+    /// Runs `program` to completion. Errors only if it could not be started.
+    fn run(&mut self, program: &str, args: &[&str]) -> io::Result<CommandOutput>;
+}
+
+/// Why a step that shells out failed: the tool, or a file it reads or
+/// writes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    Io {
+        path: PathBuf,
+        message: String,
+    },
+    /// The tool could not be started.
+    Spawn {
+        program: String,
+        message: String,
+    },
+    Failed {
+        command: String,
+        stderr: String,
+    },
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Io { path, message } => write!(f, "{}: {}", path.display(), message),
+            CommandError::Spawn { program, message } => {
+                write!(f, "cannot run {}: {}", program, message)
+            }
+            CommandError::Failed { command, stderr } => {
+                write!(f, "`{}` failed: {}", command, stderr.trim())
+            }
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// Maps an I/O error on `path`; `?` turns it into the caller's error.
+pub(crate) fn io_error(path: &Path) -> impl FnOnce(io::Error) -> CommandError {
+    let path = path.to_path_buf();
+    move |e| CommandError::Io {
+        path,
+        message: e.to_string(),
+    }
+}
+
+/// Runs `program` and returns what it printed, or why it couldn't be run or
+/// didn't succeed.
+pub(crate) fn run_checked(
+    runner: &mut dyn CommandRunner,
+    program: &str,
+    args: &[&str],
+) -> Result<String, CommandError> {
+    let output = runner.run(program, args).map_err(|e| CommandError::Spawn {
+        program: program.to_string(),
+        message: e.to_string(),
+    })?;
+    if output.success {
+        Ok(output.stdout)
+    } else {
+        Err(CommandError::Failed {
+            command: format!("{} {}", program, args.join(" ")),
+            stderr: output.stderr,
+        })
+    }
+}
+
+/// Runs commands for real.
+#[derive(Debug, Default)]
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&mut self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let output = Command::new(program).args(args).output()?;
+        Ok(CommandOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+}
+
+/// Records every command instead of running it and answers with canned
+/// output. Commands without a canned answer succeed with no output.
+#[derive(Debug, Default)]
+pub struct FakeRunner {
+    calls: Vec<Vec<String>>,
+    responses: Vec<(Vec<String>, CommandOutput)>,
+}
+
+impl FakeRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers commands starting with `prefix` (program first) with `output`.
+    /// The earliest matching prefix wins.
+    pub fn respond(&mut self, prefix: &[&str], output: CommandOutput) -> &mut Self {
+        self.responses
+            .push((prefix.iter().map(|s| s.to_string()).collect(), output));
+        self
+    }
+
+    /// Every command run so far, program first.
+    pub fn calls(&self) -> &[Vec<String>] {
+        &self.calls
+    }
+}
+
+impl CommandRunner for FakeRunner {
+    fn run(&mut self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let call: Vec<String> = std::iter::once(program)
+            .chain(args.iter().copied())
+            .map(str::to_string)
+            .collect();
+        let output = self
+            .responses
+            .iter()
+            .find(|(prefix, _)| call.starts_with(prefix))
+            .map(|(_, output)| output.clone())
+            .unwrap_or_else(|| CommandOutput::success(""));
+        self.calls.push(call);
+        Ok(output)
+    }
+}
@@ -0,0 +1,399 @@
+//! Finding, booting and deploying to iOS simulators with `xcrun simctl`.
+//!
+//! The device to use is read from `[package.metadata.ios]`:
+//!
+//! ```toml
+//! simulator = "iPhone 15 Pro"      # a device name or UDID
+//! simulator-runtime = "iOS 17.2"   # optional
+//! ```
+//!
+//! and can be overridden with the `IOS_SIMULATOR` and `IOS_SIMULATOR_RUNTIME`
+//! environment variables. Without either, a booted simulator is preferred,
+//! then an iPhone on the newest runtime.
+
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::metadata::IosMetadata;
+use crate::runner::{run_checked, CommandError, CommandRunner};
+use crate::xcframework::is_simulator_target;
+
+pub const SIMULATOR_ENV: &str = "IOS_SIMULATOR";
+pub const SIMULATOR_RUNTIME_ENV: &str = "IOS_SIMULATOR_RUNTIME";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceState {
+    Booted,
+    Shutdown,
+    /// Transitional states such as `Booting` or `Shutting Down`.
+    Other(String),
+}
+
+impl From<String> for DeviceState {
+    fn from(state: String) -> Self {
+        match state.as_str() {
+            "Booted" => DeviceState::Booted,
+            "Shutdown" => DeviceState::Shutdown,
+            _ => DeviceState::Other(state),
+        }
+    }
+}
+
+/// A simulator runtime, e.g. `com.apple.CoreSimulator.SimRuntime.iOS-17-2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Runtime {
+    pub identifier: String,
+}
+
+impl Runtime {
+    /// The last part of the identifier, e.g. `iOS-17-2`. Xcode 10 and older
+    /// use names like `iOS 12.1` as identifiers, which are returned as is.
+    fn short(&self) -> &str {
+        self.identifier
+            .strip_prefix("com.apple.CoreSimulator.SimRuntime.")
+            .unwrap_or(&self.identifier)
+    }
+
+    /// The platform, e.g. `iOS` or `watchOS`.
+    pub fn platform(&self) -> &str {
+        self.short().split(['-', ' ']).next().unwrap_or_default()
+    }
+
+    /// The version numbers, e.g. `[17, 2]`.
+    pub fn version(&self) -> Vec<u32> {
+        let short = self.short();
+        short[self.platform().len()..]
+            .split(['-', '.', ' '])
+            .filter_map(|part| part.parse().ok())
+            .collect()
+    }
+
+    /// How Xcode shows the runtime, e.g. `iOS 17.2`.
+    pub fn name(&self) -> String {
+        let version: Vec<String> = self.version().iter().map(u32::to_string).collect();
+        format!("{} {}", self.platform(), version.join("."))
+    }
+
+    /// Whether `query` names this runtime by identifier, short identifier or
+    /// display name, ignoring case.
+    pub fn matches(&self, query: &str) -> bool {
+        [self.identifier.as_str(), self.short(), &self.name()]
+            .iter()
+            .any(|candidate| candidate.eq_ignore_ascii_case(query))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimDevice {
+    pub name: String,
+    pub udid: String,
+    pub runtime: Runtime,
+    pub state: DeviceState,
+    /// False if the device's runtime is missing or it is otherwise unusable.
+    pub is_available: bool,
+}
+
+/// The output of `xcrun simctl list devices --json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceList {
+    pub devices: Vec<SimDevice>,
+}
+
+#[derive(Deserialize)]
+struct RawList {
+    devices: BTreeMap<String, Vec<RawDevice>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawDevice {
+    name: String,
+    udid: String,
+    state: String,
+    /// Xcode 10.1 and newer.
+    is_available: Option<bool>,
+    /// Xcode 10 and older: `(available)` or `(unavailable, <reason>)`.
+    availability: Option<String>,
+}
+
+impl DeviceList {
+    pub fn parse(json: &str) -> Result<Self, SimctlError> {
+        let raw: RawList =
+            serde_json::from_str(json).map_err(|e| SimctlError::Json(e.to_string()))?;
+        let devices = raw
+            .devices
+            .into_iter()
+            .flat_map(|(runtime, devices)| {
+                devices.into_iter().map(move |device| SimDevice {
+                    is_available: device
+                        .is_available
+                        .or_else(|| device.availability.map(|a| a == "(available)"))
+                        .unwrap_or(true),
+                    name: device.name,
+                    udid: device.udid,
+                    runtime: Runtime {
+                        identifier: runtime.clone(),
+                    },
+                    state: device.state.into(),
+                })
+            })
+            .collect();
+        Ok(Self { devices })
+    }
+}
+
+/// Which simulator to deploy to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceQuery {
+    /// A device name or UDID.
+    pub device: Option<String>,
+    /// A runtime identifier or name such as `iOS 17.2`.
+    pub runtime: Option<String>,
+}
+
+impl DeviceQuery {
+    /// Reads the query from the metadata, letting the environment variables
+    /// named by [`SIMULATOR_ENV`] and [`SIMULATOR_RUNTIME_ENV`] override it.
+    /// `env` looks up a variable; pass `|key| std::env::var(key).ok()`.
+    pub fn new(metadata: &IosMetadata, env: impl Fn(&str) -> Option<String>) -> Self {
+        let non_empty = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+        Self {
+            device: non_empty(env(SIMULATOR_ENV)).or_else(|| metadata.simulator.clone()),
+            runtime: non_empty(env(SIMULATOR_RUNTIME_ENV))
+                .or_else(|| metadata.simulator_runtime.clone()),
+        }
+    }
+
+    /// Picks the device to use out of `list`. Only available iOS simulators
+    /// are considered. Among several matches, a booted one and then the
+    /// newest runtime win.
+    pub fn select<'a>(&self, list: &'a DeviceList) -> Result<&'a SimDevice, SimctlError> {
+        let mut candidates: Vec<&SimDevice> = list
+            .devices
+            .iter()
+            .filter(|device| device.is_available && device.runtime.platform() == "iOS")
+            .filter(|device| match &self.runtime {
+                Some(runtime) => device.runtime.matches(runtime),
+                None => true,
+            })
+            .collect();
+
+        match &self.device {
+            Some(query) => candidates
+                .retain(|device| device.udid.eq_ignore_ascii_case(query) || device.name == *query),
+            None => {
+                if !candidates.iter().any(|d| d.state == DeviceState::Booted) {
+                    let iphones: Vec<_> = candidates
+                        .iter()
+                        .copied()
+                        .filter(|device| device.name.starts_with("iPhone"))
+                        .collect();
+                    if !iphones.is_empty() {
+                        candidates = iphones;
+                    }
+                }
+            }
+        }
+
+        candidates.sort_by_key(|device| {
+            (
+                Reverse(device.state == DeviceState::Booted),
+                Reverse(device.runtime.version()),
+            )
+        });
+        candidates
+            .first()
+            .copied()
+            .ok_or_else(|| SimctlError::NoDevice(self.to_string()))
+    }
+}
+
+impl fmt::Display for DeviceQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.device, &self.runtime) {
+            (Some(device), Some(runtime)) => write!(f, "{:?} on {}", device, runtime),
+            (Some(device), None) => write!(f, "{:?}", device),
+            (None, Some(runtime)) => write!(f, "any iOS simulator on {}", runtime),
+            (None, None) => write!(f, "any iOS simulator"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimctlError {
+    Command(CommandError),
+    Json(String),
+    /// No simulator matches the query, described in the message.
+    NoDevice(String),
+}
+
+impl fmt::Display for SimctlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimctlError::Command(error) => write!(f, "{}", error),
+            SimctlError::Json(message) => write!(f, "unexpected simctl output: {}", message),
+            SimctlError::NoDevice(query) => write!(
+                f,
+                "no available simulator matches {}; see `xcrun simctl list devices`",
+                query
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SimctlError {}
+
+impl From<CommandError> for SimctlError {
+    fn from(error: CommandError) -> Self {
+        SimctlError::Command(error)
+    }
+}
+
+/// `xcrun simctl`, run through a [`CommandRunner`].
+pub struct Simctl<'r> {
+    runner: &'r mut dyn CommandRunner,
+}
+
+impl<'r> Simctl<'r> {
+    pub fn new(runner: &'r mut dyn CommandRunner) -> Self {
+        Self { runner }
+    }
+
+    fn run(&mut self, args: &[&str]) -> Result<String, SimctlError> {
+        let mut full = vec!["simctl"];
+        full.extend_from_slice(args);
+        Ok(run_checked(self.runner, "xcrun", &full)?)
+    }
+
+    pub fn list_devices(&mut self) -> Result<DeviceList, SimctlError> {
+        DeviceList::parse(&self.run(&["list", "devices", "--json"])?)
+    }
+
+    /// Boots the device and waits until it has finished booting.
+    pub fn boot(&mut self, udid: &str) -> Result<(), SimctlError> {
+        self.run(&["bootstatus", udid, "-b"]).map(drop)
+    }
+
+    pub fn install(&mut self, udid: &str, app: &Path) -> Result<(), SimctlError> {
+        let app = app.to_string_lossy();
+        self.run(&["install", udid, &app]).map(drop)
+    }
+
+    pub fn launch(&mut self, udid: &str, bundle_id: &str) -> Result<(), SimctlError> {
+        self.run(&["launch", udid, bundle_id]).map(drop)
+    }
+
+    /// Brings the Simulator app to the front showing `udid`. Best effort.
+    pub fn show(&mut self, udid: &str) {
+        let _ = self.runner.run(
+            "open",
+            &["-a", "Simulator", "--args", "-CurrentDeviceUDID", udid],
+        );
+    }
+}
+
+/// Where [`deploy`] installed the app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deployment {
+    pub device: SimDevice,
+    /// Whether the device had to be booted first.
+    pub booted: bool,
+}
+
+/// Installs and launches the bundle at `app` on the device selected by
+/// `query`, booting it if needed.
+pub fn deploy(
+    simctl: &mut Simctl,
+    query: &DeviceQuery,
+    app: &Path,
+    bundle_id: &str,
+) -> Result<Deployment, SimctlError> {
+    let list = simctl.list_devices()?;
+    let device = query.select(&list)?.clone();
+    let booted = device.state != DeviceState::Booted;
+    if booted {
+        simctl.boot(&device.udid)?;
+    }
+    simctl.install(&device.udid, app)?;
+    simctl.launch(&device.udid, bundle_id)?;
+    simctl.show(&device.udid);
+    Ok(Deployment { device, booted })
+}
+
+/// Set to `1` to deploy after packaging, like `cargo xtask bundle --deploy`.
+pub const DEPLOY_ENV: &str = "IOS_DEPLOY";
+
+/// Whether the variable named by [`DEPLOY_ENV`] is `1`, `true` or `yes`.
+pub fn deploy_requested(env: impl Fn(&str) -> Option<String>) -> bool {
+    env(DEPLOY_ENV).is_some_and(|value| {
+        matches!(
+            value.trim().to_ascii_lowercase().as_str(),
+            "1" | "true" | "yes"
+        )
+    })
+}
+
+/// What the deploy step did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeployReport {
+    /// Nothing was run, for the given reason.
+    Skipped(String),
+    Deployed(Deployment),
+    Failed(SimctlError),
+}
+
+impl DeployReport {
+    pub fn is_failure(&self) -> bool {
+        matches!(self, DeployReport::Failed(_))
+    }
+}
+
+impl fmt::Display for DeployReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeployReport::Skipped(reason) => write!(f, "skipped ({})", reason),
+            DeployReport::Deployed(Deployment { device, booted }) => {
+                write!(
+                    f,
+                    "launched on {} ({}, {})",
+                    device.name,
+                    device.runtime.name(),
+                    device.udid
+                )?;
+                if *booted {
+                    write!(f, ", booted first")?;
+                }
+                Ok(())
+            }
+            DeployReport::Failed(error) => write!(f, "failed: {}", error),
+        }
+    }
+}
+
+/// Runs [`deploy`] if `enabled` and `target` is a simulator target. When it
+/// is skipped, `runner` is never called.
+pub fn deploy_step(
+    enabled: bool,
+    target: &str,
+    runner: &mut dyn CommandRunner,
+    query: &DeviceQuery,
+    app: &Path,
+    bundle_id: &str,
+) -> DeployReport {
+    if !enabled {
+        return DeployReport::Skipped(format!(
+            "pass --deploy or set {}=1 to install on a simulator",
+            DEPLOY_ENV
+        ));
+    }
+    if !is_simulator_target(target) {
+        return DeployReport::Skipped(format!("{} is not a simulator target", target));
+    }
+    match deploy(&mut Simctl::new(runner), query, app, bundle_id) {
+        Ok(deployment) => DeployReport::Deployed(deployment),
+        Err(error) => DeployReport::Failed(error),
+    }
+}
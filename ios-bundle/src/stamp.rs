@@ -0,0 +1,72 @@
+//! Rewriting generated files only when what they are generated from changed.
+//!
+//! Next to each output a stamp file records a hash of the inputs that
+//! produced it. Build scripts run on every relevant change, but the output is
+//! only rewritten when the hash differs or the output is gone, so version
+//! bumps are picked up while unchanged bundles keep their timestamps.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StampOutcome {
+    Created,
+    /// The output existed but was produced from different inputs.
+    Replaced,
+    UpToDate,
+}
+
+/// FNV-1a, chosen because its output never changes between Rust releases.
+pub fn fingerprint(inputs: &[&[u8]]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for input in inputs {
+        // Length-prefix each input so ("ab", "c") and ("a", "bc") differ.
+        for byte in (input.len() as u64).to_le_bytes().iter().chain(*input) {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+/// Writes `contents` to `output` unless `stamp` shows it was already
+/// written from the same `inputs`.
+pub fn write_if_stale(
+    output: &Path,
+    stamp: &Path,
+    inputs: &[&[u8]],
+    contents: &[u8],
+) -> io::Result<StampOutcome> {
+    let existed = output.exists();
+    if is_up_to_date(&[output], stamp, inputs) {
+        return Ok(StampOutcome::UpToDate);
+    }
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(output, contents)?;
+    write_stamp(stamp, inputs)?;
+
+    Ok(if existed {
+        StampOutcome::Replaced
+    } else {
+        StampOutcome::Created
+    })
+}
+
+/// Whether every one of `outputs` exists and `stamp` shows they were made
+/// from `inputs`. For outputs that are expensive to produce, checked before
+/// producing them; [`write_stamp`] records them afterwards.
+pub fn is_up_to_date(outputs: &[&Path], stamp: &Path, inputs: &[&[u8]]) -> bool {
+    outputs.iter().all(|output| output.exists())
+        && fs::read_to_string(stamp).ok() == Some(fingerprint(inputs))
+}
+
+pub fn write_stamp(stamp: &Path, inputs: &[&[u8]]) -> io::Result<()> {
+    if let Some(parent) = stamp.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(stamp, fingerprint(inputs))
+}
@@ -0,0 +1,457 @@
+//! Debug symbols for crash reports: archiving each build's dSYM under its
+//! build UUID, and turning crash addresses back into Rust functions, files
+//! and lines.
+//!
+//! Extracting a dSYM needs `dsymutil` from Xcode; `rustc` already runs it
+//! when `split-debuginfo` is `packed`, and that dSYM is used when it matches.
+//! Reading one is done here, so crash reports can be symbolicated on any
+//! machine that has the archive. An archive looks like
+//!
+//! ```text
+//! target/symbols/8B3C1A2E-D4F0-463B-9C5E-7A10B2C3D4E5/rust_ios_hello.dSYM
+//! ```
+//!
+//! with one directory per UUID, so a universal binary's dSYM is filed under
+//! each of its architectures' UUIDs.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::bundle::Step;
+use crate::macho::{self, DwarfReader};
+use crate::runner::{io_error, run_checked, CommandError, CommandRunner};
+
+/// The UUID `ld` gives every image it links, which crash reports list for
+/// each loaded binary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BuildUuid(pub [u8; 16]);
+
+impl BuildUuid {
+    /// Accepts any case, with or without dashes or surrounding `<>`:
+    /// `8b3c1a2ed4f0463b...` as well as `8B3C1A2E-D4F0-...`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let digits: Vec<u8> = text
+            .trim()
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .bytes()
+            .filter(|&b| b != b'-')
+            .collect();
+        if digits.len() != 32 {
+            return None;
+        }
+        let mut uuid = [0; 16];
+        for (byte, pair) in uuid.iter_mut().zip(digits.chunks_exact(2)) {
+            *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+        }
+        Some(Self(uuid))
+    }
+}
+
+/// `8B3C1A2E-D4F0-463B-9C5E-7A10B2C3D4E5`, as `dwarfdump --uuid` prints it.
+impl fmt::Display for BuildUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                write!(f, "-")?;
+            }
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymbolError {
+    NotMachO,
+    /// The Mach-O or DWARF data is cut short or inconsistent.
+    Corrupt(String),
+    Unsupported(String),
+    /// A binary or dSYM lacks what symbolication needs.
+    Missing(String),
+    Command(CommandError),
+}
+
+impl fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolError::NotMachO => write!(f, "not a Mach-O file"),
+            SymbolError::Corrupt(message) => write!(f, "corrupt debug info: {}", message),
+            SymbolError::Unsupported(message) => write!(f, "unsupported: {}", message),
+            SymbolError::Missing(message) => write!(f, "{}", message),
+            SymbolError::Command(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for SymbolError {}
+
+impl From<CommandError> for SymbolError {
+    fn from(error: CommandError) -> Self {
+        SymbolError::Command(error)
+    }
+}
+
+/// Demangles a Rust symbol, legacy (`_ZN...E`) or v0 (`_R...`), with or
+/// without the extra leading underscore of Mach-O symbol tables, and drops
+/// its hash, giving e.g. `rust_ios_hello::ui::handle_tap`. Other symbols
+/// give `None`.
+pub fn demangle(symbol: &str) -> Option<String> {
+    rustc_demangle::try_demangle(symbol)
+        .ok()
+        .map(|demangled| format!("{:#}", demangled))
+}
+
+/// A function and source line an address maps to. Inlining turns one
+/// address into several: the inlined code, then each function it was
+/// inlined into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub function: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    /// Whether this code was inlined into the next frame.
+    pub inlined: bool,
+}
+
+/// `function (file:line)`, leaving out what isn't known.
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.function.as_deref().unwrap_or("??"))?;
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, " ({}:{})", file, line),
+            (Some(file), None) => write!(f, " ({})", file),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The symbols and debug info of one architecture of a binary or dSYM.
+pub struct DebugInfo {
+    pub uuid: BuildUuid,
+    /// `arm64`, `x86_64`, ...
+    pub architecture: String,
+    /// Where `__TEXT` starts and ends as linked; crash addresses are
+    /// relative to where it was loaded instead.
+    pub text: (u64, u64),
+    code: Vec<(u64, u64)>,
+    symbols: Vec<(u64, String)>,
+    context: addr2line::Context<DwarfReader>,
+}
+
+impl DebugInfo {
+    /// Every architecture in a Mach-O file, usually the DWARF file inside a
+    /// dSYM. An executable without DWARF still gives function names from
+    /// its symbol table.
+    pub fn parse(bytes: &[u8]) -> Result<Vec<Self>, SymbolError> {
+        macho::slices(bytes)?
+            .into_iter()
+            .map(|slice| {
+                let uuid = slice.uuid.ok_or_else(|| {
+                    SymbolError::Missing(format!("{} slice has no build UUID", slice.architecture))
+                })?;
+                let context = addr2line::Context::from_dwarf(slice.dwarf()?)
+                    .map_err(|e| SymbolError::Corrupt(e.to_string()))?;
+                Ok(Self {
+                    uuid,
+                    architecture: slice.architecture,
+                    text: slice.text,
+                    code: slice.code,
+                    symbols: slice.symbols,
+                    context,
+                })
+            })
+            .collect()
+    }
+
+    /// The frames for `address`, as linked, innermost first. Empty if
+    /// nothing is known about it.
+    pub fn lookup(&self, address: u64) -> Vec<Frame> {
+        let mut frames = Vec::new();
+        // Debug info that can't be read for this address is no worse than
+        // none: the symbol table still names the function.
+        if let Ok(mut found) = self.context.find_frames(address).skip_all_loads() {
+            while let Ok(Some(frame)) = found.next() {
+                let (file, line) = frame.location.map_or((None, None), |location| {
+                    (location.file.map(str::to_string), location.line)
+                });
+                let function = frame.function.and_then(|function| {
+                    let name = function.raw_name().ok()?;
+                    Some(demangle(&name).unwrap_or_else(|| name.into_owned()))
+                });
+                frames.push(Frame {
+                    function,
+                    file,
+                    line,
+                    inlined: true,
+                });
+            }
+        }
+        // Each frame but the last was inlined into the one after it.
+        if let Some(last) = frames.last_mut() {
+            last.inlined = false;
+            if last.function.is_none() {
+                last.function = self.symbol(address);
+            }
+        } else if let Some(function) = self.symbol(address) {
+            frames.push(Frame {
+                function: Some(function),
+                file: None,
+                line: None,
+                inlined: false,
+            });
+        }
+        frames
+    }
+
+    /// Like [`lookup`](Self::lookup), for an address in an image whose
+    /// `__TEXT` was loaded at `load_address`.
+    pub fn lookup_loaded(&self, load_address: u64, address: u64) -> Vec<Frame> {
+        match address.checked_sub(load_address) {
+            Some(offset) => self.lookup(self.text.0.wrapping_add(offset)),
+            None => Vec::new(),
+        }
+    }
+
+    fn symbol(&self, address: u64) -> Option<String> {
+        // Without a size, a symbol would otherwise cover everything up to
+        // the next one, padding and stubs included.
+        let in_code = self
+            .code
+            .iter()
+            .any(|&(start, end)| start <= address && address < end);
+        if !in_code {
+            return None;
+        }
+        let index = self
+            .symbols
+            .partition_point(|(start, _)| *start <= address)
+            .checked_sub(1)?;
+        let name = &self.symbols[index].1;
+        Some(demangle(name).unwrap_or_else(|| name.strip_prefix('_').unwrap_or(name).to_string()))
+    }
+}
+
+/// The build UUID of each architecture in a Mach-O binary, without reading
+/// its debug info.
+pub fn build_uuids(bytes: &[u8]) -> Result<Vec<(String, BuildUuid)>, SymbolError> {
+    macho::slices(bytes)?
+        .into_iter()
+        .map(|slice| match slice.uuid {
+            Some(uuid) => Ok((slice.architecture, uuid)),
+            None => Err(SymbolError::Missing(format!(
+                "{} slice has no build UUID; was it linked with -no_uuid?",
+                slice.architecture
+            ))),
+        })
+        .collect()
+}
+
+fn file_uuids(path: &Path) -> Result<Vec<(String, BuildUuid)>, SymbolError> {
+    build_uuids(&fs::read(path).map_err(io_error(path))?)
+}
+
+/// The DWARF files inside a dSYM bundle, `Contents/Resources/DWARF/*`.
+pub fn dsym_dwarf_files(dsym: &Path) -> Result<Vec<PathBuf>, SymbolError> {
+    let dir = dsym.join("Contents/Resources/DWARF");
+    let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+        .map_err(io_error(&dir))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn dsym_uuids(dsym: &Path) -> Result<Vec<(String, BuildUuid)>, SymbolError> {
+    let mut uuids = Vec::new();
+    for file in dsym_dwarf_files(dsym)? {
+        uuids.extend(file_uuids(&file)?);
+    }
+    uuids.sort();
+    Ok(uuids)
+}
+
+/// The dSYM holding `executable`'s debug info: the one the build left next
+/// to it if it matches, or a new one from `dsymutil` in `work_dir`.
+pub fn find_or_extract_dsym(
+    runner: &mut dyn CommandRunner,
+    executable: &Path,
+    work_dir: &Path,
+    report: &mut dyn FnMut(&Step),
+) -> Result<PathBuf, SymbolError> {
+    let mut wanted = file_uuids(executable)?;
+    wanted.sort();
+    let name = executable
+        .file_name()
+        .ok_or_else(|| SymbolError::Missing(format!("{} is not a file", executable.display())))?
+        .to_string_lossy()
+        .into_owned();
+
+    let beside = executable.with_file_name(format!("{}.dSYM", name));
+    if dsym_uuids(&beside).ok().as_ref() == Some(&wanted) {
+        report(&Step {
+            name: "extract symbols",
+            detail: format!("{} (from the build)", beside.display()),
+        });
+        return Ok(beside);
+    }
+
+    let dsym = work_dir.join(format!("{}.dSYM", name));
+    if dsym.exists() {
+        fs::remove_dir_all(&dsym).map_err(io_error(&dsym))?;
+    }
+    fs::create_dir_all(work_dir).map_err(io_error(work_dir))?;
+    let executable_arg = executable.display().to_string();
+    let dsym_arg = dsym.display().to_string();
+    run_checked(runner, "dsymutil", &[&executable_arg, "-o", &dsym_arg])?;
+    match dsym_uuids(&dsym) {
+        Ok(uuids) if uuids == wanted => {}
+        Ok(_) => {
+            return Err(SymbolError::Missing(format!(
+                "{} does not match {}",
+                dsym.display(),
+                executable.display()
+            )))
+        }
+        Err(e) => {
+            return Err(SymbolError::Missing(format!(
+                "dsymutil wrote no usable dSYM for {}: {}",
+                executable.display(),
+                e
+            )))
+        }
+    }
+    report(&Step {
+        name: "extract symbols",
+        detail: format!("dsymutil -> {}", dsym.display()),
+    });
+    Ok(dsym)
+}
+
+/// One architecture's symbols in an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchivedSymbols {
+    pub uuid: BuildUuid,
+    pub architecture: String,
+    /// The archived dSYM.
+    pub path: PathBuf,
+}
+
+/// Copies `dsym` into `archive` under each of its build UUIDs. A UUID that
+/// is already archived is left alone: the same UUID means the same build.
+pub fn archive_dsym(
+    dsym: &Path,
+    archive: &Path,
+    report: &mut dyn FnMut(&Step),
+) -> Result<Vec<ArchivedSymbols>, SymbolError> {
+    let name = dsym
+        .file_name()
+        .ok_or_else(|| SymbolError::Missing(format!("{} is not a dSYM", dsym.display())))?;
+    let mut archived = Vec::new();
+    for (architecture, uuid) in dsym_uuids(dsym)? {
+        let path = archive.join(uuid.to_string()).join(name);
+        let present = dsym_uuids(&path)
+            .map(|uuids| uuids.iter().any(|(_, archived)| *archived == uuid))
+            .unwrap_or(false);
+        if present {
+            report(&Step {
+                name: "archive symbols",
+                detail: format!("{} ({}) already archived", uuid, architecture),
+            });
+        } else {
+            if path.exists() {
+                fs::remove_dir_all(&path).map_err(io_error(&path))?;
+            }
+            copy_dir(dsym, &path)?;
+            report(&Step {
+                name: "archive symbols",
+                detail: format!("{} ({}) -> {}", uuid, architecture, path.display()),
+            });
+        }
+        archived.push(ArchivedSymbols {
+            uuid,
+            architecture,
+            path,
+        });
+    }
+    Ok(archived)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), SymbolError> {
+    fs::create_dir_all(to).map_err(io_error(to))?;
+    for entry in fs::read_dir(from).map_err(io_error(from))? {
+        let entry = entry.map_err(io_error(from))?;
+        let source = entry.path();
+        let destination = to.join(entry.file_name());
+        if source.is_dir() {
+            copy_dir(&source, &destination)?;
+        } else {
+            fs::copy(&source, &destination).map_err(io_error(&source))?;
+        }
+    }
+    Ok(())
+}
+
+/// The archived DWARF file for `uuid`, if there is one.
+pub fn find_symbols(archive: &Path, uuid: BuildUuid) -> Option<PathBuf> {
+    let dir = archive.join(uuid.to_string());
+    let mut dsyms: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "dSYM"))
+        .collect();
+    dsyms.sort();
+    dsyms
+        .iter()
+        .flat_map(|dsym| dsym_dwarf_files(dsym).unwrap_or_default())
+        .next()
+}
+
+/// Looks addresses up in a symbol archive, reading each build's debug info
+/// once.
+pub struct Symbolicator {
+    archive: PathBuf,
+    loaded: HashMap<BuildUuid, Option<DebugInfo>>,
+}
+
+impl Symbolicator {
+    pub fn new(archive: impl Into<PathBuf>) -> Self {
+        Self {
+            archive: archive.into(),
+            loaded: HashMap::new(),
+        }
+    }
+
+    /// The debug info for `uuid`, or `None` if the archive has none.
+    pub fn debug_info(&mut self, uuid: BuildUuid) -> Result<Option<&DebugInfo>, SymbolError> {
+        if !self.loaded.contains_key(&uuid) {
+            let info = match find_symbols(&self.archive, uuid) {
+                Some(path) => {
+                    let bytes = fs::read(&path).map_err(io_error(&path))?;
+                    DebugInfo::parse(&bytes)?
+                        .into_iter()
+                        .find(|info| info.uuid == uuid)
+                }
+                None => None,
+            };
+            self.loaded.insert(uuid, info);
+        }
+        Ok(self.loaded[&uuid].as_ref())
+    }
+
+    /// The frames for `address` in the image `uuid` loaded at
+    /// `load_address`. `None` if the archive has no symbols for it.
+    pub fn symbolicate(
+        &mut self,
+        uuid: BuildUuid,
+        load_address: u64,
+        address: u64,
+    ) -> Result<Option<Vec<Frame>>, SymbolError> {
+        Ok(self
+            .debug_info(uuid)?
+            .map(|info| info.lookup_loaded(load_address, address)))
+    }
+}
@@ -0,0 +1,278 @@
+//! Multi-architecture builds: fat simulator binaries and XCFrameworks.
+//!
+//! A simulator binary that runs on both Apple Silicon and Intel Macs holds an
+//! `arm64` and an `x86_64` slice, joined with `lipo`. An XCFramework wraps
+//! one library per platform (device, simulator) so Xcode links the right one;
+//! its layout and `Info.plist` are written here, only `lipo` is external.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use plist::{Dictionary, Value};
+
+use crate::bundle::Step;
+use crate::plist::to_xml;
+use crate::runner::{io_error, run_checked, CommandError, CommandRunner};
+
+/// An iOS target triple Rust can build for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppleTarget {
+    pub triple: String,
+    /// The architecture name Apple's tools use, `arm64` or `x86_64`.
+    pub architecture: &'static str,
+    pub simulator: bool,
+}
+
+impl AppleTarget {
+    /// `aarch64-apple-ios`, `aarch64-apple-ios-sim` or `x86_64-apple-ios`
+    /// (which only ever runs in the simulator).
+    pub fn parse(triple: &str) -> Option<Self> {
+        let (architecture, simulator) = match triple {
+            "aarch64-apple-ios" => ("arm64", false),
+            "aarch64-apple-ios-sim" => ("arm64", true),
+            "x86_64-apple-ios" => ("x86_64", true),
+            _ => return None,
+        };
+        Some(Self {
+            triple: triple.to_string(),
+            architecture,
+            simulator,
+        })
+    }
+}
+
+/// Whether binaries for `triple` run in the simulator rather than on a
+/// device.
+pub fn is_simulator_target(triple: &str) -> bool {
+    AppleTarget::parse(triple).is_some_and(|target| target.simulator)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XcframeworkError {
+    /// The targets can't be combined as asked.
+    Targets(String),
+    Command(CommandError),
+}
+
+impl fmt::Display for XcframeworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XcframeworkError::Targets(message) => write!(f, "{}", message),
+            XcframeworkError::Command(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for XcframeworkError {}
+
+impl From<CommandError> for XcframeworkError {
+    fn from(error: CommandError) -> Self {
+        XcframeworkError::Command(error)
+    }
+}
+
+/// Joins single-architecture binaries into `output` with `lipo -create`.
+/// One input is copied as it is.
+pub fn lipo(
+    runner: &mut dyn CommandRunner,
+    inputs: &[PathBuf],
+    output: &Path,
+) -> Result<(), XcframeworkError> {
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).map_err(io_error(parent))?;
+    }
+    if let [input] = inputs {
+        fs::copy(input, output).map_err(io_error(input))?;
+        return Ok(());
+    }
+
+    let mut args = vec!["-create".to_string()];
+    args.extend(inputs.iter().map(|input| input.display().to_string()));
+    args.push("-output".to_string());
+    args.push(output.display().to_string());
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    run_checked(runner, "lipo", &args)?;
+    Ok(())
+}
+
+/// The per-architecture builds of one platform variant, made into one
+/// library inside the XCFramework.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibrarySlice {
+    /// Directory name inside the XCFramework, e.g.
+    /// `ios-arm64_x86_64-simulator`, as Xcode names them.
+    pub identifier: String,
+    pub architectures: Vec<&'static str>,
+    pub simulator: bool,
+    /// One library per architecture, in the order of `architectures`.
+    pub inputs: Vec<PathBuf>,
+}
+
+impl LibrarySlice {
+    /// Groups libraries built for `targets` into a device and a simulator
+    /// slice. Two builds for the same architecture and platform can't share
+    /// a slice, so that is an error.
+    pub fn group(builds: &[(AppleTarget, PathBuf)]) -> Result<Vec<Self>, XcframeworkError> {
+        let mut slices: Vec<Self> = Vec::new();
+        for simulator in [false, true] {
+            let mut builds: Vec<&(AppleTarget, PathBuf)> = builds
+                .iter()
+                .filter(|(target, _)| target.simulator == simulator)
+                .collect();
+            if builds.is_empty() {
+                continue;
+            }
+            builds.sort_by_key(|(target, _)| target.architecture);
+            for pair in builds.windows(2) {
+                if pair[0].0.architecture == pair[1].0.architecture {
+                    return Err(XcframeworkError::Targets(format!(
+                        "{} and {} both build {} for the {}",
+                        pair[0].0.triple,
+                        pair[1].0.triple,
+                        pair[0].0.architecture,
+                        if simulator { "simulator" } else { "device" }
+                    )));
+                }
+            }
+
+            let architectures: Vec<&'static str> = builds
+                .iter()
+                .map(|(target, _)| target.architecture)
+                .collect();
+            slices.push(Self {
+                identifier: format!(
+                    "ios-{}{}",
+                    architectures.join("_"),
+                    if simulator { "-simulator" } else { "" }
+                ),
+                architectures,
+                simulator,
+                inputs: builds.iter().map(|(_, path)| path.clone()).collect(),
+            });
+        }
+        Ok(slices)
+    }
+}
+
+/// What goes into an XCFramework.
+#[derive(Debug, Clone)]
+pub struct XcframeworkSpec {
+    /// File name of the static library in each slice, e.g.
+    /// `librust_ios_hello.a`.
+    pub library_name: String,
+    /// C headers copied into each slice's `Headers` directory.
+    pub headers: Vec<PathBuf>,
+    /// Clang module name for a generated `module.modulemap`, so Swift can
+    /// `import` the headers. `None` leaves it out.
+    pub module_name: Option<String>,
+    pub slices: Vec<LibrarySlice>,
+}
+
+/// The `Info.plist` at the top of the XCFramework, listing every slice.
+pub fn xcframework_info_plist(spec: &XcframeworkSpec) -> Value {
+    let libraries: Vec<Value> = spec
+        .slices
+        .iter()
+        .map(|slice| {
+            let mut library = Dictionary::new();
+            let mut set = |key: &str, value: Value| {
+                library.insert(key.to_string(), value);
+            };
+            set("BinaryPath", spec.library_name.as_str().into());
+            set("LibraryIdentifier", slice.identifier.as_str().into());
+            set("LibraryPath", spec.library_name.as_str().into());
+            if !spec.headers.is_empty() {
+                set("HeadersPath", "Headers".into());
+            }
+            set(
+                "SupportedArchitectures",
+                slice
+                    .architectures
+                    .iter()
+                    .map(|&architecture| architecture.into())
+                    .collect::<Vec<_>>()
+                    .into(),
+            );
+            set("SupportedPlatform", "ios".into());
+            if slice.simulator {
+                set("SupportedPlatformVariant", "simulator".into());
+            }
+            Value::Dictionary(library)
+        })
+        .collect();
+
+    let mut dict = Dictionary::new();
+    dict.insert("AvailableLibraries".to_string(), Value::Array(libraries));
+    dict.insert("CFBundlePackageType".to_string(), "XFWK".into());
+    dict.insert("XCFrameworkFormatVersion".to_string(), "1.0".into());
+    Value::Dictionary(dict)
+}
+
+/// A `module.modulemap` exposing `headers` as the module `name`.
+pub fn module_map(name: &str, headers: &[PathBuf]) -> String {
+    let mut map = format!("module {} {{\n", name);
+    for header in headers {
+        if let Some(file) = header.file_name() {
+            map.push_str(&format!("    header \"{}\"\n", file.to_string_lossy()));
+        }
+    }
+    map.push_str("    export *\n}\n");
+    map
+}
+
+/// Writes the XCFramework at `output`, replacing any previous one, and
+/// reports each slice as it is done.
+pub fn create_xcframework(
+    runner: &mut dyn CommandRunner,
+    spec: &XcframeworkSpec,
+    output: &Path,
+    report: &mut dyn FnMut(&Step),
+) -> Result<(), XcframeworkError> {
+    if spec.slices.is_empty() {
+        return Err(XcframeworkError::Targets(
+            "an XCFramework needs at least one library".to_string(),
+        ));
+    }
+    if output.exists() {
+        fs::remove_dir_all(output).map_err(io_error(output))?;
+    }
+
+    for slice in &spec.slices {
+        let dir = output.join(&slice.identifier);
+        lipo(runner, &slice.inputs, &dir.join(&spec.library_name))?;
+
+        if !spec.headers.is_empty() {
+            let headers = dir.join("Headers");
+            fs::create_dir_all(&headers).map_err(io_error(&headers))?;
+            for header in &spec.headers {
+                let name = header.file_name().ok_or_else(|| CommandError::Io {
+                    path: header.clone(),
+                    message: "not a file".to_string(),
+                })?;
+                fs::copy(header, headers.join(name)).map_err(io_error(header))?;
+            }
+            if let Some(module) = &spec.module_name {
+                let path = headers.join("module.modulemap");
+                fs::write(&path, module_map(module, &spec.headers)).map_err(io_error(&path))?;
+            }
+        }
+        report(&Step {
+            name: "library",
+            detail: format!("{} ({})", slice.identifier, slice.architectures.join(", ")),
+        });
+    }
+
+    let path = output.join("Info.plist");
+    let plist = to_xml(&xcframework_info_plist(spec)).map_err(|e| CommandError::Io {
+        path: path.clone(),
+        message: e.to_string(),
+    })?;
+    fs::write(&path, plist).map_err(io_error(&path))?;
+    report(&Step {
+        name: "write Info.plist",
+        detail: path.display().to_string(),
+    });
+    Ok(())
+}
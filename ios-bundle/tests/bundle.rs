@@ -0,0 +1,376 @@
+use ios_bundle::plist::from_xml;
+use ios_bundle::{assemble, BundleSpec, Image, IosMetadata, Resource, Step};
+use plist::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MANIFEST: &str = r#"
+[package]
+name = "app"
+version = "1.2.0"
+
+[package.metadata.ios]
+bundle-id = "org.example.app"
+executable = "App"
+"#;
+
+/// A fake build output: a binary and an assets directory.
+fn spec(root: &Path) -> BundleSpec {
+    let binary = root.join("target/debug/app");
+    fs::create_dir_all(binary.parent().unwrap()).unwrap();
+    fs::write(&binary, b"\xcf\xfa\xed\xfe binary").unwrap();
+
+    let assets = root.join("assets");
+    fs::create_dir_all(assets.join("themes")).unwrap();
+    fs::write(assets.join("themes/dark.theme.ron"), "()").unwrap();
+    fs::write(assets.join("main.layout.ron"), "()").unwrap();
+    fs::write(assets.join(".DS_Store"), "").unwrap();
+
+    BundleSpec {
+        metadata: IosMetadata::from_package_manifest(MANIFEST).unwrap(),
+        manifest_dir: root.to_path_buf(),
+        executable: binary,
+        resources: vec![Resource {
+            source: assets,
+            destination: "assets".into(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }],
+    }
+}
+
+/// Every file in `dir`, relative to it, sorted.
+fn tree(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path.strip_prefix(dir).unwrap().to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+fn assemble_into(spec: &BundleSpec, root: &Path) -> Vec<Step> {
+    let mut steps = Vec::new();
+    assemble(
+        spec,
+        &root.join("App.app"),
+        &root.join("stamps"),
+        &mut |step| steps.push(step.clone()),
+    )
+    .unwrap();
+    steps
+}
+
+#[test]
+fn bundle_has_the_ios_layout() {
+    let dir = tempfile::tempdir().unwrap();
+    let spec = spec(dir.path());
+    assemble_into(&spec, dir.path());
+
+    let bundle = dir.path().join("App.app");
+    assert_eq!(
+        tree(&bundle),
+        [
+            "App",
+            "Info.plist",
+            "assets/main.layout.ron",
+            "assets/themes/dark.theme.ron",
+        ]
+        .map(PathBuf::from)
+    );
+    assert_eq!(
+        fs::read(bundle.join("App")).unwrap(),
+        b"\xcf\xfa\xed\xfe binary"
+    );
+
+    let plist = from_xml(&fs::read_to_string(bundle.join("Info.plist")).unwrap())
+        .unwrap()
+        .into_dictionary()
+        .unwrap();
+    assert_eq!(
+        plist.get("CFBundleExecutable").and_then(Value::as_string),
+        Some("App")
+    );
+    assert_eq!(
+        plist
+            .get("CFBundleShortVersionString")
+            .and_then(Value::as_string),
+        Some("1.2.0")
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn executable_is_marked_executable() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let spec = spec(dir.path());
+    assemble_into(&spec, dir.path());
+
+    let mode = fs::metadata(dir.path().join("App.app/App"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o755);
+}
+
+#[test]
+fn every_step_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let spec = spec(dir.path());
+
+    let steps = assemble_into(&spec, dir.path());
+    let names: Vec<_> = steps.iter().map(|step| step.name).collect();
+    assert_eq!(
+        names,
+        [
+            "create bundle",
+            "copy executable",
+            "write Info.plist",
+            "copy resources"
+        ]
+    );
+    assert_eq!(steps[2].detail, "created");
+    assert!(
+        steps[3].detail.ends_with("(2 files)"),
+        "{}",
+        steps[3].detail
+    );
+
+    // A second run replaces the executable but leaves the plist alone.
+    let steps = assemble_into(&spec, dir.path());
+    assert_eq!(steps[2].detail, "up to date");
+}
+
+#[test]
+fn assets_are_filtered_by_pattern() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut spec = spec(dir.path());
+    let assets = dir.path().join("assets");
+    fs::create_dir_all(assets.join("fonts")).unwrap();
+    fs::write(assets.join("fonts/FiraSans-Bold.ttf"), "font").unwrap();
+    fs::write(assets.join("fonts/FiraSans.psd"), "layers").unwrap();
+    fs::create_dir_all(assets.join("drafts")).unwrap();
+    fs::write(assets.join("drafts/next.layout.ron"), "()").unwrap();
+    spec.resources[0].include = vec!["**/*.ron".to_string(), "fonts/**".to_string()];
+    spec.resources[0].exclude = vec!["*.psd".to_string(), "drafts".to_string()];
+
+    assemble_into(&spec, dir.path());
+    assert_eq!(
+        tree(&dir.path().join("App.app/assets")),
+        [
+            "fonts/FiraSans-Bold.ttf",
+            "main.layout.ron",
+            "themes/dark.theme.ron",
+        ]
+        .map(PathBuf::from)
+    );
+}
+
+#[test]
+fn assets_are_mirrored() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut spec = spec(dir.path());
+    assemble_into(&spec, dir.path());
+
+    // Deleted from the source, then newly excluded.
+    fs::remove_file(dir.path().join("assets/main.layout.ron")).unwrap();
+    spec.resources[0].exclude = vec!["themes".to_string()];
+    let steps = assemble_into(&spec, dir.path());
+
+    let resources = steps.last().unwrap();
+    assert!(
+        resources.detail.ends_with("(0 files, 2 removed)"),
+        "{}",
+        resources.detail
+    );
+    let bundle = dir.path().join("App.app");
+    assert_eq!(tree(&bundle), ["App", "Info.plist"].map(PathBuf::from));
+    assert!(!bundle.join("assets/themes").exists());
+}
+
+#[test]
+fn empty_resource_sets_are_copied() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut spec = spec(dir.path());
+    spec.resources[0].exclude = vec!["**".to_string()];
+    let steps = assemble_into(&spec, dir.path());
+    assert!(
+        steps.last().unwrap().detail.ends_with("(0 files)"),
+        "{}",
+        steps.last().unwrap().detail
+    );
+
+    let empty = dir.path().join("empty");
+    fs::create_dir(&empty).unwrap();
+    spec.resources[0] = Resource {
+        source: empty,
+        destination: "assets".into(),
+        include: Vec::new(),
+        exclude: Vec::new(),
+    };
+    assemble_into(&spec, dir.path());
+    assert_eq!(
+        tree(&dir.path().join("App.app")),
+        ["App", "Info.plist"].map(PathBuf::from)
+    );
+}
+
+#[test]
+fn icons_and_launch_screen_come_from_one_image() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut spec = spec(dir.path());
+    spec.metadata.icon = Some("branding/icon.png".to_string());
+    spec.metadata.launch_background = Some([0x26, 0x26, 0x26]);
+    fs::create_dir_all(dir.path().join("branding")).unwrap();
+    fs::write(
+        dir.path().join("branding/icon.png"),
+        Image::filled(1024, 1024, [222, 165, 132, 255]).encode(),
+    )
+    .unwrap();
+
+    let steps = assemble_into(&spec, dir.path());
+    let bundle = dir.path().join("App.app");
+    let files = tree(&bundle);
+    for expected in [
+        "AppIcon20x20@2x.png",
+        "AppIcon60x60@3x.png",
+        "LaunchBackground.png",
+    ] {
+        assert!(files.contains(&PathBuf::from(expected)), "{:?}", files);
+    }
+    let icon = Image::decode(&fs::read(bundle.join("AppIcon60x60@3x.png")).unwrap()).unwrap();
+    assert_eq!((icon.width, icon.height), (180, 180));
+    assert_eq!(icon.pixel(90, 90), [222, 165, 132, 255]);
+    assert_eq!(
+        steps
+            .iter()
+            .map(|step| step.name)
+            .filter(|name| name.contains("icons") || name.contains("launch"))
+            .collect::<Vec<_>>(),
+        ["generate icons", "write launch screen"]
+    );
+
+    let plist = from_xml(&fs::read_to_string(bundle.join("Info.plist")).unwrap())
+        .unwrap()
+        .into_dictionary()
+        .unwrap();
+    assert!(plist.get("CFBundleIcons").is_some());
+    assert_eq!(
+        plist
+            .get("UILaunchScreen")
+            .and_then(Value::as_dictionary)
+            .and_then(|screen| screen.get("UIImageName"))
+            .and_then(Value::as_string),
+        Some("LaunchBackground")
+    );
+
+    // Nothing is rescaled until the source changes.
+    let steps = assemble_into(&spec, dir.path());
+    assert_eq!(steps[3].detail, "up to date");
+    assert_eq!(steps[4].detail, "up to date");
+    fs::write(
+        dir.path().join("branding/icon.png"),
+        Image::filled(1024, 1024, [0, 0, 0, 255]).encode(),
+    )
+    .unwrap();
+    let steps = assemble_into(&spec, dir.path());
+    assert!(
+        steps[3].detail.ends_with("-> 8 icons"),
+        "{}",
+        steps[3].detail
+    );
+}
+
+#[test]
+fn small_icon_fails_at_its_step() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut spec = spec(dir.path());
+    spec.metadata.icon = Some("icon.png".to_string());
+    fs::write(
+        dir.path().join("icon.png"),
+        Image::filled(256, 256, [0, 0, 0, 255]).encode(),
+    )
+    .unwrap();
+
+    let error = assemble(
+        &spec,
+        &dir.path().join("App.app"),
+        &dir.path().join("stamps"),
+        &mut |_| {},
+    )
+    .unwrap_err();
+    assert_eq!(error.step, "generate icons");
+    assert_eq!(error.path, dir.path().join("icon.png"));
+    assert_eq!(
+        error.source.to_string(),
+        "icon must be at least 1024x1024, not 256x256"
+    );
+}
+
+#[test]
+fn missing_executable_fails_at_its_step() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut spec = spec(dir.path());
+    spec.executable = dir.path().join("target/debug/missing");
+
+    let error = assemble(
+        &spec,
+        &dir.path().join("App.app"),
+        &dir.path().join("stamps"),
+        &mut |_| {},
+    )
+    .unwrap_err();
+    assert_eq!(error.step, "copy executable");
+    assert_eq!(error.path, spec.executable);
+    assert!(!dir.path().join("App.app/Info.plist").exists());
+}
+
+#[test]
+fn package_name_and_version_come_from_the_manifest() {
+    let metadata = IosMetadata::from_package_manifest(
+        "[package]\nname = \"demo\"\nversion = \"0.3.0\"\n\n\
+         [package.metadata.ios]\nbundle-id = \"org.example.demo\"\n",
+    )
+    .unwrap();
+    assert_eq!(metadata.executable, "demo");
+    assert_eq!(metadata.version, "0.3.0");
+
+    assert!(IosMetadata::from_package_manifest(
+        "[package]\nname = \"demo\"\n\n[package.metadata.ios]\nbundle-id = \"a.b\"\n"
+    )
+    .is_err());
+}
+
+#[test]
+fn asset_resource_comes_from_the_manifest() {
+    let metadata = IosMetadata::from_package_manifest(MANIFEST).unwrap();
+    assert_eq!(
+        metadata.assets_resource(Path::new("/work")),
+        Resource {
+            source: "/work/assets".into(),
+            destination: "assets".into(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    );
+
+    let metadata = IosMetadata::from_package_manifest(&format!(
+        "{}assets = \"game/assets\"\nassets-exclude = [\"*.blend\"]\n",
+        MANIFEST
+    ))
+    .unwrap();
+    let resource = metadata.assets_resource(Path::new("/work"));
+    assert_eq!(resource.source, Path::new("/work/game/assets"));
+    assert_eq!(resource.destination, Path::new("assets"));
+    assert_eq!(resource.exclude, ["*.blend"]);
+}
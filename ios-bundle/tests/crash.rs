@@ -0,0 +1,129 @@
+use ios_bundle::{archive_dsym, symbolicate_report, BuildUuid, CrashReport, Symbolicator};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The same crash, as iOS 18 writes it and in the older text format. The
+/// app's build is `fixtures/symbols/crashy`.
+const IPS: &str = include_str!("fixtures/crash/rust-ios-test.ips");
+const TEXT: &str = include_str!("fixtures/crash/rust-ios-test.crash");
+const DSYM: &[u8] = include_bytes!("fixtures/symbols/crashy");
+
+const LIB: &str = "/Users/dev/rust-ios-hello/src/lib.rs";
+const BUTTON: &str = "/Users/dev/rust-ios-hello/src/ui/button.rs";
+
+/// A symbol archive holding the fixture app's dSYM.
+fn archive(dir: &Path) -> PathBuf {
+    let dsym = dir.join("rust-ios-test.dSYM");
+    let dwarf = dsym.join("Contents/Resources/DWARF");
+    fs::create_dir_all(&dwarf).unwrap();
+    fs::write(dwarf.join("rust-ios-test"), DSYM).unwrap();
+    let archive = dir.join("symbols");
+    archive_dsym(&dsym, &archive, &mut |_| {}).unwrap();
+    archive
+}
+
+#[test]
+fn both_formats_read_the_same() {
+    let ips = CrashReport::parse(IPS).unwrap();
+    assert_eq!(ips, CrashReport::parse(TEXT).unwrap());
+
+    assert_eq!(ips.images.len(), 4);
+    assert_eq!(ips.images[0].name, "rust-ios-test");
+    assert_eq!(
+        ips.images[0].uuid,
+        BuildUuid::parse("8B3C1A2E-D4F0-463B-9C5E-7A10B2C3D4E5")
+    );
+    assert_eq!(ips.images[0].load_address, 0x1_04a2_8000);
+
+    let titles: Vec<&str> = ips.threads.iter().map(|t| t.title.as_str()).collect();
+    assert_eq!(titles, ["Thread 0 Crashed", "Thread 1"]);
+    assert!(ips.threads[0].crashed);
+    let app_frame = &ips.threads[0].frames[2];
+    assert_eq!(app_frame.image, Some(0));
+    assert_eq!(app_frame.address, 0x1_04a2_c01c);
+    assert_eq!(
+        ips.threads[0].frames[1].symbol.as_deref(),
+        Some("abort + 180")
+    );
+}
+
+#[test]
+fn app_frames_get_rust_functions_and_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut symbolicator = Symbolicator::new(archive(dir.path()));
+    let report = CrashReport::parse(IPS).unwrap();
+    let symbolicated = symbolicate_report(&report, &mut symbolicator).unwrap();
+
+    let expected = [
+        "Thread 0 Crashed:".to_string(),
+        "0   libsystem_kernel.dylib         0x00000001e8f4a2ec __pthread_kill + 8".to_string(),
+        "1   libsystem_c.dylib              0x00000001b2e1a2c0 abort + 180".to_string(),
+        format!(
+            "2   rust-ios-test                  0x0000000104a2c01c crashy::report ({}:25) [inlined]",
+            LIB
+        ),
+        format!(
+            "2   rust-ios-test                  0x0000000104a2c01c crashy::ui::button::handle_tap ({}:4)",
+            BUTTON
+        ),
+        format!(
+            "3   rust-ios-test                  0x0000000104a2c050 rust_gui_pump ({}:31)",
+            LIB
+        ),
+        "4   UIKitCore                      0x00000001a8b3e1c4 -[UIApplication _run] + 888".to_string(),
+        String::new(),
+        "Thread 1:".to_string(),
+        "0   libsystem_kernel.dylib         0x00000001e8f45c8c __workq_kernreturn + 8".to_string(),
+        // A return address: the call is the instruction before it, on the
+        // line before the one 0x...c048 itself is on.
+        format!(
+            "1   rust-ios-test                  0x0000000104a2c048 rust_gui_pump ({}:30)",
+            LIB
+        ),
+    ];
+    assert_eq!(symbolicated.lines().collect::<Vec<_>>(), expected);
+
+    let text = CrashReport::parse(TEXT).unwrap();
+    assert_eq!(
+        symbolicate_report(&text, &mut symbolicator).unwrap(),
+        symbolicated
+    );
+}
+
+#[test]
+fn frames_without_archived_symbols_keep_the_report_text() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut symbolicator = Symbolicator::new(dir.path().join("empty"));
+    let report = CrashReport::parse(TEXT).unwrap();
+    let symbolicated = symbolicate_report(&report, &mut symbolicator).unwrap();
+    assert!(symbolicated
+        .contains("2   rust-ios-test                  0x0000000104a2c01c 0x104a28000 + 16412\n"));
+}
+
+#[test]
+fn register_dumps_are_not_threads() {
+    let report = CrashReport::parse(TEXT).unwrap();
+    assert_eq!(report.threads.len(), 2);
+    assert_eq!(report.threads[1].frames.len(), 2);
+}
+
+#[test]
+fn frames_past_the_address_space_are_skipped() {
+    let report = CrashReport::parse(
+        "{\"usedImages\": [{\"name\": \"app\", \"base\": 18446744073709551615}], \
+         \"threads\": [{\"frames\": [\
+         {\"imageIndex\": 0, \"imageOffset\": 16}, \
+         {\"imageIndex\": 0, \"imageOffset\": 0}]}]}",
+    )
+    .unwrap();
+
+    assert_eq!(report.threads[0].frames.len(), 1);
+    assert_eq!(report.threads[0].frames[0].address, u64::MAX);
+}
+
+#[test]
+fn other_files_are_rejected() {
+    assert!(CrashReport::parse("hello").is_err());
+    assert!(CrashReport::parse("{\"bug_type\": \"309\"}\n{\"threads\": []}").is_err());
+    assert!(CrashReport::parse("{ not json").is_err());
+}
@@ -0,0 +1,335 @@
+use ios_bundle::plist::from_xml;
+use ios_bundle::{
+    install_and_launch, sign_bundle, CommandError, CommandOutput, DeviceError, DeviceTool,
+    FakeRunner, IosMetadata, ProvisioningProfile, SigningConfig,
+};
+use plist::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const PROFILE: &[u8] = include_bytes!("fixtures/profiles/development.mobileprovision");
+
+/// 2024-06-01, between the fixture profile's creation and expiry.
+fn june_2024() -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(1_717_200_000)
+}
+
+fn config(profile: PathBuf) -> SigningConfig {
+    SigningConfig {
+        identity: "Apple Development: Jane Doe (AB12CD34EF)".to_string(),
+        provisioning_profile: profile,
+        device: Some("00008110-001A2B3C4D5E801E".to_string()),
+    }
+}
+
+fn calls(runner: &FakeRunner) -> Vec<String> {
+    runner.calls().iter().map(|call| call.join(" ")).collect()
+}
+
+#[test]
+fn profile_is_read_from_its_signature_envelope() {
+    let profile = ProvisioningProfile::parse(PROFILE).unwrap();
+    assert_eq!(profile.name, "iOS Team Provisioning Profile: *");
+    assert_eq!(profile.uuid, "5f3c2b1a-9e8d-4c7b-a6f5-e4d3c2b1a098");
+    assert_eq!(profile.team_id, "AB12CD34EF");
+    assert_eq!(profile.application_identifier, "AB12CD34EF.*");
+    assert_eq!(
+        profile.expiration_date.to_xml_format(),
+        "2025-03-01T09:15:42Z"
+    );
+    assert_eq!(
+        profile.devices.as_deref(),
+        Some(
+            &[
+                "00008110-001A2B3C4D5E801E".to_string(),
+                "00008030-000C1D2E3F40802E".to_string()
+            ][..]
+        )
+    );
+
+    assert!(matches!(
+        ProvisioningProfile::parse(b"\x30\x82 not a profile"),
+        Err(DeviceError::Profile(_))
+    ));
+}
+
+#[test]
+fn profile_checks_bundle_id_and_expiry() {
+    let mut profile = ProvisioningProfile::parse(PROFILE).unwrap();
+    assert!(profile.allows_bundle_id("com.example.rustiostest"));
+
+    profile.application_identifier = "AB12CD34EF.com.example.*".to_string();
+    assert!(profile.allows_bundle_id("com.example.rustiostest"));
+    assert!(!profile.allows_bundle_id("org.other.app"));
+
+    profile.application_identifier = "AB12CD34EF.com.example.app".to_string();
+    assert!(profile.allows_bundle_id("com.example.app"));
+    assert!(!profile.allows_bundle_id("com.example.app2"));
+
+    assert!(!profile.is_expired(june_2024()));
+    // 2025-03-01T09:15:42Z exactly.
+    assert!(profile.is_expired(UNIX_EPOCH + Duration::from_secs(1_740_820_542)));
+}
+
+#[test]
+fn entitlements_are_derived_from_the_profile() {
+    let profile = ProvisioningProfile::parse(PROFILE).unwrap();
+    let entitlements = profile.entitlements_for("com.example.rustiostest");
+
+    assert_eq!(
+        entitlements
+            .get("application-identifier")
+            .and_then(Value::as_string),
+        Some("AB12CD34EF.com.example.rustiostest")
+    );
+    assert_eq!(
+        entitlements
+            .get("com.apple.developer.team-identifier")
+            .and_then(Value::as_string),
+        Some("AB12CD34EF")
+    );
+    assert_eq!(
+        entitlements
+            .get("get-task-allow")
+            .and_then(Value::as_boolean),
+        Some(true)
+    );
+    assert_eq!(
+        entitlements.get("keychain-access-groups"),
+        Some(&Value::Array(vec![
+            "AB12CD34EF.com.example.rustiostest".into()
+        ]))
+    );
+}
+
+#[test]
+fn signing_embeds_profile_and_runs_codesign() {
+    let dir = tempfile::tempdir().unwrap();
+    let profile = dir.path().join("development.mobileprovision");
+    fs::write(&profile, PROFILE).unwrap();
+    let bundle = dir.path().join("RustApp.app");
+    fs::create_dir_all(&bundle).unwrap();
+    let work = dir.path().join("signing");
+
+    let mut runner = FakeRunner::new();
+    let mut steps = Vec::new();
+    sign_bundle(
+        &mut runner,
+        &config(profile),
+        "com.example.rustiostest",
+        &bundle,
+        &work,
+        june_2024(),
+        &mut |step| steps.push(step.name),
+    )
+    .unwrap();
+
+    assert_eq!(
+        steps,
+        [
+            "check profile",
+            "embed profile",
+            "write entitlements",
+            "codesign"
+        ]
+    );
+    assert_eq!(
+        fs::read(bundle.join("embedded.mobileprovision")).unwrap(),
+        PROFILE
+    );
+    let entitlements = work.join("entitlements.plist");
+    let written = from_xml(&fs::read_to_string(&entitlements).unwrap())
+        .unwrap()
+        .into_dictionary()
+        .unwrap();
+    assert_eq!(
+        written
+            .get("application-identifier")
+            .and_then(Value::as_string),
+        Some("AB12CD34EF.com.example.rustiostest")
+    );
+    assert_eq!(
+        calls(&runner),
+        [format!(
+            "codesign --force --sign Apple Development: Jane Doe (AB12CD34EF) \
+             --entitlements {} --generate-entitlement-der --timestamp=none {}",
+            entitlements.display(),
+            bundle.display()
+        )]
+    );
+}
+
+#[test]
+fn unusable_profiles_stop_before_codesign() {
+    let dir = tempfile::tempdir().unwrap();
+    let profile = dir.path().join("development.mobileprovision");
+    fs::write(&profile, PROFILE).unwrap();
+    let bundle = dir.path().join("RustApp.app");
+    fs::create_dir_all(&bundle).unwrap();
+
+    let mut runner = FakeRunner::new();
+    let expired = sign_bundle(
+        &mut runner,
+        &config(profile.clone()),
+        "com.example.rustiostest",
+        &bundle,
+        dir.path(),
+        UNIX_EPOCH + Duration::from_secs(1_800_000_000),
+        &mut |_| {},
+    );
+    assert_eq!(
+        expired,
+        Err(DeviceError::Profile(
+            "\"iOS Team Provisioning Profile: *\" expired on 2025-03-01T09:15:42Z".to_string()
+        ))
+    );
+
+    let missing = sign_bundle(
+        &mut runner,
+        &config(dir.path().join("missing.mobileprovision")),
+        "com.example.rustiostest",
+        &bundle,
+        dir.path(),
+        june_2024(),
+        &mut |_| {},
+    );
+    assert!(matches!(
+        missing,
+        Err(DeviceError::Command(CommandError::Io { .. }))
+    ));
+    assert!(runner.calls().is_empty());
+    assert!(!bundle.join("embedded.mobileprovision").exists());
+}
+
+#[test]
+fn signing_config_comes_from_metadata_and_environment() {
+    let metadata = IosMetadata::from_manifest(
+        "[package.metadata.ios]\nbundle-id = \"a.b\"\n\
+         signing-identity = \"Apple Development\"\n\
+         provisioning-profile = \"signing/dev.mobileprovision\"\n",
+        "app",
+        "1.0.0",
+    )
+    .unwrap();
+
+    let config = SigningConfig::new(&metadata, Path::new("/work"), |_| None).unwrap();
+    assert_eq!(config.identity, "Apple Development");
+    assert_eq!(
+        config.provisioning_profile,
+        Path::new("/work/signing/dev.mobileprovision")
+    );
+    assert_eq!(config.device, None);
+
+    let config = SigningConfig::new(&metadata, Path::new("/work"), |key| match key {
+        "IOS_SIGNING_IDENTITY" => Some("0123456789ABCDEF".to_string()),
+        "IOS_DEVICE" => Some("Jane's iPhone".to_string()),
+        _ => None,
+    })
+    .unwrap();
+    assert_eq!(config.identity, "0123456789ABCDEF");
+    assert_eq!(config.device.as_deref(), Some("Jane's iPhone"));
+
+    let unsigned = IosMetadata::from_manifest(
+        "[package.metadata.ios]\nbundle-id = \"a.b\"\n",
+        "app",
+        "1.0.0",
+    )
+    .unwrap();
+    assert_eq!(
+        SigningConfig::new(&unsigned, Path::new("/work"), |_| None),
+        Err(DeviceError::Config(
+            "device builds need package.metadata.ios.signing-identity or IOS_SIGNING_IDENTITY"
+                .to_string()
+        ))
+    );
+}
+
+#[test]
+fn device_tool_prefers_devicectl() {
+    let mut runner = FakeRunner::new();
+    assert_eq!(DeviceTool::detect(&mut runner), Ok(DeviceTool::Devicectl));
+
+    runner.respond(
+        &["xcrun", "--find", "devicectl"],
+        CommandOutput::failure(""),
+    );
+    assert_eq!(DeviceTool::detect(&mut runner), Ok(DeviceTool::IosDeploy));
+
+    runner.respond(&["ios-deploy"], CommandOutput::failure(""));
+    assert!(matches!(
+        DeviceTool::detect(&mut runner),
+        Err(DeviceError::Config(_))
+    ));
+}
+
+#[test]
+fn devicectl_installs_and_launches_on_the_device() {
+    let mut runner = FakeRunner::new();
+    install_and_launch(
+        &mut runner,
+        DeviceTool::Devicectl,
+        Some("00008110-001A2B3C4D5E801E"),
+        Path::new("/work/RustApp.app"),
+        "com.example.rustiostest",
+        &mut |_| {},
+    )
+    .unwrap();
+    assert_eq!(
+        calls(&runner),
+        [
+            "xcrun devicectl device install app --device 00008110-001A2B3C4D5E801E /work/RustApp.app",
+            "xcrun devicectl device process launch --device 00008110-001A2B3C4D5E801E \
+             --terminate-existing com.example.rustiostest",
+        ]
+    );
+
+    let mut runner = FakeRunner::new();
+    let without_device = install_and_launch(
+        &mut runner,
+        DeviceTool::Devicectl,
+        None,
+        Path::new("/work/RustApp.app"),
+        "com.example.rustiostest",
+        &mut |_| {},
+    );
+    assert!(matches!(without_device, Err(DeviceError::Config(_))));
+    assert!(runner.calls().is_empty());
+}
+
+#[test]
+fn ios_deploy_installs_and_launches_in_one_call() {
+    let mut runner = FakeRunner::new();
+    install_and_launch(
+        &mut runner,
+        DeviceTool::IosDeploy,
+        Some("00008110-001A2B3C4D5E801E"),
+        Path::new("RustApp.app"),
+        "com.example.rustiostest",
+        &mut |_| {},
+    )
+    .unwrap();
+    assert_eq!(
+        calls(&runner),
+        ["ios-deploy --bundle RustApp.app --justlaunch --id 00008110-001A2B3C4D5E801E"]
+    );
+
+    let mut runner = FakeRunner::new();
+    runner.respond(
+        &["ios-deploy"],
+        CommandOutput::failure("[....] No devices found\n"),
+    );
+    let error = install_and_launch(
+        &mut runner,
+        DeviceTool::IosDeploy,
+        None,
+        Path::new("RustApp.app"),
+        "com.example.rustiostest",
+        &mut |_| {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "`ios-deploy --bundle RustApp.app --justlaunch` failed: [....] No devices found"
+    );
+}
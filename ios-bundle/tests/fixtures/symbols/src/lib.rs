@@ -0,0 +1,34 @@
+#![feature(no_core, lang_items)]
+#![no_core]
+#![allow(internal_features)]
+
+#[lang = "pointee_sized"]
+pub trait PointeeSized {}
+#[lang = "meta_sized"]
+pub trait MetaSized: PointeeSized {}
+#[lang = "sized"]
+pub trait Sized: MetaSized {}
+#[lang = "copy"]
+pub trait Copy {}
+
+impl Copy for u32 {}
+
+extern "C" {
+    fn abort_with(code: u32) -> u32;
+    fn log_value(value: u32);
+}
+
+pub mod ui;
+
+#[inline(always)]
+fn report(code: u32) -> u32 {
+    unsafe { abort_with(code) }
+}
+
+#[no_mangle]
+pub extern "C-unwind" fn rust_gui_pump(taps: u32) -> u32 {
+    unsafe { log_value(taps) };
+    let code = ui::button::handle_tap(taps);
+    unsafe { log_value(code) };
+    code
+}
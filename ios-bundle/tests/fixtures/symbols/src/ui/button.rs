@@ -0,0 +1,7 @@
+#[inline(never)]
+pub fn handle_tap(taps: u32) -> u32 {
+    unsafe { crate::log_value(taps) };
+    let code = crate::report(taps);
+    unsafe { crate::log_value(code) };
+    code
+}
@@ -0,0 +1,159 @@
+use ios_bundle::{
+    app_icons, check_icon_source, icons_plist, launch_image, DeviceFamily, Image, PngError,
+};
+use plist::{Dictionary, Value};
+
+fn pixels(image: &Image) -> Vec<[u8; 4]> {
+    (0..image.height)
+        .flat_map(|y| (0..image.width).map(move |x| image.pixel(x, y)))
+        .collect()
+}
+
+#[test]
+fn unreadable_images_are_rejected() {
+    assert_eq!(Image::decode(b"GIF89a"), Err(PngError::NotPng));
+
+    let png = Image::filled(3, 2, [0, 0, 255, 128]).encode();
+    let mut damaged = png.clone();
+    damaged[20] ^= 1;
+    assert!(matches!(Image::decode(&damaged), Err(PngError::Corrupt(_))));
+    assert!(matches!(
+        Image::decode(&png[..png.len() - 12]),
+        Err(PngError::Corrupt(_))
+    ));
+}
+
+#[test]
+fn encoding_round_trips() {
+    let mut image = Image::filled(5, 3, [10, 20, 30, 255]);
+    image.rgba[4..8].copy_from_slice(&[200, 100, 0, 255]);
+    let opaque = image.encode();
+    // Opaque images are written without an alpha channel.
+    assert_eq!(opaque[25], 2);
+    assert_eq!(Image::decode(&opaque).unwrap(), image);
+
+    image.rgba[11] = 0;
+    let translucent = image.encode();
+    assert_eq!(translucent[25], 6);
+    assert_eq!(Image::decode(&translucent).unwrap(), image);
+}
+
+#[test]
+fn downscaling_averages_covered_pixels() {
+    let mut checker = Image::filled(4, 4, [0, 0, 0, 255]);
+    for y in 0..4 {
+        for x in 0..4 {
+            if (x + y) % 2 == 0 {
+                let i = (y * 4 + x) * 4;
+                checker.rgba[i..i + 3].copy_from_slice(&[255, 255, 255]);
+            }
+        }
+    }
+    let half = checker.resize(2, 2);
+    assert!(pixels(&half)
+        .iter()
+        .all(|&pixel| pixel == [128, 128, 128, 255]));
+
+    // 3 -> 2 splits the middle column between both outputs.
+    let mut stripes = Image::filled(3, 1, [0, 0, 0, 255]);
+    stripes.rgba[..3].copy_from_slice(&[255, 255, 255]);
+    assert_eq!(
+        pixels(&stripes.resize(2, 1)),
+        [[170, 170, 170, 255], [0, 0, 0, 255]]
+    );
+}
+
+#[test]
+fn transparent_pixels_do_not_darken_edges() {
+    let mut edge = Image::filled(2, 1, [0, 0, 0, 0]);
+    edge.rgba[..4].copy_from_slice(&[255, 0, 0, 255]);
+    assert_eq!(pixels(&edge.resize(1, 1)), [[255, 0, 0, 128]]);
+}
+
+#[test]
+fn every_family_gets_its_icon_sizes() {
+    let names = |families: &[DeviceFamily]| {
+        app_icons(families)
+            .iter()
+            .map(|icon| (icon.file_name(), icon.pixels))
+            .collect::<Vec<_>>()
+    };
+
+    let iphone = names(&[DeviceFamily::IPhone]);
+    assert_eq!(iphone.len(), 8);
+    assert!(iphone.contains(&("AppIcon60x60@2x.png".to_string(), 120)));
+    assert!(iphone.contains(&("AppIcon60x60@3x.png".to_string(), 180)));
+
+    let both = names(&[DeviceFamily::IPhone, DeviceFamily::IPad]);
+    assert_eq!(both.len(), 17);
+    assert!(both.contains(&("AppIcon76x76~ipad.png".to_string(), 76)));
+    assert!(both.contains(&("AppIcon83.5x83.5@2x~ipad.png".to_string(), 167)));
+}
+
+/// The `CFBundleIconFiles` under `key` in `dict`.
+fn icon_files<'a>(dict: &'a Dictionary, key: &str) -> Option<Vec<&'a str>> {
+    let files = dict
+        .get(key)
+        .and_then(Value::as_dictionary)
+        .and_then(|icons| icons.get("CFBundlePrimaryIcon"))
+        .and_then(Value::as_dictionary)
+        .and_then(|primary| primary.get("CFBundleIconFiles"))
+        .and_then(Value::as_array)?;
+    Some(files.iter().filter_map(Value::as_string).collect())
+}
+
+#[test]
+fn icon_plist_lists_base_names_per_idiom() {
+    let iphone = icons_plist(&[DeviceFamily::IPhone]);
+    assert_eq!(
+        icon_files(&iphone, "CFBundleIcons"),
+        Some(vec![
+            "AppIcon20x20",
+            "AppIcon29x29",
+            "AppIcon40x40",
+            "AppIcon60x60"
+        ])
+    );
+    assert_eq!(iphone.get("CFBundleIcons~ipad"), None);
+
+    let ipad = icons_plist(&[DeviceFamily::IPad]);
+    assert_eq!(ipad.get("CFBundleIcons"), None);
+    assert_eq!(
+        icon_files(&ipad, "CFBundleIcons~ipad"),
+        Some(vec![
+            "AppIcon20x20",
+            "AppIcon29x29",
+            "AppIcon40x40",
+            "AppIcon76x76",
+            "AppIcon83.5x83.5"
+        ])
+    );
+}
+
+#[test]
+fn icon_source_must_be_large_and_square() {
+    assert_eq!(
+        check_icon_source(&Image::filled(1024, 512, [0; 4])),
+        Err("icon must be square, not 1024x512".to_string())
+    );
+    assert_eq!(
+        check_icon_source(&Image::filled(512, 512, [0; 4])),
+        Err("icon must be at least 1024x1024, not 512x512".to_string())
+    );
+    assert_eq!(
+        check_icon_source(&Image::filled(1024, 1024, [0; 4])),
+        Ok(())
+    );
+}
+
+#[test]
+fn launch_image_is_one_opaque_color() {
+    let image = launch_image([0x26, 0x26, 0x26]);
+    assert_eq!((image.width, image.height), (1366, 1366));
+    assert!(image
+        .rgba
+        .chunks_exact(4)
+        .all(|pixel| pixel == [0x26, 0x26, 0x26, 255]));
+    // A solid color compresses to next to nothing.
+    assert!(image.encode().len() < 64 * 1024);
+}
@@ -0,0 +1,187 @@
+use ios_bundle::plist::to_xml;
+use ios_bundle::{DeviceFamily, IosMetadata, MetadataError, Orientation};
+use plist::Value;
+
+const APP_MANIFEST: &str = include_str!("../../Cargo.toml");
+
+/// The Info.plist written for `manifest`, read back with the `plist` crate.
+fn plist_for(manifest: &str) -> Value {
+    let metadata = IosMetadata::from_manifest(manifest, "my-app", "2.3.4").unwrap();
+    let xml = to_xml(&metadata.info_plist()).unwrap();
+    Value::from_reader_xml(xml.as_bytes()).unwrap()
+}
+
+fn get<'a>(plist: &'a Value, key: &str) -> Option<&'a Value> {
+    plist.as_dictionary()?.get(key)
+}
+
+fn string<'a>(plist: &'a Value, key: &str) -> &'a str {
+    get(plist, key)
+        .and_then(Value::as_string)
+        .unwrap_or_else(|| panic!("{} is missing or not a string", key))
+}
+
+fn strings<'a>(plist: &'a Value, key: &str) -> Vec<&'a str> {
+    get(plist, key)
+        .and_then(Value::as_array)
+        .unwrap_or_else(|| panic!("{} is missing or not an array", key))
+        .iter()
+        .map(|value| value.as_string().unwrap())
+        .collect()
+}
+
+#[test]
+fn app_manifest_produces_every_key() {
+    let plist = plist_for(APP_MANIFEST);
+
+    assert_eq!(
+        string(&plist, "CFBundleIdentifier"),
+        "com.example.rustiostest"
+    );
+    assert_eq!(string(&plist, "CFBundleDisplayName"), "Rust GUI");
+    assert_eq!(string(&plist, "CFBundleName"), "Rust iOS Test");
+    assert_eq!(string(&plist, "CFBundleExecutable"), "rust-ios-test");
+    assert_eq!(string(&plist, "CFBundleShortVersionString"), "2.3.4");
+    assert_eq!(string(&plist, "CFBundleVersion"), "1");
+    assert_eq!(string(&plist, "CFBundlePackageType"), "APPL");
+    assert_eq!(string(&plist, "MinimumOSVersion"), "12.0");
+    assert_eq!(
+        strings(&plist, "UISupportedInterfaceOrientations"),
+        [
+            "UIInterfaceOrientationPortrait",
+            "UIInterfaceOrientationLandscapeLeft",
+            "UIInterfaceOrientationLandscapeRight",
+            "UIInterfaceOrientationPortraitUpsideDown",
+        ]
+    );
+    assert_eq!(
+        get(&plist, "UIDeviceFamily"),
+        Some(&Value::Array(vec![Value::from(1i64), Value::from(2i64)]))
+    );
+    assert_eq!(strings(&plist, "UIRequiredDeviceCapabilities"), ["arm64"]);
+
+    let scenes = get(&plist, "UIApplicationSceneManifest").unwrap();
+    assert_eq!(
+        get(scenes, "UIApplicationSupportsMultipleScenes").and_then(Value::as_boolean),
+        Some(false)
+    );
+    let configuration = &get(scenes, "UISceneConfigurations")
+        .and_then(|configs| get(configs, "UIWindowSceneSessionRoleApplication"))
+        .and_then(Value::as_array)
+        .unwrap()[0];
+    assert_eq!(
+        string(configuration, "UISceneConfigurationName"),
+        "Default Configuration"
+    );
+}
+
+#[test]
+fn defaults_apply_when_only_bundle_id_is_set() {
+    let metadata = IosMetadata::from_manifest(
+        "[package.metadata.ios]\nbundle-id = \"org.example.app\"\n",
+        "my-app",
+        "2.3.4",
+    )
+    .unwrap();
+
+    assert_eq!(metadata.display_name, "my-app");
+    assert_eq!(metadata.executable, "my-app");
+    assert_eq!(metadata.build_number, "2.3.4");
+    assert_eq!(metadata.orientations, [Orientation::Portrait]);
+    assert_eq!(metadata.device_family, [DeviceFamily::IPhone]);
+    assert_eq!(metadata.minimum_os, "12.0");
+}
+
+#[test]
+fn values_are_escaped() {
+    let plist = plist_for(
+        "[package.metadata.ios]\nbundle-id = \"org.example.app\"\ndisplay-name = \"Tom & Jerry's <App>\"\n",
+    );
+
+    assert_eq!(string(&plist, "CFBundleDisplayName"), "Tom & Jerry's <App>");
+}
+
+#[test]
+fn extra_keys_override_generated_ones() {
+    let plist = plist_for(
+        "[package.metadata.ios]\nbundle-id = \"org.example.app\"\n\
+         [package.metadata.ios.info-plist]\nMinimumOSVersion = \"15.0\"\nITSAppUsesNonExemptEncryption = false\n",
+    );
+
+    assert_eq!(string(&plist, "MinimumOSVersion"), "15.0");
+    assert_eq!(
+        get(&plist, "ITSAppUsesNonExemptEncryption").and_then(Value::as_boolean),
+        Some(false)
+    );
+}
+
+#[test]
+fn icon_and_launch_screen_keys() {
+    let plist = plist_for(
+        "[package.metadata.ios]\nbundle-id = \"org.example.app\"\n\
+         icon = \"ios/AppIcon.png\"\nlaunch-background = \"#1E90ff\"\n",
+    );
+    assert_eq!(
+        get(&plist, "CFBundleIcons")
+            .and_then(|icons| get(icons, "CFBundlePrimaryIcon"))
+            .and_then(|primary| get(primary, "CFBundleIconFiles"))
+            .and_then(Value::as_array)
+            .map(|files| files.len()),
+        Some(4)
+    );
+    assert_eq!(
+        get(&plist, "UILaunchScreen")
+            .and_then(|screen| get(screen, "UIImageName"))
+            .and_then(Value::as_string),
+        Some("LaunchBackground")
+    );
+
+    let metadata = IosMetadata::from_manifest(
+        "[package.metadata.ios]\nbundle-id = \"a.b\"\nlaunch-background = \"#1E90ff\"\n",
+        "a",
+        "1.0",
+    )
+    .unwrap();
+    assert_eq!(metadata.launch_background, Some([0x1e, 0x90, 0xff]));
+
+    // Without either, no icons are listed but the launch screen is still
+    // declared, so the app isn't letterboxed.
+    let plist = plist_for("[package.metadata.ios]\nbundle-id = \"org.example.app\"\n");
+    assert_eq!(get(&plist, "CFBundleIcons"), None);
+    assert_eq!(
+        get(&plist, "UILaunchScreen"),
+        Some(&Value::Dictionary(Default::default()))
+    );
+}
+
+#[test]
+fn bad_metadata_is_reported() {
+    let parse = |ios: &str| {
+        IosMetadata::from_manifest(&format!("[package.metadata.ios]\n{}", ios), "a", "1.0")
+    };
+
+    assert_eq!(
+        IosMetadata::from_manifest("[package]\nname = \"a\"\n", "a", "1.0"),
+        Err(MetadataError::Missing("bundle-id"))
+    );
+    assert!(matches!(
+        parse("bundle-id = \"com.example/app\""),
+        Err(MetadataError::Invalid { key, .. }) if key == "bundle-id"
+    ));
+    assert!(matches!(
+        parse("bundle-id = \"a.b\"\norientations = [\"sideways\"]"),
+        Err(MetadataError::Invalid { key, .. }) if key == "orientations"
+    ));
+    assert!(matches!(
+        parse("bundle-id = \"a.b\"\nbundle-identifier = \"a.b\""),
+        Err(MetadataError::Invalid { key, .. }) if key == "bundle-identifier"
+    ));
+    assert!(matches!(
+        parse("bundle-id = \"a.b\"\nlaunch-background = \"dark gray\""),
+        Err(MetadataError::Invalid { key, .. }) if key == "launch-background"
+    ));
+    assert!(matches!(
+        IosMetadata::from_manifest("[package", "a", "1.0"),
+        Err(MetadataError::Toml(_))
+    ));
+}
@@ -0,0 +1,43 @@
+use ios_bundle::pattern::{matches, selected};
+
+#[test]
+fn wildcards_stay_within_a_component() {
+    assert!(matches("themes/*.ron", "themes/dark.theme.ron"));
+    assert!(!matches("themes/*.ron", "themes/old/dark.theme.ron"));
+    assert!(matches(
+        "layouts/main.?ayout.ron",
+        "layouts/main.layout.ron"
+    ));
+    assert!(!matches("layouts/*.ron", "themes/dark.theme.ron"));
+}
+
+#[test]
+fn double_star_spans_directories() {
+    assert!(matches("**/*.ttf", "Fira.ttf"));
+    assert!(matches("**/*.ttf", "fonts/latin/Fira.ttf"));
+    assert!(matches("fonts/**", "fonts/latin/Fira.ttf"));
+    assert!(matches("fonts/**/Fira.ttf", "fonts/Fira.ttf"));
+    assert!(!matches("fonts/**", "images/fonts.png"));
+}
+
+#[test]
+fn names_match_at_any_depth_and_cover_directories() {
+    assert!(matches("*.psd", "images/icons/button.psd"));
+    assert!(matches("drafts", "drafts/next.layout.ron"));
+    assert!(matches("drafts", "layouts/drafts/next.layout.ron"));
+    assert!(matches(
+        "/layouts/drafts/",
+        "layouts/drafts/next.layout.ron"
+    ));
+    assert!(!matches("drafts", "layouts/drafts.ron.bak/x"));
+}
+
+#[test]
+fn exclude_wins_over_include() {
+    let include = ["**/*.ron".to_string()];
+    let exclude = ["drafts".to_string()];
+    assert!(selected("themes/dark.theme.ron", &include, &exclude));
+    assert!(!selected("drafts/next.layout.ron", &include, &exclude));
+    assert!(!selected("fonts/Fira.ttf", &include, &exclude));
+    assert!(selected("fonts/Fira.ttf", &[], &[]));
+}
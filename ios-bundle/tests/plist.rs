@@ -0,0 +1,159 @@
+use ios_bundle::plist::{from_xml, to_xml};
+use ios_bundle::PlistError;
+use plist::{Dictionary, Value};
+
+fn sample() -> Value {
+    let mut inner = Dictionary::new();
+    inner.insert("Flag".to_string(), false.into());
+    inner.insert("Empty".to_string(), Value::Array(Vec::new()));
+
+    let mut dict = Dictionary::new();
+    dict.insert("Name".to_string(), "Tom & Jerry <3 \"quotes\"".into());
+    dict.insert("Count".to_string(), (-42).into());
+    dict.insert("Ratio".to_string(), Value::Real(1.5));
+    dict.insert("Enabled".to_string(), true.into());
+    dict.insert(
+        "List".to_string(),
+        Value::Array(vec!["a".into(), "b".into()]),
+    );
+    dict.insert("Nested".to_string(), inner.into());
+    dict.insert("Unicode".to_string(), "Привет 👋\n\ttab".into());
+    Value::Dictionary(dict)
+}
+
+/// Reads `xml` with the `plist` crate directly, as a check on what
+/// [`to_xml`] wrote.
+fn parse(xml: &str) -> Value {
+    Value::from_reader_xml(xml.as_bytes()).expect("a valid XML plist")
+}
+
+#[test]
+fn round_trips() {
+    let xml = to_xml(&sample()).unwrap();
+
+    assert_eq!(from_xml(&xml).unwrap(), sample());
+}
+
+#[test]
+fn written_values_read_back_independently() {
+    let plist = parse(&to_xml(&sample()).unwrap());
+    let dict = plist.as_dictionary().unwrap();
+
+    assert_eq!(
+        dict.get("Name").and_then(Value::as_string),
+        Some("Tom & Jerry <3 \"quotes\"")
+    );
+    assert_eq!(
+        dict.get("Count").and_then(Value::as_signed_integer),
+        Some(-42)
+    );
+    assert_eq!(dict.get("Ratio").and_then(Value::as_real), Some(1.5));
+    assert_eq!(dict.get("Enabled").and_then(Value::as_boolean), Some(true));
+    assert_eq!(
+        dict.get("Unicode").and_then(Value::as_string),
+        Some("Привет 👋\n\ttab")
+    );
+    let nested = dict.get("Nested").and_then(Value::as_dictionary);
+    assert_eq!(
+        nested.and_then(|n| n.get("Empty")),
+        Some(&Value::Array(Vec::new()))
+    );
+    assert_eq!(
+        dict.keys().map(String::as_str).collect::<Vec<_>>(),
+        ["Count", "Enabled", "List", "Name", "Nested", "Ratio", "Unicode"]
+    );
+}
+
+#[test]
+fn writes_xcode_style_document() {
+    let mut dict = Dictionary::new();
+    dict.insert(
+        "UIDeviceFamily".to_string(),
+        Value::Array(vec![1.into(), 2.into()]),
+    );
+    dict.insert("CFBundleName".to_string(), "A&B".into());
+
+    assert_eq!(
+        to_xml(&dict.into()).unwrap(),
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n\
+         <dict>\n\
+         \t<key>CFBundleName</key>\n\
+         \t<string>A&amp;B</string>\n\
+         \t<key>UIDeviceFamily</key>\n\
+         \t<array>\n\
+         \t\t<integer>1</integer>\n\
+         \t\t<integer>2</integer>\n\
+         \t</array>\n\
+         </dict>\n\
+         </plist>\n"
+    );
+}
+
+#[test]
+fn markup_in_values_cannot_break_out() {
+    let value = Value::String("</string><true/>".to_string());
+    let xml = to_xml(&value).unwrap();
+
+    assert_eq!(parse(&xml), Value::String("</string><true/>".to_string()));
+}
+
+#[test]
+fn unrepresentable_values_are_rejected() {
+    assert_eq!(
+        to_xml(&Value::from("bell\u{7}")),
+        Err(PlistError::UnrepresentableCharacter('\u{7}'))
+    );
+    let mut dict = Dictionary::new();
+    dict.insert("key\u{0}".to_string(), true.into());
+    assert_eq!(
+        to_xml(&Value::Array(vec![dict.into()])),
+        Err(PlistError::UnrepresentableCharacter('\u{0}'))
+    );
+    assert!(matches!(
+        to_xml(&Value::Real(f64::NAN)),
+        Err(PlistError::UnrepresentableReal(_))
+    ));
+}
+
+#[test]
+fn syntax_errors_are_reported() {
+    let xml = "<plist>\n<dict>\n<key>A</key>\n<integer>x</integer>\n</dict>\n</plist>";
+
+    assert!(matches!(from_xml(xml), Err(PlistError::Syntax(_))));
+}
+
+#[test]
+fn dates_and_data_round_trip() {
+    let mut dict = Dictionary::new();
+    dict.insert(
+        "ExpirationDate".to_string(),
+        Value::Date(plist::Date::from_xml_format("2025-01-09T10:02:11Z").unwrap()),
+    );
+    dict.insert(
+        "Certificate".to_string(),
+        Value::Data(b"hello plist".to_vec()),
+    );
+    dict.insert("Empty".to_string(), Value::Data(Vec::new()));
+    let value = Value::Dictionary(dict);
+
+    let xml = to_xml(&value).unwrap();
+    let plist = parse(&xml);
+    assert_eq!(
+        plist
+            .as_dictionary()
+            .and_then(|d| d.get("ExpirationDate"))
+            .and_then(Value::as_date)
+            .map(|date| date.to_xml_format()),
+        Some("2025-01-09T10:02:11Z".to_string())
+    );
+    assert_eq!(
+        plist
+            .as_dictionary()
+            .and_then(|d| d.get("Certificate"))
+            .and_then(Value::as_data),
+        Some(&b"hello plist"[..])
+    );
+    assert_eq!(from_xml(&xml).unwrap(), value);
+}
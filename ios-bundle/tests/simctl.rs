@@ -0,0 +1,304 @@
+use ios_bundle::{
+    deploy, deploy_requested, deploy_step, CommandOutput, DeployReport, DeviceList, DeviceQuery,
+    DeviceState, FakeRunner, IosMetadata, Simctl, SimctlError,
+};
+use std::path::Path;
+
+const XCODE15: &str = include_str!("fixtures/simctl/xcode15.json");
+const XCODE10: &str = include_str!("fixtures/simctl/xcode10.json");
+const NONE_BOOTED: &str = include_str!("fixtures/simctl/none_booted.json");
+
+fn query(device: Option<&str>, runtime: Option<&str>) -> DeviceQuery {
+    DeviceQuery {
+        device: device.map(str::to_string),
+        runtime: runtime.map(str::to_string),
+    }
+}
+
+fn selected(json: &str, query: DeviceQuery) -> Result<String, SimctlError> {
+    let list = DeviceList::parse(json).unwrap();
+    query.select(&list).map(|device| device.udid.clone())
+}
+
+#[test]
+fn device_list_is_typed() {
+    let list = DeviceList::parse(XCODE15).unwrap();
+    assert_eq!(list.devices.len(), 5);
+
+    let watch = list
+        .devices
+        .iter()
+        .find(|device| device.name == "Apple Watch Series 9 (45mm)")
+        .unwrap();
+    assert_eq!(watch.runtime.platform(), "watchOS");
+    assert_eq!(watch.runtime.name(), "watchOS 10.2");
+    assert_eq!(watch.state, DeviceState::Booted);
+
+    let broken = list
+        .devices
+        .iter()
+        .find(|device| device.udid == "C0FFEE00-1234-4321-ABCD-0123456789AB")
+        .unwrap();
+    assert_eq!(broken.name, "iPhone 15");
+    assert_eq!(broken.runtime.version(), [17, 2]);
+    assert_eq!(broken.state, DeviceState::Shutdown);
+    assert!(!broken.is_available);
+}
+
+#[test]
+fn old_xcode_format_is_understood() {
+    let list = DeviceList::parse(XCODE10).unwrap();
+    let iphone_x = list
+        .devices
+        .iter()
+        .find(|device| device.name == "iPhone X")
+        .unwrap();
+    assert!(!iphone_x.is_available);
+    assert_eq!(iphone_x.runtime.name(), "iOS 11.4");
+
+    assert_eq!(
+        selected(XCODE10, DeviceQuery::default()).unwrap(),
+        "E4B4F3A1-7C1D-4D8F-AF1C-8D7A6B5C4D3E"
+    );
+}
+
+#[test]
+fn malformed_output_is_an_error() {
+    assert!(matches!(
+        DeviceList::parse("{\"devices\": []}"),
+        Err(SimctlError::Json(_))
+    ));
+}
+
+#[test]
+fn default_prefers_booted_ios_device() {
+    // The booted watch doesn't count.
+    assert_eq!(
+        selected(XCODE15, DeviceQuery::default()).unwrap(),
+        "9F8E7D6C-5B4A-4392-8170-6F5E4D3C2B1A"
+    );
+    // Nothing booted: an iPhone on the newest runtime.
+    assert_eq!(
+        selected(NONE_BOOTED, DeviceQuery::default()).unwrap(),
+        "BBBBBBBB-CCCC-4DDD-8EEE-FFFFFFFFFFFF"
+    );
+}
+
+#[test]
+fn device_is_selected_by_name_udid_and_runtime() {
+    assert_eq!(
+        selected(XCODE15, query(Some("iPhone 15 Pro"), None)).unwrap(),
+        "A2B3C4D5-E6F7-4819-A2B3-C4D5E6F70819",
+        "the newest runtime wins"
+    );
+    for runtime in [
+        "iOS 17.0",
+        "ios-17-0",
+        "com.apple.CoreSimulator.SimRuntime.iOS-17-0",
+    ] {
+        assert_eq!(
+            selected(XCODE15, query(Some("iPhone 15 Pro"), Some(runtime))).unwrap(),
+            "1B7E3A4C-2D5F-4E6A-8B9C-0D1E2F3A4B5C",
+            "{}",
+            runtime
+        );
+    }
+    assert_eq!(
+        selected(
+            XCODE15,
+            query(Some("1b7e3a4c-2d5f-4e6a-8b9c-0d1e2f3a4b5c"), None)
+        )
+        .unwrap(),
+        "1B7E3A4C-2D5F-4E6A-8B9C-0D1E2F3A4B5C"
+    );
+}
+
+#[test]
+fn unavailable_or_unknown_devices_are_not_selected() {
+    for query in [
+        query(Some("iPhone 15"), None),
+        query(Some("Apple Watch Series 9 (45mm)"), None),
+        query(Some("iPhone 15 Pro"), Some("iOS 16.4")),
+    ] {
+        let description = query.to_string();
+        assert_eq!(
+            selected(XCODE15, query),
+            Err(SimctlError::NoDevice(description))
+        );
+    }
+}
+
+#[test]
+fn environment_overrides_metadata() {
+    let metadata = IosMetadata::from_manifest(
+        "[package.metadata.ios]\nbundle-id = \"a.b\"\n\
+         simulator = \"iPhone 15 Pro\"\nsimulator-runtime = \"iOS 17.0\"\n",
+        "app",
+        "1.0.0",
+    )
+    .unwrap();
+
+    let from_config = DeviceQuery::new(&metadata, |_| None);
+    assert_eq!(from_config, query(Some("iPhone 15 Pro"), Some("iOS 17.0")));
+
+    let from_env = DeviceQuery::new(&metadata, |key| match key {
+        "IOS_SIMULATOR" => Some("A2B3C4D5-E6F7-4819-A2B3-C4D5E6F70819".to_string()),
+        "IOS_SIMULATOR_RUNTIME" => Some(String::new()),
+        _ => None,
+    });
+    assert_eq!(
+        from_env,
+        query(
+            Some("A2B3C4D5-E6F7-4819-A2B3-C4D5E6F70819"),
+            Some("iOS 17.0")
+        )
+    );
+}
+
+fn fake_with(json: &str) -> FakeRunner {
+    let mut runner = FakeRunner::new();
+    runner.respond(
+        &["xcrun", "simctl", "list", "devices", "--json"],
+        CommandOutput::success(json),
+    );
+    runner
+}
+
+fn calls(runner: &FakeRunner) -> Vec<String> {
+    runner.calls().iter().map(|call| call.join(" ")).collect()
+}
+
+#[test]
+fn deploy_boots_and_targets_the_device_by_udid() {
+    let mut runner = fake_with(XCODE15);
+    let deployment = deploy(
+        &mut Simctl::new(&mut runner),
+        &query(Some("iPhone 15 Pro"), None),
+        Path::new("/work/RustApp.app"),
+        "org.example.app",
+    )
+    .unwrap();
+
+    assert!(deployment.booted);
+    assert_eq!(
+        calls(&runner),
+        [
+            "xcrun simctl list devices --json",
+            "xcrun simctl bootstatus A2B3C4D5-E6F7-4819-A2B3-C4D5E6F70819 -b",
+            "xcrun simctl install A2B3C4D5-E6F7-4819-A2B3-C4D5E6F70819 /work/RustApp.app",
+            "xcrun simctl launch A2B3C4D5-E6F7-4819-A2B3-C4D5E6F70819 org.example.app",
+            "open -a Simulator --args -CurrentDeviceUDID A2B3C4D5-E6F7-4819-A2B3-C4D5E6F70819",
+        ]
+    );
+}
+
+#[test]
+fn deploy_to_booted_device_skips_boot() {
+    let mut runner = fake_with(XCODE15);
+    let deployment = deploy(
+        &mut Simctl::new(&mut runner),
+        &DeviceQuery::default(),
+        Path::new("App.app"),
+        "org.example.app",
+    )
+    .unwrap();
+
+    assert!(!deployment.booted);
+    assert_eq!(deployment.device.name, "iPad Air (5th generation)");
+    assert!(!calls(&runner)
+        .iter()
+        .any(|call| call.contains("bootstatus")));
+}
+
+#[test]
+fn failed_install_stops_the_deploy() {
+    let mut runner = fake_with(XCODE15);
+    runner.respond(
+        &["xcrun", "simctl", "install"],
+        CommandOutput::failure("An error was encountered processing the command\n"),
+    );
+    let error = deploy(
+        &mut Simctl::new(&mut runner),
+        &DeviceQuery::default(),
+        Path::new("App.app"),
+        "org.example.app",
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "`xcrun simctl install 9F8E7D6C-5B4A-4392-8170-6F5E4D3C2B1A App.app` failed: \
+         An error was encountered processing the command"
+    );
+    assert_eq!(runner.calls().len(), 2);
+}
+
+#[test]
+fn disabled_deploy_spawns_nothing() {
+    let mut runner = fake_with(XCODE15);
+    let report = deploy_step(
+        false,
+        "aarch64-apple-ios-sim",
+        &mut runner,
+        &DeviceQuery::default(),
+        Path::new("App.app"),
+        "org.example.app",
+    );
+    assert!(matches!(report, DeployReport::Skipped(_)));
+    assert!(!report.is_failure());
+    assert!(runner.calls().is_empty());
+
+    // Device builds can't go to a simulator, even when asked.
+    let report = deploy_step(
+        true,
+        "aarch64-apple-ios",
+        &mut runner,
+        &DeviceQuery::default(),
+        Path::new("App.app"),
+        "org.example.app",
+    );
+    assert_eq!(
+        report,
+        DeployReport::Skipped("aarch64-apple-ios is not a simulator target".to_string())
+    );
+    assert!(runner.calls().is_empty());
+}
+
+#[test]
+fn deploy_is_opt_in() {
+    let env =
+        |value: &'static str| move |key: &str| (key == "IOS_DEPLOY").then(|| value.to_string());
+    assert!(!deploy_requested(|_| None));
+    assert!(!deploy_requested(env("0")));
+    assert!(!deploy_requested(env("")));
+    assert!(deploy_requested(env("1")));
+    assert!(deploy_requested(env("true")));
+}
+
+#[test]
+fn deploy_report_summarizes_the_outcome() {
+    let mut runner = fake_with(XCODE15);
+    let report = deploy_step(
+        true,
+        "aarch64-apple-ios-sim",
+        &mut runner,
+        &query(Some("iPhone 15 Pro"), Some("iOS 17.0")),
+        Path::new("App.app"),
+        "org.example.app",
+    );
+    assert_eq!(
+        report.to_string(),
+        "launched on iPhone 15 Pro (iOS 17.0, 1B7E3A4C-2D5F-4E6A-8B9C-0D1E2F3A4B5C), booted first"
+    );
+
+    let mut runner = fake_with("not json");
+    let report = deploy_step(
+        true,
+        "aarch64-apple-ios-sim",
+        &mut runner,
+        &DeviceQuery::default(),
+        Path::new("App.app"),
+        "org.example.app",
+    );
+    assert!(report.is_failure());
+}
@@ -0,0 +1,90 @@
+use ios_bundle::plist::{from_xml, to_xml};
+use ios_bundle::{fingerprint, write_if_stale, IosMetadata, StampOutcome};
+use plist::Value;
+use std::fs;
+
+fn plist_for(version: &str) -> String {
+    let metadata = IosMetadata::from_manifest(
+        "[package.metadata.ios]\nbundle-id = \"org.example.app\"\n",
+        "app",
+        version,
+    )
+    .unwrap();
+    to_xml(&metadata.info_plist()).unwrap()
+}
+
+#[test]
+fn stale_plist_is_replaced() {
+    let dir = tempfile::tempdir().unwrap();
+    let plist = dir.path().join("App.app/Info.plist");
+    let stamp = dir.path().join("out/Info.plist.stamp");
+
+    let old = plist_for("1.0.0");
+    let outcome = write_if_stale(&plist, &stamp, &[old.as_bytes()], old.as_bytes()).unwrap();
+    assert_eq!(outcome, StampOutcome::Created);
+
+    let new = plist_for("1.1.0");
+    let outcome = write_if_stale(&plist, &stamp, &[new.as_bytes()], new.as_bytes()).unwrap();
+    assert_eq!(outcome, StampOutcome::Replaced);
+
+    let written = from_xml(&fs::read_to_string(&plist).unwrap()).unwrap();
+    assert_eq!(
+        written
+            .as_dictionary()
+            .and_then(|dict| dict.get("CFBundleShortVersionString"))
+            .and_then(Value::as_string),
+        Some("1.1.0")
+    );
+}
+
+#[test]
+fn unchanged_inputs_leave_output_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("Info.plist");
+    let stamp = dir.path().join("Info.plist.stamp");
+
+    write_if_stale(&output, &stamp, &[b"inputs"], b"first").unwrap();
+    let outcome = write_if_stale(&output, &stamp, &[b"inputs"], b"second").unwrap();
+
+    assert_eq!(outcome, StampOutcome::UpToDate);
+    assert_eq!(fs::read(&output).unwrap(), b"first");
+}
+
+#[test]
+fn missing_output_is_rewritten_even_with_matching_stamp() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("Info.plist");
+    let stamp = dir.path().join("Info.plist.stamp");
+
+    write_if_stale(&output, &stamp, &[b"inputs"], b"contents").unwrap();
+    fs::remove_file(&output).unwrap();
+
+    let outcome = write_if_stale(&output, &stamp, &[b"inputs"], b"contents").unwrap();
+    assert_eq!(outcome, StampOutcome::Created);
+    assert!(output.exists());
+}
+
+#[test]
+fn plist_without_stamp_is_treated_as_stale() {
+    // What an old build.rs, which never wrote stamps, left behind.
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("Info.plist");
+    fs::write(&output, "hand-written").unwrap();
+
+    let outcome = write_if_stale(
+        &output,
+        &dir.path().join("Info.plist.stamp"),
+        &[b"inputs"],
+        b"generated",
+    )
+    .unwrap();
+
+    assert_eq!(outcome, StampOutcome::Replaced);
+    assert_eq!(fs::read(&output).unwrap(), b"generated");
+}
+
+#[test]
+fn fingerprint_separates_inputs() {
+    assert_ne!(fingerprint(&[b"ab", b"c"]), fingerprint(&[b"a", b"bc"]));
+    assert_eq!(fingerprint(&[b"x"]), fingerprint(&[b"x"]));
+}
@@ -0,0 +1,363 @@
+//! The fixtures are `rustc` output for `fixtures/symbols/src` (built for
+//! `aarch64-apple-ios` and `x86_64-apple-ios` at opt-level 1 with full
+//! debug info and legacy symbol names), linked at the usual
+//! `0x100004000` and laid out as a dSYM would be:
+//!
+//! - `crashy`: DWARF 4, arm64
+//! - `crashy-universal`: DWARF 5 arm64 and DWARF 4 x86_64
+//! - `crashy-executable`: the arm64 binary `crashy` belongs to, symbol table
+//!   only
+
+use ios_bundle::{
+    archive_dsym, build_uuids, demangle, dsym_dwarf_files, find_or_extract_dsym, find_symbols,
+    BuildUuid, CommandError, CommandOutput, DebugInfo, FakeRunner, Frame, SymbolError,
+    Symbolicator,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DSYM: &[u8] = include_bytes!("fixtures/symbols/crashy");
+const UNIVERSAL: &[u8] = include_bytes!("fixtures/symbols/crashy-universal");
+const EXECUTABLE: &[u8] = include_bytes!("fixtures/symbols/crashy-executable");
+
+const ARM64: &str = "8B3C1A2E-D4F0-463B-9C5E-7A10B2C3D4E5";
+const UNIVERSAL_ARM64: &str = "1F2E3D4C-5B6A-4798-8796-A5B4C3D2E1F0";
+const UNIVERSAL_X86_64: &str = "01234567-89AB-4CDE-BF01-23456789ABCD";
+
+const LIB: &str = "/Users/dev/rust-ios-hello/src/lib.rs";
+const BUTTON: &str = "/Users/dev/rust-ios-hello/src/ui/button.rs";
+
+fn uuid(text: &str) -> BuildUuid {
+    BuildUuid::parse(text).unwrap()
+}
+
+fn only(bytes: &[u8]) -> DebugInfo {
+    let mut infos = DebugInfo::parse(bytes).unwrap();
+    assert_eq!(infos.len(), 1);
+    infos.remove(0)
+}
+
+/// Frames as `function (file:line)`, with inlined ones marked.
+fn describe(frames: &[Frame]) -> Vec<String> {
+    frames
+        .iter()
+        .map(|frame| {
+            if frame.inlined {
+                format!("{} [inlined]", frame)
+            } else {
+                frame.to_string()
+            }
+        })
+        .collect()
+}
+
+/// A dSYM bundle holding `dwarf`, as `dsymutil` lays it out.
+fn write_dsym(path: &Path, name: &str, dwarf: &[u8]) {
+    let dir = path.join("Contents/Resources/DWARF");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(name), dwarf).unwrap();
+}
+
+#[test]
+fn uuids_read_any_spelling_and_print_like_dwarfdump() {
+    let expected = uuid(ARM64);
+    for spelling in [
+        "8b3c1a2e-d4f0-463b-9c5e-7a10b2c3d4e5",
+        "<8b3c1a2ed4f0463b9c5e7a10b2c3d4e5>",
+        "8B3C1A2ED4F0463B9C5E7A10B2C3D4E5",
+    ] {
+        assert_eq!(BuildUuid::parse(spelling), Some(expected), "{}", spelling);
+    }
+    assert_eq!(expected.to_string(), ARM64);
+    assert_eq!(BuildUuid::parse("8b3c1a2e"), None);
+    assert_eq!(BuildUuid::parse("zz3c1a2ed4f0463b9c5e7a10b2c3d4e5"), None);
+}
+
+#[test]
+fn symbols_are_demangled_without_their_hash() {
+    assert_eq!(
+        demangle("__ZN6crashy2ui6button10handle_tap17hc631cfe00ab2b1ccE").as_deref(),
+        Some("crashy::ui::button::handle_tap")
+    );
+    assert_eq!(
+        demangle(
+            "_ZN64_$LT$rust_ios_hello..ui..Counter$u20$as$u20$core..fmt..Debug$GT$3fmt17h0123456789abcdefE"
+        )
+        .as_deref(),
+        Some("<rust_ios_hello::ui::Counter as core::fmt::Debug>::fmt")
+    );
+    assert_eq!(
+        demangle("__RNvNtCs1234_6crashy2ui5twice").as_deref(),
+        Some("crashy::ui::twice")
+    );
+    assert_eq!(demangle("_rust_gui_pump"), None);
+    assert_eq!(demangle("_ZN6crashy"), None);
+}
+
+#[test]
+fn dsym_knows_its_build() {
+    let info = only(DSYM);
+    assert_eq!(info.uuid, uuid(ARM64));
+    assert_eq!(info.architecture, "arm64");
+    assert_eq!(info.text, (0x1_0000_0000, 0x1_0000_8000));
+    assert_eq!(
+        build_uuids(EXECUTABLE).unwrap(),
+        [("arm64".to_string(), uuid(ARM64))]
+    );
+}
+
+#[test]
+fn addresses_map_to_rust_functions_and_lines() {
+    let info = only(DSYM);
+    assert_eq!(
+        describe(&info.lookup(0x1_0000_4000)),
+        [format!("crashy::ui::button::handle_tap ({}:2)", BUTTON)]
+    );
+    assert_eq!(
+        describe(&info.lookup(0x1_0000_4010)),
+        [format!("crashy::ui::button::handle_tap ({}:3)", BUTTON)]
+    );
+    // `#[no_mangle]` functions have no linkage name, so they go by their
+    // plain name, as in the symbol table.
+    assert_eq!(
+        describe(&info.lookup(0x1_0000_4047)),
+        [format!("rust_gui_pump ({}:30)", LIB)]
+    );
+    assert_eq!(
+        describe(&info.lookup(0x1_0000_4048)),
+        [format!("rust_gui_pump ({}:31)", LIB)]
+    );
+    // Past the end of the code.
+    assert_eq!(info.lookup(0x1_0000_4068), []);
+    assert_eq!(info.lookup(0x1_0000_3fff), []);
+}
+
+#[test]
+fn inlined_calls_become_extra_frames() {
+    let info = only(DSYM);
+    assert_eq!(
+        describe(&info.lookup(0x1_0000_4018)),
+        [
+            format!("crashy::report ({}:25) [inlined]", LIB),
+            format!("crashy::ui::button::handle_tap ({}:4)", BUTTON),
+        ]
+    );
+}
+
+#[test]
+fn universal_dsyms_hold_each_architecture() {
+    let infos = DebugInfo::parse(UNIVERSAL).unwrap();
+    let summary: Vec<(String, String)> = infos
+        .iter()
+        .map(|info| (info.architecture.clone(), info.uuid.to_string()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("arm64".to_string(), UNIVERSAL_ARM64.to_string()),
+            ("x86_64".to_string(), UNIVERSAL_X86_64.to_string()),
+        ]
+    );
+
+    // DWARF 5 reads the same as the DWARF 4 build of the same code.
+    let dwarf4 = only(DSYM);
+    for address in [0x1_0000_4000, 0x1_0000_4018, 0x1_0000_4024, 0x1_0000_4060] {
+        assert_eq!(
+            infos[0].lookup(address),
+            dwarf4.lookup(address),
+            "{:#x}",
+            address
+        );
+    }
+
+    // Intel code is laid out differently.
+    assert_eq!(
+        describe(&infos[1].lookup(0x1_0000_4010)),
+        [
+            format!("crashy::report ({}:25) [inlined]", LIB),
+            format!("crashy::ui::button::handle_tap ({}:4)", BUTTON),
+        ]
+    );
+    assert_eq!(
+        describe(&infos[1].lookup(0x1_0000_403d)),
+        [format!("rust_gui_pump ({}:31)", LIB)]
+    );
+}
+
+#[test]
+fn symbol_table_names_functions_without_debug_info() {
+    let info = only(EXECUTABLE);
+    assert_eq!(
+        describe(&info.lookup(0x1_0000_4018)),
+        ["crashy::ui::button::handle_tap"]
+    );
+    assert_eq!(describe(&info.lookup(0x1_0000_4040)), ["rust_gui_pump"]);
+    assert_eq!(info.lookup(0x1_0000_4068), []);
+}
+
+#[test]
+fn crash_addresses_are_relative_to_the_load_address() {
+    let info = only(DSYM);
+    let load_address = 0x1_04a2_8000;
+    assert_eq!(
+        info.lookup_loaded(load_address, load_address + 0x4048),
+        info.lookup(0x1_0000_4048)
+    );
+    assert_eq!(info.lookup_loaded(load_address, 0x1000), []);
+}
+
+#[test]
+fn other_files_are_rejected() {
+    assert!(matches!(
+        DebugInfo::parse(b"GIF89a"),
+        Err(SymbolError::NotMachO)
+    ));
+    assert!(matches!(
+        DebugInfo::parse(&DSYM[..600]),
+        Err(SymbolError::Corrupt(_))
+    ));
+    assert!(matches!(
+        DebugInfo::parse(&UNIVERSAL[..100]),
+        Err(SymbolError::Corrupt(_))
+    ));
+}
+
+#[test]
+fn dsym_from_the_build_is_used_when_it_matches() {
+    let dir = tempfile::tempdir().unwrap();
+    let executable = dir.path().join("rust_ios_hello");
+    fs::write(&executable, EXECUTABLE).unwrap();
+    let beside = dir.path().join("rust_ios_hello.dSYM");
+    write_dsym(&beside, "rust_ios_hello", DSYM);
+
+    let mut runner = FakeRunner::new();
+    let mut steps = Vec::new();
+    let dsym = find_or_extract_dsym(
+        &mut runner,
+        &executable,
+        &dir.path().join("bundle"),
+        &mut |step| steps.push(step.detail.clone()),
+    )
+    .unwrap();
+    assert_eq!(dsym, beside);
+    assert!(runner.calls().is_empty());
+    assert_eq!(steps, [format!("{} (from the build)", beside.display())]);
+}
+
+#[test]
+fn stale_dsym_is_extracted_again() {
+    let dir = tempfile::tempdir().unwrap();
+    let executable = dir.path().join("rust_ios_hello");
+    fs::write(&executable, EXECUTABLE).unwrap();
+    // Left over from an earlier build.
+    write_dsym(
+        &dir.path().join("rust_ios_hello.dSYM"),
+        "rust_ios_hello",
+        UNIVERSAL,
+    );
+    let work_dir = dir.path().join("bundle");
+
+    let mut runner = FakeRunner::new();
+    let result = find_or_extract_dsym(&mut runner, &executable, &work_dir, &mut |_| {});
+    let dsym = work_dir.join("rust_ios_hello.dSYM");
+    assert_eq!(
+        runner.calls(),
+        [vec![
+            "dsymutil".to_string(),
+            executable.display().to_string(),
+            "-o".to_string(),
+            dsym.display().to_string(),
+        ]]
+    );
+    // The fake dsymutil writes nothing.
+    assert!(matches!(result, Err(SymbolError::Missing(message)) if message.contains("dsymutil")));
+
+    let mut runner = FakeRunner::new();
+    runner.respond(
+        &["dsymutil"],
+        CommandOutput::failure("error: unable to open executable"),
+    );
+    assert!(matches!(
+        find_or_extract_dsym(&mut runner, &executable, &work_dir, &mut |_| {}),
+        Err(SymbolError::Command(CommandError::Failed { stderr, .. })) if stderr.contains("unable to open")
+    ));
+}
+
+#[test]
+fn binaries_without_a_uuid_cannot_be_archived() {
+    let dir = tempfile::tempdir().unwrap();
+    let executable = dir.path().join("rust_ios_hello");
+    // Blank out LC_UUID (0x1b) so the command is skipped.
+    let mut bytes = EXECUTABLE.to_vec();
+    bytes[32] = 0x7f;
+    fs::write(&executable, bytes).unwrap();
+
+    let result = find_or_extract_dsym(&mut FakeRunner::new(), &executable, dir.path(), &mut |_| {});
+    assert!(
+        matches!(result, Err(SymbolError::Missing(message)) if message.contains("no build UUID"))
+    );
+}
+
+#[test]
+fn archive_is_keyed_by_build_uuid() {
+    let dir = tempfile::tempdir().unwrap();
+    let dsym = dir.path().join("rust_ios_hello.dSYM");
+    write_dsym(&dsym, "rust_ios_hello", UNIVERSAL);
+    let archive = dir.path().join("symbols");
+
+    let mut steps = Vec::new();
+    let archived =
+        archive_dsym(&dsym, &archive, &mut |step| steps.push(step.detail.clone())).unwrap();
+    let paths: Vec<PathBuf> = archived.iter().map(|a| a.path.clone()).collect();
+    assert_eq!(
+        paths,
+        [
+            archive.join(UNIVERSAL_ARM64).join("rust_ios_hello.dSYM"),
+            archive.join(UNIVERSAL_X86_64).join("rust_ios_hello.dSYM"),
+        ]
+    );
+    assert_eq!(
+        find_symbols(&archive, uuid(UNIVERSAL_X86_64)),
+        Some(dsym_dwarf_files(&paths[1]).unwrap().remove(0))
+    );
+    assert_eq!(find_symbols(&archive, uuid(ARM64)), None);
+    assert!(steps[0].starts_with(&format!("{} (arm64) -> ", UNIVERSAL_ARM64)));
+
+    // The same build again is left alone.
+    steps.clear();
+    archive_dsym(&dsym, &archive, &mut |step| steps.push(step.detail.clone())).unwrap();
+    assert_eq!(
+        steps,
+        [
+            format!("{} (arm64) already archived", UNIVERSAL_ARM64),
+            format!("{} (x86_64) already archived", UNIVERSAL_X86_64),
+        ]
+    );
+}
+
+#[test]
+fn symbolicator_reads_the_archive() {
+    let dir = tempfile::tempdir().unwrap();
+    let dsym = dir.path().join("rust_ios_hello.dSYM");
+    write_dsym(&dsym, "rust_ios_hello", DSYM);
+    let archive = dir.path().join("symbols");
+    archive_dsym(&dsym, &archive, &mut |_| {}).unwrap();
+
+    let mut symbolicator = Symbolicator::new(&archive);
+    let frames = symbolicator
+        .symbolicate(uuid(ARM64), 0x1_04a2_8000, 0x1_04a2_c018)
+        .unwrap()
+        .unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(
+        frames[1].function.as_deref(),
+        Some("crashy::ui::button::handle_tap")
+    );
+    assert_eq!(frames[1].line, Some(4));
+    assert_eq!(
+        symbolicator
+            .symbolicate(uuid(UNIVERSAL_ARM64), 0x1_04a2_8000, 0x1_04a2_c018)
+            .unwrap(),
+        None
+    );
+}
@@ -0,0 +1,202 @@
+use ios_bundle::plist::from_xml;
+use ios_bundle::{
+    create_xcframework, deploy_step, is_simulator_target, lipo, module_map, xcframework_info_plist,
+    AppleTarget, CommandOutput, DeployReport, DeviceQuery, FakeRunner, LibrarySlice,
+    XcframeworkError, XcframeworkSpec,
+};
+use plist::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `xcodebuild -create-xcframework` output for the same three libraries.
+const XCODE_PLIST: &str = include_str!("fixtures/xcframework/Info.plist");
+
+fn target(triple: &str) -> AppleTarget {
+    AppleTarget::parse(triple).unwrap()
+}
+
+fn builds() -> Vec<(AppleTarget, PathBuf)> {
+    [
+        "x86_64-apple-ios",
+        "aarch64-apple-ios",
+        "aarch64-apple-ios-sim",
+    ]
+    .iter()
+    .map(|triple| {
+        (
+            target(triple),
+            Path::new("target")
+                .join(triple)
+                .join("release/librust_ios_hello.a"),
+        )
+    })
+    .collect()
+}
+
+fn spec(headers: Vec<PathBuf>) -> XcframeworkSpec {
+    XcframeworkSpec {
+        library_name: "librust_ios_hello.a".to_string(),
+        headers,
+        module_name: Some("RustIosHello".to_string()),
+        slices: LibrarySlice::group(&builds()).unwrap(),
+    }
+}
+
+#[test]
+fn targets_know_their_platform() {
+    assert_eq!(target("x86_64-apple-ios").architecture, "x86_64");
+    assert!(is_simulator_target("x86_64-apple-ios"));
+    assert!(is_simulator_target("aarch64-apple-ios-sim"));
+    assert!(!is_simulator_target("aarch64-apple-ios"));
+    assert!(!is_simulator_target("aarch64-apple-darwin"));
+    assert_eq!(AppleTarget::parse("aarch64-apple-darwin"), None);
+}
+
+#[test]
+fn intel_simulator_builds_can_be_deployed() {
+    let report = deploy_step(
+        true,
+        "x86_64-apple-ios",
+        &mut FakeRunner::new(),
+        &DeviceQuery::default(),
+        Path::new("App.app"),
+        "org.example.app",
+    );
+    // The fake returns no devices, so it gets as far as picking one.
+    assert!(matches!(report, DeployReport::Failed(_)), "{}", report);
+}
+
+#[test]
+fn builds_are_grouped_per_platform() {
+    let slices = LibrarySlice::group(&builds()).unwrap();
+    assert_eq!(
+        slices
+            .iter()
+            .map(|slice| (slice.identifier.as_str(), slice.architectures.clone()))
+            .collect::<Vec<_>>(),
+        [
+            ("ios-arm64", vec!["arm64"]),
+            ("ios-arm64_x86_64-simulator", vec!["arm64", "x86_64"]),
+        ]
+    );
+    assert_eq!(
+        slices[1].inputs,
+        [
+            Path::new("target/aarch64-apple-ios-sim/release/librust_ios_hello.a"),
+            Path::new("target/x86_64-apple-ios/release/librust_ios_hello.a"),
+        ]
+    );
+
+    let duplicate = [
+        (target("aarch64-apple-ios-sim"), PathBuf::from("a.a")),
+        (target("aarch64-apple-ios-sim"), PathBuf::from("b.a")),
+    ];
+    assert_eq!(
+        LibrarySlice::group(&duplicate),
+        Err(XcframeworkError::Targets(
+            "aarch64-apple-ios-sim and aarch64-apple-ios-sim both build arm64 for the simulator"
+                .to_string()
+        ))
+    );
+}
+
+#[test]
+fn info_plist_matches_xcodebuild() {
+    let plist = xcframework_info_plist(&spec(vec!["include/rust_ios_hello.h".into()]));
+    assert_eq!(plist, from_xml(XCODE_PLIST).unwrap());
+
+    // Without headers there is no HeadersPath.
+    let plist = xcframework_info_plist(&spec(Vec::new()));
+    let libraries = plist
+        .as_dictionary()
+        .and_then(|dict| dict.get("AvailableLibraries"))
+        .and_then(Value::as_array)
+        .unwrap();
+    assert!(libraries.iter().all(|library| library
+        .as_dictionary()
+        .is_some_and(|library| !library.contains_key("HeadersPath"))));
+}
+
+#[test]
+fn module_map_exports_the_headers() {
+    assert_eq!(
+        module_map("RustIosHello", &["include/rust_ios_hello.h".into()]),
+        "module RustIosHello {\n    header \"rust_ios_hello.h\"\n    export *\n}\n"
+    );
+}
+
+#[test]
+fn xcframework_has_one_directory_per_slice() {
+    let dir = tempfile::tempdir().unwrap();
+    let header = dir.path().join("rust_ios_hello.h");
+    fs::write(&header, "/* header */").unwrap();
+    let mut spec = spec(vec![header]);
+    for slice in &mut spec.slices {
+        for input in &mut slice.inputs {
+            *input = dir.path().join(&*input);
+            fs::create_dir_all(input.parent().unwrap()).unwrap();
+            fs::write(&input, "!<arch>\n").unwrap();
+        }
+    }
+    let out = dir.path().join("RustIosHello.xcframework");
+    fs::create_dir_all(out.join("stale")).unwrap();
+
+    let mut runner = FakeRunner::new();
+    let mut steps = Vec::new();
+    create_xcframework(&mut runner, &spec, &out, &mut |step| {
+        steps.push(step.detail.clone())
+    })
+    .unwrap();
+
+    for file in [
+        "Info.plist",
+        "ios-arm64/librust_ios_hello.a",
+        "ios-arm64/Headers/rust_ios_hello.h",
+        "ios-arm64/Headers/module.modulemap",
+        "ios-arm64_x86_64-simulator/Headers/rust_ios_hello.h",
+    ] {
+        assert!(out.join(file).is_file(), "{} is missing", file);
+    }
+    assert!(!out.join("stale").exists());
+    assert_eq!(steps[0], "ios-arm64 (arm64)");
+    assert_eq!(steps[1], "ios-arm64_x86_64-simulator (arm64, x86_64)");
+
+    // The device library is copied, the simulator ones are joined.
+    let simulator = &spec.slices[1];
+    assert_eq!(
+        runner.calls(),
+        [vec![
+            "lipo".to_string(),
+            "-create".to_string(),
+            simulator.inputs[0].display().to_string(),
+            simulator.inputs[1].display().to_string(),
+            "-output".to_string(),
+            out.join("ios-arm64_x86_64-simulator/librust_ios_hello.a")
+                .display()
+                .to_string(),
+        ]]
+    );
+    let written = from_xml(&fs::read_to_string(out.join("Info.plist")).unwrap()).unwrap();
+    assert_eq!(written, xcframework_info_plist(&spec));
+}
+
+#[test]
+fn lipo_failure_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut runner = FakeRunner::new();
+    runner.respond(
+        &["lipo"],
+        CommandOutput::failure(
+            "fatal error: lipo: a.a and b.a have the same architectures (arm64)\n",
+        ),
+    );
+    let error = lipo(
+        &mut runner,
+        &["a.a".into(), "b.a".into()],
+        &dir.path().join("fat.a"),
+    )
+    .unwrap_err();
+    assert!(error
+        .to_string()
+        .ends_with("have the same architectures (arm64)"));
+}
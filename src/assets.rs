@@ -0,0 +1,43 @@
+//! Where the app reads its asset files from.
+//!
+//! On desktop, Bevy resolves the `assets` directory against
+//! `CARGO_MANIFEST_DIR` (set by `cargo run`) or the executable's directory,
+//! so the files in the repository are used directly. On iOS they are copied
+//! into the app bundle by `cargo xtask bundle`, and the bundle's resources
+//! directory is the one holding the executable. Resolving that explicitly
+//! keeps a `CARGO_MANIFEST_DIR` or `BEVY_ASSET_ROOT` inherited from the
+//! launching tool from pointing the app at the Mac's file system.
+
+use bevy::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// Name of the asset directory, in the package and inside the bundle.
+pub const ASSET_DIR: &str = "assets";
+
+/// The asset directory of the app bundle containing `executable`.
+pub fn bundle_asset_root(executable: &Path) -> PathBuf {
+    executable
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(ASSET_DIR)
+}
+
+/// `AssetPlugin::file_path` for the platform being built for.
+pub fn asset_root() -> String {
+    #[cfg(target_os = "ios")]
+    if let Ok(executable) = std::env::current_exe() {
+        return bundle_asset_root(&executable)
+            .to_string_lossy()
+            .into_owned();
+    }
+    ASSET_DIR.to_string()
+}
+
+/// `AssetPlugin` reading from [`asset_root`]. Use it in place of the default
+/// one: `DefaultPlugins.set(asset_plugin())`.
+pub fn asset_plugin() -> AssetPlugin {
+    AssetPlugin {
+        file_path: asset_root(),
+        ..default()
+    }
+}
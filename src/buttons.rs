@@ -0,0 +1,413 @@
+//! Data-driven buttons.
+//!
+//! A button is a `Button` entity with an [`ActionButton`] saying what it does
+//! and a [`ButtonPalette`] saying how it looks. Buttons that also carry a
+//! [`ButtonRole`] get their palette from the current [`Theme`].
+//! [`style_buttons`] colors every button from its palette, and presses are
+//! sent as [`ButtonActivated`] messages that [`dispatch_button_actions`] turns
+//! into state changes. Buttons with an [`ActionButton::gesture`] are activated
+//! by that gesture instead of the press.
+//!
+//! Like UIKit's "touch up inside", a press activates its button when the
+//! pointer is released over it. Dragging off cancels the press, dragging back
+//! on resumes it. Each press follows the finger or mouse that started it
+//! ([`ButtonPress`]), because Bevy keeps `Interaction::Pressed` after the
+//! pointer leaves and clears it when any finger lifts.
+
+use bevy::input::touch::Touches;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::Deserialize;
+
+use crate::dialog::{confirm_dialog, Confirmation, Modal};
+use crate::focus::Focusable;
+use crate::gestures::{Gesture, GestureRecognized, GestureRecognizer};
+use crate::history::{undo_or_redo, GuiCommand, History};
+use crate::theme::{toggle_theme, ButtonRole, Theme, ThemeSettings, Themes};
+use crate::ui::ClickButton;
+use crate::GuiState;
+
+/// What a button does when pressed.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub enum ButtonAction {
+    Click,
+    DoubleClick,
+    SpecialEffect,
+    Info,
+    Reset,
+    Undo,
+    Redo,
+    /// Switches between the dark and light [`Theme`](crate::Theme).
+    ToggleTheme,
+    /// Closes the dialog the button is in without doing anything.
+    Cancel,
+    /// An action defined by the host app. It is sent as a [`ButtonActivated`]
+    /// message but otherwise ignored by this crate.
+    Custom(String),
+}
+
+impl ButtonAction {
+    /// The gesture that activates buttons with this action, `None` for a
+    /// plain press.
+    pub fn gesture(&self) -> Option<Gesture> {
+        match self {
+            ButtonAction::DoubleClick => Some(Gesture::DoubleTap),
+            _ => None,
+        }
+    }
+
+    /// What to ask before carrying out this action, `None` for actions that
+    /// run straight away.
+    pub fn confirmation(&self) -> Option<Confirmation> {
+        match self {
+            ButtonAction::Reset => Some(Confirmation {
+                title: "Reset GUI?",
+                message: "The click count, button state and message go back to their defaults.",
+                confirm: "Reset",
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Marks a `Button` entity as one driven by [`ButtonAction`].
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+#[require(ButtonPress)]
+pub struct ActionButton {
+    pub action: ButtonAction,
+    /// Disabled buttons are drawn with [`ButtonPalette::disabled`] and ignore
+    /// presses.
+    pub disabled: bool,
+    /// Activates the button when recognized, instead of when pressed.
+    pub gesture: Option<Gesture>,
+}
+
+impl ActionButton {
+    pub fn new(action: ButtonAction) -> Self {
+        Self {
+            gesture: action.gesture(),
+            action,
+            disabled: false,
+        }
+    }
+}
+
+/// What is pressing an [`ActionButton`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressPointer {
+    Mouse,
+    Touch(u64),
+    /// `Interaction` was set to `Pressed` without a pointer, e.g. by a test
+    /// or host app. Released when `Interaction` changes again.
+    Interaction,
+}
+
+/// The press in progress on an [`ActionButton`].
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ButtonPress {
+    #[default]
+    Released,
+    /// Held by `pointer`, which is over the button if `inside`.
+    Held { pointer: PressPointer, inside: bool },
+}
+
+impl ButtonPress {
+    /// How the button should look while `interaction` is Bevy's idea of it.
+    pub fn interaction(&self, interaction: Interaction) -> Interaction {
+        match *self {
+            ButtonPress::Held { inside: true, .. } => Interaction::Pressed,
+            ButtonPress::Held { inside: false, .. } => Interaction::None,
+            ButtonPress::Released if interaction == Interaction::Pressed => Interaction::Hovered,
+            ButtonPress::Released => interaction,
+        }
+    }
+}
+
+/// Background colors of a button in each interaction state.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ButtonPalette {
+    pub normal: Color,
+    pub hovered: Color,
+    pub pressed: Color,
+    pub disabled: Color,
+}
+
+impl ButtonPalette {
+    pub fn color(&self, interaction: Interaction, disabled: bool) -> Color {
+        if disabled {
+            return self.disabled;
+        }
+        match interaction {
+            Interaction::Pressed => self.pressed,
+            Interaction::Hovered => self.hovered,
+            Interaction::None => self.normal,
+        }
+    }
+}
+
+/// Sent when an enabled [`ActionButton`] is pressed.
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct ButtonActivated {
+    pub entity: Entity,
+    pub action: ButtonAction,
+}
+
+/// Everything needed to spawn a labelled action button.
+pub fn action_button(
+    label: impl Into<String>,
+    action: ButtonAction,
+    palette: ButtonPalette,
+    node: Node,
+    font_size: f32,
+) -> impl Bundle {
+    (
+        Button,
+        Node {
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..node
+        },
+        BackgroundColor(palette.normal),
+        palette,
+        ActionButton::new(action),
+        GestureRecognizer::default(),
+        Focusable,
+        children![(
+            Text::new(label),
+            TextColor(Color::WHITE),
+            TextFont {
+                font_size,
+                ..default()
+            },
+        )],
+    )
+}
+
+pub fn style_buttons(
+    mut button_query: Query<
+        (
+            &Interaction,
+            &ButtonPress,
+            &ActionButton,
+            &ButtonPalette,
+            &mut BackgroundColor,
+        ),
+        Or<(
+            Changed<Interaction>,
+            Changed<ButtonPress>,
+            Changed<ActionButton>,
+            Changed<ButtonPalette>,
+        )>,
+    >,
+) {
+    for (interaction, press, button, palette, mut color) in &mut button_query {
+        *color = palette
+            .color(press.interaction(*interaction), button.disabled)
+            .into();
+    }
+}
+
+/// Whether the logical-pixel `point` is over `node`. `None` before layout
+/// has given the node a size, e.g. headless.
+fn node_contains(node: Option<(&ComputedNode, &UiGlobalTransform)>, point: Vec2) -> Option<bool> {
+    let (node, transform) = node.filter(|(node, _)| node.size != Vec2::ZERO)?;
+    Some(node.contains_point(*transform, point / node.inverse_scale_factor))
+}
+
+/// Follows each press from `Interaction::Pressed` to its release and
+/// activates the button if the pointer was released over it.
+pub fn emit_button_actions(
+    touches: Res<Touches>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut button_query: Query<(
+        Entity,
+        Ref<Interaction>,
+        &ActionButton,
+        &mut ButtonPress,
+        Option<(&ComputedNode, &UiGlobalTransform)>,
+    )>,
+    mut activated: MessageWriter<ButtonActivated>,
+) {
+    let cursor = windows
+        .single()
+        .ok()
+        .and_then(|window| window.cursor_position());
+
+    for (entity, interaction, button, mut press, node) in &mut button_query {
+        let ButtonPress::Held { pointer, inside } = *press else {
+            if interaction.is_changed() && *interaction == Interaction::Pressed && !button.disabled
+            {
+                // Interaction doesn't say which pointer pressed; it's one
+                // that just went down over the button.
+                let pointer = touches
+                    .iter_just_pressed()
+                    .find(|touch| node_contains(node, touch.position()) != Some(false))
+                    .map(|touch| PressPointer::Touch(touch.id()))
+                    .or_else(|| {
+                        mouse
+                            .just_pressed(MouseButton::Left)
+                            .then_some(PressPointer::Mouse)
+                    })
+                    .unwrap_or(PressPointer::Interaction);
+                *press = ButtonPress::Held {
+                    pointer,
+                    inside: true,
+                };
+            }
+            continue;
+        };
+
+        // A touch can end without being released, when the system cancels
+        // it; that never activates.
+        let (position, ended, not_released) = match pointer {
+            PressPointer::Touch(id) => {
+                let canceled = touches.iter_just_canceled().find(|t| t.id() == id);
+                let touch = touches
+                    .get_pressed(id)
+                    .or_else(|| touches.get_released(id))
+                    .or(canceled);
+                (
+                    touch.map(|touch| touch.position()),
+                    touches.get_pressed(id).is_none(),
+                    touches.get_released(id).is_none(),
+                )
+            }
+            PressPointer::Mouse => (cursor, !mouse.pressed(MouseButton::Left), false),
+            PressPointer::Interaction => (None, *interaction != Interaction::Pressed, false),
+        };
+        let inside = match position {
+            Some(position) => node_contains(node, position).unwrap_or(true),
+            None => inside,
+        };
+
+        if !ended {
+            press.set_if_neq(ButtonPress::Held { pointer, inside });
+            continue;
+        }
+        *press = ButtonPress::Released;
+        if inside && !not_released && !button.disabled && button.gesture.is_none() {
+            activated.write(ButtonActivated {
+                entity,
+                action: button.action.clone(),
+            });
+        }
+    }
+}
+
+/// Activates buttons whose [`ActionButton::gesture`] was just recognized.
+pub fn emit_gesture_actions(
+    mut recognized: MessageReader<GestureRecognized>,
+    button_query: Query<&ActionButton>,
+    mut activated: MessageWriter<ButtonActivated>,
+) {
+    for GestureRecognized { entity, gesture } in recognized.read() {
+        let Ok(button) = button_query.get(*entity) else {
+            continue;
+        };
+        if button.gesture == Some(*gesture) && !button.disabled {
+            activated.write(ButtonActivated {
+                entity: *entity,
+                action: button.action.clone(),
+            });
+        }
+    }
+}
+
+pub fn dispatch_button_actions(
+    mut commands: Commands,
+    mut activated: MessageReader<ButtonActivated>,
+    modal: Modal,
+    mut gui_state: ResMut<GuiState>,
+    mut history: ResMut<History>,
+    mut theme: ResMut<Theme>,
+    themes: Res<Themes>,
+    mut theme_settings: ResMut<ThemeSettings>,
+) {
+    // A dialog spawned this frame isn't visible to `modal` until the next.
+    let mut asking = modal.dialog().is_some();
+    for ButtonActivated { entity, action } in activated.read() {
+        if modal.blocks(*entity) {
+            continue;
+        }
+        // Asked from a button on the screen; the dialog's confirm button
+        // carries the same action and gets past this.
+        if let Some(confirmation) = action.confirmation() {
+            if !modal.contains(*entity) {
+                if !asking {
+                    commands.spawn(confirm_dialog(action.clone(), &confirmation, &theme));
+                    asking = true;
+                }
+                continue;
+            }
+        }
+        match action {
+            ButtonAction::Click => {
+                history.execute(GuiCommand::Click, &mut gui_state);
+                println!("Button clicked, count: {}", gui_state.click_count);
+            }
+            ButtonAction::DoubleClick => {
+                history.execute(GuiCommand::DoubleClick, &mut gui_state);
+                println!("{}", gui_state.message);
+            }
+            ButtonAction::SpecialEffect => {
+                history.execute(GuiCommand::SpecialEffect, &mut gui_state);
+                println!("{}", gui_state.message);
+            }
+            ButtonAction::Info => {
+                println!(
+                    "GUI Info: {} clicks, state: {}",
+                    gui_state.click_count,
+                    gui_state.state_label()
+                );
+            }
+            ButtonAction::Reset => {
+                // Reset goes through the history so it can be undone
+                history.execute(GuiCommand::Reset, &mut gui_state);
+                println!("GUI Reset!");
+            }
+            ButtonAction::Undo => undo_or_redo(false, &mut history, &mut gui_state),
+            ButtonAction::Redo => undo_or_redo(true, &mut history, &mut gui_state),
+            ButtonAction::ToggleTheme => {
+                toggle_theme(&mut theme, &themes, &mut theme_settings);
+                println!("Theme: {:?}", theme.kind);
+            }
+            ButtonAction::Cancel | ButtonAction::Custom(_) => {}
+        }
+    }
+}
+
+/// Turns the click button green while `button_state` is on.
+pub fn update_click_button_role(
+    gui_state: Res<GuiState>,
+    mut button_query: Query<&mut ButtonRole, With<ClickButton>>,
+) {
+    if !gui_state.is_changed() {
+        return;
+    }
+    let role = if gui_state.button_state {
+        ButtonRole::Active
+    } else {
+        ButtonRole::Primary
+    };
+    for mut current in &mut button_query {
+        current.set_if_neq(role);
+    }
+}
+
+/// Disables Undo/Redo when there is nothing to undo or redo.
+pub fn update_history_buttons(history: Res<History>, mut button_query: Query<&mut ActionButton>) {
+    if !history.is_changed() {
+        return;
+    }
+    for mut button in &mut button_query {
+        let disabled = match button.action {
+            ButtonAction::Undo => !history.can_undo(),
+            ButtonAction::Redo => !history.can_redo(),
+            _ => continue,
+        };
+        if button.disabled != disabled {
+            button.disabled = disabled;
+        }
+    }
+}
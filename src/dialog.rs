@@ -0,0 +1,194 @@
+//! Modal confirmation dialogs.
+//!
+//! Buttons whose action has a [`ButtonAction::confirmation`], like Reset,
+//! don't act straight away: [`dispatch_button_actions`] opens a
+//! [`confirm_dialog`] instead, and the action runs when the dialog's
+//! [`ConfirmButton`] is pressed. The dialog's backdrop covers the screen and
+//! blocks pointers from reaching the tree behind it, and while it is open
+//! only buttons inside it are dispatched or take focus ([`Modal`]). Cancel,
+//! Escape, the gamepad's East button (B on Xbox pads) and tapping the
+//! backdrop around the panel all close it without doing anything.
+//!
+//! [`dispatch_button_actions`]: crate::dispatch_button_actions
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use crate::buttons::{action_button, ButtonAction, ButtonActivated};
+use crate::gestures::{Gesture, GestureRecognized, GestureRecognizer};
+use crate::theme::{ButtonRole, ButtonRow, TextRole, Theme};
+
+/// Dims the screen behind a dialog.
+const BACKDROP: Color = Color::srgba(0.0, 0.0, 0.0, 0.5);
+
+/// What a dialog asks before carrying out an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Confirmation {
+    pub title: &'static str,
+    pub message: &'static str,
+    /// Label of the button that carries out the action.
+    pub confirm: &'static str,
+}
+
+/// The backdrop of an open dialog, and the root of its tree.
+#[derive(Component)]
+pub struct ConfirmDialog;
+
+/// The box holding a dialog's text and buttons; takes the theme background.
+#[derive(Component)]
+pub struct DialogPanel;
+
+#[derive(Component)]
+pub struct ConfirmButton;
+
+#[derive(Component)]
+pub struct CancelButton;
+
+/// A dialog asking to confirm `action`, covering the whole screen.
+pub fn confirm_dialog(
+    action: ButtonAction,
+    confirmation: &Confirmation,
+    theme: &Theme,
+) -> impl Bundle {
+    let text = |text: &str, role: TextRole| {
+        (
+            Text::new(text),
+            TextColor(theme.text),
+            TextFont {
+                font_size: theme.font_size(role),
+                ..default()
+            },
+            role,
+        )
+    };
+    let button = |label: &'static str, action: ButtonAction, role: ButtonRole| {
+        let node = Node {
+            width: Val::Px(120.0),
+            height: Val::Px(50.0),
+            ..default()
+        };
+        (
+            action_button(
+                label,
+                action,
+                theme.palette(role),
+                node,
+                theme.font_sizes.button,
+            ),
+            role,
+        )
+    };
+
+    (
+        ConfirmDialog,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(BACKDROP),
+        // Nodes let pointers through by default.
+        FocusPolicy::Block,
+        // Above the screen, which has no z-index of its own.
+        GlobalZIndex(1),
+        GestureRecognizer::default(),
+        children![(
+            DialogPanel,
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(theme.spacing.row_gap),
+                padding: UiRect::all(Val::Px(theme.spacing.padding)),
+                max_width: Val::Px(360.0),
+                ..default()
+            },
+            BackgroundColor(theme.background),
+            BorderRadius::all(Val::Px(12.0)),
+            // Taps on the text or padding mustn't reach the backdrop.
+            FocusPolicy::Block,
+            children![
+                text(confirmation.title, TextRole::Status),
+                text(confirmation.message, TextRole::Message),
+                (
+                    Node {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(theme.spacing.column_gap),
+                        ..default()
+                    },
+                    ButtonRow,
+                    children![
+                        (
+                            button("Cancel", ButtonAction::Cancel, ButtonRole::Neutral),
+                            CancelButton,
+                        ),
+                        (
+                            button(confirmation.confirm, action, ButtonRole::Danger),
+                            ConfirmButton,
+                        ),
+                    ],
+                ),
+            ],
+        )],
+    )
+}
+
+/// Which entities an open dialog lets through.
+#[derive(SystemParam)]
+pub struct Modal<'w, 's> {
+    dialogs: Query<'w, 's, Entity, With<ConfirmDialog>>,
+    parents: Query<'w, 's, &'static ChildOf>,
+}
+
+impl Modal<'_, '_> {
+    /// The open dialog, if any.
+    pub fn dialog(&self) -> Option<Entity> {
+        self.dialogs.iter().next()
+    }
+
+    /// Whether `entity` is part of the open dialog.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.dialog().is_some_and(|dialog| {
+            entity == dialog || self.parents.iter_ancestors(entity).any(|a| a == dialog)
+        })
+    }
+
+    /// Whether `entity` is behind the open dialog.
+    pub fn blocks(&self, entity: Entity) -> bool {
+        self.dialog().is_some() && !self.contains(entity)
+    }
+}
+
+/// Closes the open dialog once one of its buttons is pressed, or on Escape,
+/// the gamepad's back button or a tap on the backdrop outside the panel.
+pub fn close_dialogs(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    modal: Modal,
+    mut activated: MessageReader<ButtonActivated>,
+    mut gestures: MessageReader<GestureRecognized>,
+) {
+    let Some(dialog) = modal.dialog() else {
+        activated.clear();
+        gestures.clear();
+        return;
+    };
+
+    let mut close = keys.just_pressed(KeyCode::Escape)
+        || gamepads
+            .iter()
+            .any(|pad| pad.just_pressed(GamepadButton::East));
+    for ButtonActivated { entity, .. } in activated.read() {
+        close |= modal.contains(*entity);
+    }
+    for GestureRecognized { entity, gesture } in gestures.read() {
+        close |= *entity == dialog && *gesture == Gesture::Tap;
+    }
+    if close {
+        commands.entity(dialog).despawn();
+    }
+}
@@ -0,0 +1,194 @@
+//! Running the GUI inside a native host app.
+//!
+//! Normally `main()` hands the process to `App::run()`. When the UI lives in
+//! an existing iOS app instead, the host owns the run loop: it starts an
+//! [`EmbeddedApp`] rendering into a view it provides, pumps one frame per
+//! display refresh, pauses and resumes it with the app's lifecycle, and shuts
+//! it down. Pausing sends Bevy's `AppLifecycle` messages so plugins such as
+//! [`PersistencePlugin`](crate::PersistencePlugin) can save before the host is
+//! suspended.
+//!
+//! [`EmbeddedApp::new`] takes any `App` with [`GuiPlugin`](crate::GuiPlugin),
+//! so a headless app built from `MinimalPlugins` drives the same lifecycle in
+//! tests.
+
+use bevy::app::{AppExit, PluginsState};
+use bevy::prelude::*;
+use bevy::window::{
+    AppLifecycle, PrimaryWindow, RawHandleWrapper, WindowResolution, WindowWrapper,
+};
+use raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, RawWindowHandle,
+    UiKitWindowHandle, WindowHandle,
+};
+use std::ffi::c_void;
+use std::ptr::NonNull;
+
+/// Where an [`EmbeddedApp`] is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbedState {
+    Running,
+    /// Frames are skipped until [`EmbeddedApp::resume`].
+    Paused,
+}
+
+/// A Bevy app whose frames are driven by the host.
+pub struct EmbeddedApp {
+    app: App,
+    state: EmbedState,
+}
+
+impl EmbeddedApp {
+    /// Finishes building `app` and runs its first frame, so `Startup` systems
+    /// have run when this returns.
+    pub fn new(mut app: App) -> Self {
+        while app.plugins_state() == PluginsState::Adding {
+            bevy::tasks::tick_global_task_pools_on_main_thread();
+        }
+        app.finish();
+        app.cleanup();
+
+        let mut embedded = Self {
+            app,
+            state: EmbedState::Running,
+        };
+        embedded.send_lifecycle(AppLifecycle::Running);
+        embedded.app.update();
+        embedded
+    }
+
+    pub fn state(&self) -> EmbedState {
+        self.state
+    }
+
+    pub fn app(&self) -> &App {
+        &self.app
+    }
+
+    pub fn app_mut(&mut self) -> &mut App {
+        &mut self.app
+    }
+
+    /// Runs one frame unless paused. Returns `false` once the app has asked to
+    /// exit, after which the host should call [`EmbeddedApp::shutdown`].
+    pub fn pump(&mut self) -> bool {
+        if self.app.should_exit().is_some() {
+            return false;
+        }
+        if self.state == EmbedState::Running {
+            self.app.update();
+        }
+        self.app.should_exit().is_none()
+    }
+
+    /// Gives systems one frame to react to `WillSuspend`, then stops updating.
+    pub fn pause(&mut self) {
+        if self.state == EmbedState::Paused {
+            return;
+        }
+        self.send_lifecycle(AppLifecycle::WillSuspend);
+        self.app.update();
+        self.send_lifecycle(AppLifecycle::Suspended);
+        self.app.update();
+        self.state = EmbedState::Paused;
+    }
+
+    /// Runs a `WillResume` frame; the next [`pump`](Self::pump) is `Running`.
+    pub fn resume(&mut self) {
+        if self.state == EmbedState::Running {
+            return;
+        }
+        self.send_lifecycle(AppLifecycle::WillResume);
+        self.app.update();
+        self.send_lifecycle(AppLifecycle::Running);
+        self.state = EmbedState::Running;
+    }
+
+    /// Updates the size of the primary window, e.g. after the host view rotated.
+    pub fn resize(&mut self, physical_width: u32, physical_height: u32, scale_factor: f32) {
+        let world = self.app.world_mut();
+        let mut windows = world.query_filtered::<&mut Window, With<PrimaryWindow>>();
+        for mut window in windows.iter_mut(world) {
+            window.resolution = WindowResolution::new(physical_width, physical_height)
+                .with_scale_factor_override(scale_factor);
+        }
+    }
+
+    /// Sends `AppExit`, runs a last frame so exit handlers such as saving the
+    /// state get to run, and drops the app.
+    pub fn shutdown(mut self) -> AppExit {
+        if self.app.should_exit().is_none() {
+            self.app.world_mut().write_message(AppExit::Success);
+            self.app.update();
+        }
+        self.app.should_exit().unwrap_or(AppExit::Success)
+    }
+
+    fn send_lifecycle(&mut self, lifecycle: AppLifecycle) {
+        self.app.world_mut().write_message(lifecycle);
+    }
+}
+
+/// A `UIView` lent by the host for the app to render into.
+#[derive(Debug, Clone, Copy)]
+pub struct HostView {
+    pub ui_view: NonNull<c_void>,
+    pub physical_width: u32,
+    pub physical_height: u32,
+    pub scale_factor: f32,
+}
+
+// The view is only touched by the renderer on the main thread, which is where
+// the host drives the app from.
+unsafe impl Send for HostView {}
+unsafe impl Sync for HostView {}
+
+impl HasWindowHandle for HostView {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        let handle = RawWindowHandle::UiKit(UiKitWindowHandle::new(self.ui_view));
+        // SAFETY: the host keeps the view alive until it shuts the app down.
+        Ok(unsafe { WindowHandle::borrow_raw(handle) })
+    }
+}
+
+impl HasDisplayHandle for HostView {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        Ok(DisplayHandle::uikit())
+    }
+}
+
+impl HostView {
+    /// Spawns the primary window backed by this view.
+    pub fn spawn_window(self, world: &mut World) -> Result<Entity, HandleError> {
+        let handle = RawHandleWrapper::new(&WindowWrapper::new(self))?;
+        let window = Window {
+            title: crate::APP_TITLE.to_string(),
+            resolution: WindowResolution::new(self.physical_width, self.physical_height)
+                .with_scale_factor_override(self.scale_factor),
+            ..default()
+        };
+        Ok(world.spawn((window, handle, PrimaryWindow)).id())
+    }
+}
+
+/// The app `main()` would run, but rendering into `view` and without winit,
+/// which would otherwise take over the run loop.
+#[cfg(target_os = "ios")]
+pub fn host_app(view: HostView) -> Result<App, HandleError> {
+    use bevy::window::ExitCondition;
+
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            })
+            .set(crate::asset_plugin())
+            .disable::<bevy::winit::WinitPlugin>(),
+    )
+    .add_plugins(crate::app_plugins());
+    view.spawn_window(app.world_mut())?;
+    Ok(app)
+}
@@ -0,0 +1,253 @@
+//! C API for native host apps.
+//!
+//! The click count and button state are mirrored into a process-wide atomic
+//! every time [`GuiState`] changes, so a Swift or Objective-C shell can read
+//! them without touching Bevy. Both live in one word, so a reader never sees
+//! a count from one change paired with the state from another. Setters and
+//! reset are queued as [`GuiCommand`]s and applied by [`apply_host_commands`]
+//! on the next frame, which keeps them undoable like any other change. The
+//! getters and callbacks only see them once applied; until an app runs, they
+//! simply wait in the queue. `include/rust_ios_hello.h` is generated from
+//! this file by cbindgen.
+//!
+//! State-change callbacks run inside the app's frame that made the change, on
+//! whichever thread Bevy runs that system on; never inside a setter.
+//!
+//! The `rust_gui_start` family runs an [`EmbeddedApp`] inside the host. It is
+//! kept per thread, so all lifecycle calls must come from the thread that
+//! started it, normally the main thread.
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use std::cell::RefCell;
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::embed::EmbeddedApp;
+use crate::history::{GuiCommand, History};
+use crate::GuiState;
+
+/// The published [`RustGuiState`]: the click count in the low 32 bits, the
+/// button state in the next one.
+static STATE: AtomicU64 = AtomicU64::new(0);
+
+/// Commands from the host waiting for the next frame.
+static HOST_COMMANDS: Mutex<Vec<GuiCommand>> = Mutex::new(Vec::new());
+
+static CALLBACKS: Mutex<Vec<Subscription>> = Mutex::new(Vec::new());
+static NEXT_TOKEN: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static EMBEDDED: RefCell<Option<EmbeddedApp>> = const { RefCell::new(None) };
+}
+
+/// The part of [`GuiState`] visible to the host.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RustGuiState {
+    pub click_count: i32,
+    pub button_state: bool,
+}
+
+/// Called with the new state and the `user_data` given at registration.
+pub type RustGuiStateCallback = Option<extern "C" fn(state: RustGuiState, user_data: *mut c_void)>;
+
+#[derive(Clone, Copy)]
+struct Subscription {
+    token: u64,
+    callback: extern "C" fn(state: RustGuiState, user_data: *mut c_void),
+    user_data: *mut c_void,
+}
+
+// `user_data` is owned by the host, which promised it can be used from any
+// thread by registering it.
+unsafe impl Send for Subscription {}
+
+impl RustGuiState {
+    fn pack(self) -> u64 {
+        (self.click_count as u32 as u64) | ((self.button_state as u64) << 32)
+    }
+
+    fn unpack(bits: u64) -> RustGuiState {
+        RustGuiState {
+            click_count: bits as u32 as i32,
+            button_state: bits & (1 << 32) != 0,
+        }
+    }
+}
+
+/// Updates the mirrored state and notifies subscribers if it changed.
+pub(crate) fn publish(click_count: i32, button_state: bool) {
+    let state = RustGuiState {
+        click_count,
+        button_state,
+    };
+    if STATE.swap(state.pack(), Ordering::SeqCst) == state.pack() {
+        return;
+    }
+
+    // Copy the list so callbacks may register or unregister.
+    let subscriptions = CALLBACKS.lock().unwrap().clone();
+    for subscription in subscriptions {
+        (subscription.callback)(state, subscription.user_data);
+    }
+}
+
+fn queue(command: GuiCommand) {
+    HOST_COMMANDS.lock().unwrap().push(command);
+}
+
+/// Applies setters and resets requested through the C API.
+pub fn apply_host_commands(mut history: ResMut<History>, mut gui_state: ResMut<GuiState>) {
+    let commands = std::mem::take(&mut *HOST_COMMANDS.lock().unwrap());
+    for command in commands {
+        history.execute(command, &mut gui_state);
+        println!("Host command: {:?}", command);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rust_gui_click_count() -> i32 {
+    rust_gui_state().click_count
+}
+
+#[no_mangle]
+pub extern "C" fn rust_gui_button_state() -> bool {
+    rust_gui_state().button_state
+}
+
+#[no_mangle]
+pub extern "C" fn rust_gui_state() -> RustGuiState {
+    RustGuiState::unpack(STATE.load(Ordering::SeqCst))
+}
+
+/// Setters and reset take effect, and show in the getters and callbacks, on
+/// the app's next frame.
+#[no_mangle]
+pub extern "C" fn rust_gui_set_click_count(click_count: i32) {
+    queue(GuiCommand::SetClickCount(click_count));
+}
+
+#[no_mangle]
+pub extern "C" fn rust_gui_set_button_state(button_state: bool) {
+    queue(GuiCommand::SetButtonState(button_state));
+}
+
+#[no_mangle]
+pub extern "C" fn rust_gui_reset() {
+    queue(GuiCommand::Reset);
+}
+
+/// Returns a token for [`rust_gui_unregister_state_callback`], or 0 if
+/// `callback` is null.
+#[no_mangle]
+pub extern "C" fn rust_gui_register_state_callback(
+    callback: RustGuiStateCallback,
+    user_data: *mut c_void,
+) -> u64 {
+    let Some(callback) = callback else {
+        return 0;
+    };
+    let token = NEXT_TOKEN.fetch_add(1, Ordering::SeqCst);
+    CALLBACKS.lock().unwrap().push(Subscription {
+        token,
+        callback,
+        user_data,
+    });
+    token
+}
+
+/// Returns whether a callback was registered under `token`.
+#[no_mangle]
+pub extern "C" fn rust_gui_unregister_state_callback(token: u64) -> bool {
+    let mut callbacks = CALLBACKS.lock().unwrap();
+    let before = callbacks.len();
+    callbacks.retain(|subscription| subscription.token != token);
+    callbacks.len() != before
+}
+
+/// Makes `app` the one driven by `rust_gui_pump` and friends on this thread.
+/// Returns `false`, dropping `app`, if one is already running.
+///
+/// `rust_gui_start` does this with the app from `host_app`; tests can
+/// install a headless one.
+pub fn start_embedded(app: App) -> bool {
+    EMBEDDED.with_borrow_mut(|embedded| {
+        if embedded.is_some() {
+            return false;
+        }
+        *embedded = Some(EmbeddedApp::new(app));
+        true
+    })
+}
+
+fn with_embedded<R>(f: impl FnOnce(&mut EmbeddedApp) -> R) -> Option<R> {
+    EMBEDDED.with_borrow_mut(|embedded| embedded.as_mut().map(f))
+}
+
+/// Starts the GUI rendering into `ui_view`, a `UIView *`. Sizes are in
+/// pixels. Returns `false` if the view is null or the GUI is already running.
+///
+/// iOS only. Call this and the embedding functions below from the main
+/// thread.
+#[cfg(target_os = "ios")]
+#[no_mangle]
+pub extern "C" fn rust_gui_start(
+    ui_view: *mut c_void,
+    physical_width: u32,
+    physical_height: u32,
+    scale_factor: f32,
+) -> bool {
+    let Some(ui_view) = std::ptr::NonNull::new(ui_view) else {
+        return false;
+    };
+    let view = crate::embed::HostView {
+        ui_view,
+        physical_width,
+        physical_height,
+        scale_factor,
+    };
+    match crate::embed::host_app(view) {
+        Ok(app) => start_embedded(app),
+        Err(e) => {
+            eprintln!("Cannot render into the host view: {}", e);
+            false
+        }
+    }
+}
+
+/// Runs one frame. Returns `false` if nothing is running or the app asked to
+/// exit.
+#[no_mangle]
+pub extern "C" fn rust_gui_pump() -> bool {
+    with_embedded(EmbeddedApp::pump).unwrap_or(false)
+}
+
+#[no_mangle]
+pub extern "C" fn rust_gui_pause() {
+    with_embedded(EmbeddedApp::pause);
+}
+
+#[no_mangle]
+pub extern "C" fn rust_gui_resume() {
+    with_embedded(EmbeddedApp::resume);
+}
+
+#[no_mangle]
+pub extern "C" fn rust_gui_resize(physical_width: u32, physical_height: u32, scale_factor: f32) {
+    with_embedded(|app| app.resize(physical_width, physical_height, scale_factor));
+}
+
+/// Stops and drops the running app. Returns its exit code, or 0 if nothing
+/// was running.
+#[no_mangle]
+pub extern "C" fn rust_gui_shutdown() -> i32 {
+    let Some(embedded) = EMBEDDED.with_borrow_mut(Option::take) else {
+        return 0;
+    };
+    match embedded.shutdown() {
+        AppExit::Success => 0,
+        AppExit::Error(code) => code.get() as i32,
+    }
+}
@@ -0,0 +1,275 @@
+//! Keyboard and gamepad focus.
+//!
+//! Tab and Shift-Tab step through the [`Focusable`] nodes in reading order:
+//! row by row from the top, left to right within a row. Arrow keys and the
+//! D-pad move to the nearest node in that direction. Enter, Space and the
+//! gamepad's South button (A on Xbox pads) activate the focused
+//! [`ActionButton`], and [`show_focus_ring`] outlines it in the theme's
+//! `focus_ring` color. Clicking or touching anywhere hides focus again until
+//! the next navigation key. While a dialog is open, focus stays inside it.
+
+use bevy::input::touch::Touches;
+use bevy::prelude::*;
+use std::cmp::Ordering;
+
+use crate::buttons::{ActionButton, ButtonActivated};
+use crate::dialog::Modal;
+use crate::theme::Theme;
+
+/// Lets a node take keyboard and gamepad focus. Every [`action_button`] has
+/// one.
+///
+/// [`action_button`]: crate::action_button
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Focusable;
+
+/// The focused node, if any.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Focus(pub Option<Entity>);
+
+/// Where a navigation key moves focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusMove {
+    Next,
+    Previous,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl FocusMove {
+    /// The reading-order step used when there is no layout to search.
+    fn fallback(self) -> FocusMove {
+        match self {
+            FocusMove::Previous | FocusMove::Up | FocusMove::Left => FocusMove::Previous,
+            FocusMove::Next | FocusMove::Down | FocusMove::Right => FocusMove::Next,
+        }
+    }
+
+    /// How far `to` lies beyond `from` in this direction, and how far off to
+    /// the side, both measured between edges. `None` if `to` isn't in this
+    /// direction at all. UI coordinates grow downwards.
+    fn offsets(self, from: Rect, to: Rect) -> Option<(f32, f32)> {
+        let gap = |a_min: f32, a_max: f32, b_min: f32, b_max: f32| {
+            (b_min - a_max).max(a_min - b_max).max(0.0)
+        };
+        let horizontal = gap(from.min.x, from.max.x, to.min.x, to.max.x);
+        let vertical = gap(from.min.y, from.max.y, to.min.y, to.max.y);
+        let delta = to.center() - from.center();
+        let (ahead, aside) = match self {
+            FocusMove::Up => (-delta.y, horizontal),
+            FocusMove::Down => (delta.y, horizontal),
+            FocusMove::Left => (-delta.x, vertical),
+            FocusMove::Right => (delta.x, vertical),
+            FocusMove::Next | FocusMove::Previous => return None,
+        };
+        let beyond = match self {
+            FocusMove::Up | FocusMove::Down => vertical,
+            _ => horizontal,
+        };
+        (ahead > 0.0).then_some((beyond, aside))
+    }
+}
+
+/// Marks the node whose `Outline` is the focus ring, so it can be taken off
+/// again when focus moves.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct FocusRing;
+
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    entity: Entity,
+    /// `None` for nodes that haven't been laid out.
+    bounds: Option<Rect>,
+    enabled: bool,
+}
+
+fn bounds(node: &ComputedNode, transform: &UiGlobalTransform) -> Option<Rect> {
+    (node.size != Vec2::ZERO).then(|| Rect::from_center_size(transform.translation, node.size))
+}
+
+/// Sorts `candidates` into reading order. Nodes overlapping vertically share
+/// a row; unlaid-out nodes keep spawn order.
+fn reading_order(candidates: &mut [Candidate]) {
+    let top = |c: &Candidate| c.bounds.map_or(0.0, |b| b.min.y);
+    candidates.sort_by(|a, b| {
+        top(a)
+            .total_cmp(&top(b))
+            .then_with(|| a.entity.index().cmp(&b.entity.index()))
+    });
+
+    let mut start = 0;
+    while start < candidates.len() {
+        let mut bottom = candidates[start].bounds.map_or(0.0, |b| b.max.y);
+        let mut end = start + 1;
+        while end < candidates.len() && top(&candidates[end]) < bottom {
+            bottom = bottom.max(candidates[end].bounds.map_or(0.0, |b| b.max.y));
+            end += 1;
+        }
+        candidates[start..end].sort_by(|a, b| {
+            let left = |c: &Candidate| c.bounds.map_or(0.0, |b| b.min.x);
+            left(a).total_cmp(&left(b))
+        });
+        start = end;
+    }
+}
+
+/// Where `step` moves focus from `current` among `order`, which is in
+/// reading order.
+fn target(order: &[Candidate], current: Option<Entity>, step: FocusMove) -> Option<Entity> {
+    let enabled = || order.iter().filter(|c| c.enabled);
+    let from = current.and_then(|entity| order.iter().position(|c| c.entity == entity));
+    let Some(from) = from else {
+        return match step.fallback() {
+            FocusMove::Previous => enabled().next_back(),
+            _ => enabled().next(),
+        }
+        .map(|c| c.entity);
+    };
+
+    if let (Some(origin), FocusMove::Up | FocusMove::Down | FocusMove::Left | FocusMove::Right) =
+        (order[from].bounds, step)
+    {
+        // Closest along the direction, with sideways distance counting
+        // double so a node straight ahead wins over a nearer diagonal one.
+        // Ties go to the earlier node in reading order.
+        return enabled()
+            .filter_map(|c| {
+                let (ahead, aside) = step.offsets(origin, c.bounds?)?;
+                Some((c.entity, ahead + 2.0 * aside))
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+            .map(|(entity, _)| entity);
+    }
+
+    let (before, after) = (&order[..from], &order[from + 1..]);
+    let found = match step.fallback() {
+        FocusMove::Previous => before
+            .iter()
+            .rev()
+            .chain(after.iter().rev())
+            .find(|c| c.enabled),
+        _ => after.iter().chain(before).find(|c| c.enabled),
+    };
+    found.map(|c| c.entity)
+}
+
+/// The move asked for by this frame's keys or D-pad presses.
+fn requested_move(keys: &ButtonInput<KeyCode>, gamepads: &Query<&Gamepad>) -> Option<FocusMove> {
+    if keys.just_pressed(KeyCode::Tab) {
+        let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        return Some(if shift {
+            FocusMove::Previous
+        } else {
+            FocusMove::Next
+        });
+    }
+    let directions = [
+        (KeyCode::ArrowUp, GamepadButton::DPadUp, FocusMove::Up),
+        (KeyCode::ArrowDown, GamepadButton::DPadDown, FocusMove::Down),
+        (KeyCode::ArrowLeft, GamepadButton::DPadLeft, FocusMove::Left),
+        (
+            KeyCode::ArrowRight,
+            GamepadButton::DPadRight,
+            FocusMove::Right,
+        ),
+    ];
+    directions
+        .into_iter()
+        .find(|&(key, button, _)| {
+            keys.just_pressed(key) || gamepads.iter().any(|pad| pad.just_pressed(button))
+        })
+        .map(|(_, _, step)| step)
+}
+
+pub fn navigate_focus(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    gamepads: Query<&Gamepad>,
+    mut focus: ResMut<Focus>,
+    modal: Modal,
+    focusables: Query<
+        (
+            Entity,
+            Option<&ActionButton>,
+            Option<(&ComputedNode, &UiGlobalTransform)>,
+        ),
+        With<Focusable>,
+    >,
+    mut activated: MessageWriter<ButtonActivated>,
+) {
+    if focus.0.is_some_and(|entity| !focusables.contains(entity))
+        || mouse.get_just_pressed().next().is_some()
+        || touches.any_just_pressed()
+    {
+        focus.set_if_neq(Focus(None));
+    }
+
+    let mut step = requested_move(&keys, &gamepads);
+    if focus.0.is_some_and(|entity| modal.blocks(entity)) {
+        // A dialog opened over the focused node; focus follows into it.
+        focus.0 = None;
+        step = Some(FocusMove::Next);
+    }
+
+    if let Some(step) = step {
+        let mut order: Vec<Candidate> = focusables
+            .iter()
+            .filter(|(entity, ..)| !modal.blocks(*entity))
+            .map(|(entity, button, node)| Candidate {
+                entity,
+                bounds: node.and_then(|(node, transform)| bounds(node, transform)),
+                enabled: button.is_none_or(|button| !button.disabled),
+            })
+            .collect();
+        reading_order(&mut order);
+        if let Some(entity) = target(&order, focus.0, step) {
+            focus.set_if_neq(Focus(Some(entity)));
+        }
+    }
+
+    let activate = keys.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::Space])
+        || gamepads
+            .iter()
+            .any(|pad| pad.just_pressed(GamepadButton::South));
+    if !activate {
+        return;
+    }
+    // Gesture buttons too: a key press has no double tap to wait for.
+    if let Some((entity, Some(button), _)) = focus.0.and_then(|entity| focusables.get(entity).ok())
+    {
+        if !button.disabled {
+            activated.write(ButtonActivated {
+                entity,
+                action: button.action.clone(),
+            });
+        }
+    }
+}
+
+/// Moves the focus ring to the focused node.
+pub fn show_focus_ring(
+    mut commands: Commands,
+    focus: Res<Focus>,
+    theme: Res<Theme>,
+    ringed: Query<Entity, With<FocusRing>>,
+) {
+    if !focus.is_changed() && !theme.is_changed() {
+        return;
+    }
+    for entity in &ringed {
+        if Some(entity) != focus.0 {
+            commands.entity(entity).remove::<(FocusRing, Outline)>();
+        }
+    }
+    if let Some(entity) = focus.0 {
+        if let Ok(mut entity) = commands.get_entity(entity) {
+            entity.try_insert((
+                FocusRing,
+                Outline::new(Val::Px(3.0), Val::Px(2.0), theme.focus_ring),
+            ));
+        }
+    }
+}
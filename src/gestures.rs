@@ -0,0 +1,202 @@
+//! Taps, double taps, long presses and press-and-hold repeats on UI nodes.
+//!
+//! Any node with a [`GestureRecognizer`] (every [`action_button`] has one)
+//! gets [`GestureRecognized`] messages. Presses come from the node's
+//! `Interaction`, which Bevy UI derives from the mouse and touches, and the
+//! pointer position from `Touches` or the window cursor. All timing uses
+//! `Time`, so tests can step a virtual clock instead of sleeping.
+//!
+//! [`action_button`]: crate::action_button
+
+use bevy::input::touch::Touches;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use std::time::Duration;
+
+/// A gesture recognized on a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    /// Pressed and released without moving or holding long enough for a
+    /// long press.
+    Tap,
+    /// The second of two taps close together in time and space. The
+    /// second tap is also sent as a [`Gesture::Tap`] just before it.
+    DoubleTap,
+    /// Held in place for [`GestureSettings::long_press`]. Releasing
+    /// afterwards is not a tap.
+    LongPress,
+    /// Sent while held in place, first after
+    /// [`GestureSettings::repeat_delay`] and then every
+    /// [`GestureSettings::repeat_interval`]. Counts from 1.
+    Repeat(u32),
+}
+
+/// Sent when a node with a [`GestureRecognizer`] recognizes a gesture.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GestureRecognized {
+    pub entity: Entity,
+    pub gesture: Gesture,
+}
+
+/// Timing and distance thresholds shared by every recognizer. Distances
+/// are in logical pixels.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct GestureSettings {
+    /// Longest gap between releasing the first tap and pressing the second.
+    pub double_tap_interval: Duration,
+    /// Furthest apart the two taps of a double tap may be.
+    pub double_tap_slop: f32,
+    /// How far the pointer may move during a press before it stops being a
+    /// tap, long press or repeat. Fingers moving further start a pan or
+    /// pinch.
+    pub tap_slop: f32,
+    pub long_press: Duration,
+    pub repeat_delay: Duration,
+    pub repeat_interval: Duration,
+    /// Shortest distance a finger must travel to swipe.
+    pub swipe_distance: f32,
+    /// Longest a swipe may take, from touching down to lifting.
+    pub swipe_time: Duration,
+}
+
+impl Default for GestureSettings {
+    /// Close to UIKit's defaults.
+    fn default() -> Self {
+        Self {
+            double_tap_interval: Duration::from_millis(300),
+            double_tap_slop: 40.0,
+            tap_slop: 10.0,
+            long_press: Duration::from_millis(500),
+            repeat_delay: Duration::from_millis(500),
+            repeat_interval: Duration::from_millis(100),
+            swipe_distance: 50.0,
+            swipe_time: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Recognizes gestures on a UI node. Needs nothing besides the node itself.
+#[derive(Component, Debug, Clone, Default)]
+#[require(Interaction)]
+pub struct GestureRecognizer {
+    press: Option<Press>,
+    /// Release time and position of a tap that may start a double tap.
+    last_tap: Option<(Duration, Option<Vec2>)>,
+}
+
+#[derive(Debug, Clone)]
+struct Press {
+    started: Duration,
+    position: Option<Vec2>,
+    /// Moved further than the tap slop, which cancels every gesture.
+    moved: bool,
+    long_pressed: bool,
+    repeats: u32,
+}
+
+/// Where the pointer is: the first touch, or the mouse cursor. `None` when
+/// neither is known, e.g. headless, in which case distances aren't checked.
+fn pointer_position(
+    touches: &Touches,
+    windows: &Query<&Window, With<PrimaryWindow>>,
+) -> Option<Vec2> {
+    touches
+        .iter()
+        .chain(touches.iter_just_released())
+        .next()
+        .map(|touch| touch.position())
+        .or_else(|| windows.single().ok()?.cursor_position())
+}
+
+fn within(a: Option<Vec2>, b: Option<Vec2>, slop: f32) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.distance(b) <= slop,
+        _ => true,
+    }
+}
+
+pub fn recognize_gestures(
+    time: Res<Time>,
+    settings: Res<GestureSettings>,
+    touches: Res<Touches>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut recognizers: Query<(Entity, &Interaction, &mut GestureRecognizer)>,
+    mut recognized: MessageWriter<GestureRecognized>,
+) {
+    let now = time.elapsed();
+    let position = pointer_position(&touches, &windows);
+
+    for (entity, interaction, mut recognizer) in &mut recognizers {
+        let recognizer = &mut *recognizer;
+        let pressed = *interaction == Interaction::Pressed;
+
+        let Some(press) = &mut recognizer.press else {
+            if pressed {
+                recognizer.press = Some(Press {
+                    started: now,
+                    position,
+                    moved: false,
+                    long_pressed: false,
+                    repeats: 0,
+                });
+            }
+            continue;
+        };
+
+        if !within(press.position, position, settings.tap_slop) {
+            press.moved = true;
+        }
+
+        if !pressed {
+            if !press.moved && !press.long_pressed {
+                recognized.write(GestureRecognized {
+                    entity,
+                    gesture: Gesture::Tap,
+                });
+                let double = recognizer.last_tap.is_some_and(|(released, at)| {
+                    press.started.saturating_sub(released) <= settings.double_tap_interval
+                        && within(at, press.position, settings.double_tap_slop)
+                });
+                if double {
+                    recognized.write(GestureRecognized {
+                        entity,
+                        gesture: Gesture::DoubleTap,
+                    });
+                    // A third tap starts over rather than making another pair.
+                    recognizer.last_tap = None;
+                } else {
+                    recognizer.last_tap = Some((now, press.position));
+                }
+            } else {
+                recognizer.last_tap = None;
+            }
+            recognizer.press = None;
+            continue;
+        }
+
+        if press.moved {
+            continue;
+        }
+        let held = now.saturating_sub(press.started);
+        if !press.long_pressed && held >= settings.long_press {
+            press.long_pressed = true;
+            recognized.write(GestureRecognized {
+                entity,
+                gesture: Gesture::LongPress,
+            });
+        }
+        let due = match held.checked_sub(settings.repeat_delay) {
+            None => 0,
+            Some(_) if settings.repeat_interval.is_zero() => 1,
+            Some(since) => (since.as_nanos() / settings.repeat_interval.as_nanos()) as u32 + 1,
+        };
+        // Catch up on every repeat that fell within this frame.
+        while press.repeats < due {
+            press.repeats += 1;
+            recognized.write(GestureRecognized {
+                entity,
+                gesture: Gesture::Repeat(press.repeats),
+            });
+        }
+    }
+}
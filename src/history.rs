@@ -0,0 +1,187 @@
+//! Undoable mutations of [`GuiState`].
+//!
+//! Every change to the state goes through a [`GuiCommand`] applied via
+//! [`History::execute`], which remembers the state it replaced so the change
+//! can be undone and redone.
+
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use crate::GuiState;
+
+/// Number of commands kept on the undo stack unless configured otherwise.
+pub const DEFAULT_HISTORY_CAPACITY: usize = 100;
+
+/// A mutation of [`GuiState`] triggered from the UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuiCommand {
+    Click,
+    DoubleClick,
+    SpecialEffect,
+    Reset,
+    /// Sent by the host app through the C API.
+    SetClickCount(i32),
+    /// Sent by the host app through the C API.
+    SetButtonState(bool),
+}
+
+impl GuiCommand {
+    pub fn apply(self, state: &mut GuiState) {
+        match self {
+            GuiCommand::Click => {
+                state.click_count += 1;
+                state.button_state = !state.button_state;
+
+                if state.click_count == 1 {
+                    state.message = "Button clicked for the first time!".to_string();
+                } else {
+                    state.message = format!(
+                        "Button clicked {} times! Current state: {}",
+                        state.click_count,
+                        state.state_label()
+                    );
+                }
+            }
+            GuiCommand::DoubleClick => {
+                state.click_count += 2;
+                state.button_state = !state.button_state;
+                state.message = format!("Double click! Total: {} clicks", state.click_count);
+            }
+            GuiCommand::SpecialEffect => {
+                let message = if state.click_count > 10 {
+                    "Amazing! You're a clicking master!"
+                } else if state.click_count > 5 {
+                    "Good job! Keep clicking!"
+                } else {
+                    "Just getting started!"
+                };
+                state.message = message.to_string();
+            }
+            GuiCommand::Reset => {
+                *state = GuiState::default();
+            }
+            GuiCommand::SetClickCount(count) => {
+                state.click_count = count;
+            }
+            GuiCommand::SetButtonState(on) => {
+                state.button_state = on;
+            }
+        }
+
+        state.sync_globals();
+    }
+}
+
+#[derive(Debug, Clone)]
+struct HistoryEntry {
+    command: GuiCommand,
+    before: GuiState,
+}
+
+/// Bounded undo/redo stacks of executed [`GuiCommand`]s.
+#[derive(Resource, Debug, Clone)]
+pub struct History {
+    undo: VecDeque<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+    capacity: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_HISTORY_CAPACITY)
+    }
+}
+
+impl History {
+    /// Creates a history that forgets the oldest command once `capacity` is
+    /// exceeded.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// Applies `command` and records it. Clears anything that could be redone.
+    pub fn execute(&mut self, command: GuiCommand, state: &mut GuiState) {
+        let before = state.clone();
+        command.apply(state);
+
+        self.redo.clear();
+        if self.capacity == 0 {
+            return;
+        }
+        if self.undo.len() == self.capacity {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(HistoryEntry { command, before });
+    }
+
+    /// Restores the state from before the last command. Returns the undone
+    /// command, or `None` if there was nothing to undo.
+    pub fn undo(&mut self, state: &mut GuiState) -> Option<GuiCommand> {
+        let entry = self.undo.pop_back()?;
+        *state = entry.before.clone();
+        state.sync_globals();
+
+        let command = entry.command;
+        self.redo.push(entry);
+        Some(command)
+    }
+
+    /// Re-applies the last undone command. Returns it, or `None` if there was
+    /// nothing to redo.
+    pub fn redo(&mut self, state: &mut GuiState) -> Option<GuiCommand> {
+        let entry = self.redo.pop()?;
+        entry.command.apply(state);
+
+        let command = entry.command;
+        self.undo.push_back(entry);
+        Some(command)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+/// Cmd+Z / Ctrl+Z undoes, with Shift it redoes.
+pub fn handle_undo_shortcuts(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut history: ResMut<History>,
+    mut gui_state: ResMut<GuiState>,
+) {
+    let command_held = keys.any_pressed([
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+    ]);
+    if !command_held || !keys.just_pressed(KeyCode::KeyZ) {
+        return;
+    }
+
+    let redo = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    undo_or_redo(redo, &mut history, &mut gui_state);
+}
+
+/// Shared by the shortcuts and the Undo/Redo buttons. Checks the stack first
+/// so an empty history doesn't mark `GuiState` as changed.
+pub(crate) fn undo_or_redo(redo: bool, history: &mut History, gui_state: &mut ResMut<GuiState>) {
+    if redo {
+        if history.can_redo() {
+            if let Some(command) = history.redo(gui_state) {
+                println!("Redo: {:?}", command);
+            }
+        }
+    } else if history.can_undo() {
+        if let Some(command) = history.undo(gui_state) {
+            println!("Undo: {:?}", command);
+        }
+    }
+}
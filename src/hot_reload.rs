@@ -0,0 +1,143 @@
+//! Live reloading of layout and theme files while developing on desktop.
+//!
+//! Only compiled with the `hot-reload` feature, which also turns on Bevy's
+//! file watcher, and never on iOS. When a watched `.layout.ron` changes the
+//! screen is despawned and rebuilt from the new file; [`GuiState`] and
+//! [`History`] are left alone and just marked changed so the fresh widgets
+//! pick up the current labels, roles and disabled states. Edits to
+//! `themes/dark.theme.ron` and `themes/light.theme.ron` replace the matching
+//! entry in [`Themes`] and, if it is the one in use, the current [`Theme`].
+
+use bevy::prelude::*;
+
+use crate::history::History;
+use crate::layout::{spawn_layout, Layout};
+use crate::theme::{Theme, ThemeKind, Themes};
+use crate::ui::{spawn_loaded_layout, ScreenLayout};
+use crate::{recognize_gestures, GuiConfig, GuiState};
+
+/// Asset path of the dark theme watched by [`HotReloadPlugin`].
+pub const DARK_THEME_PATH: &str = "themes/dark.theme.ron";
+/// Asset path of the light theme watched by [`HotReloadPlugin`].
+pub const LIGHT_THEME_PATH: &str = "themes/light.theme.ron";
+
+/// Rebuilds the UI when its layout or theme files change on disk.
+///
+/// Needs `AssetPlugin` and [`GuiPlugin`](crate::GuiPlugin). The layout is only
+/// watched if `GuiPlugin::layout` names a file.
+#[derive(Debug, Clone, Default)]
+pub struct HotReloadPlugin;
+
+/// Handles keeping the watched theme files loaded.
+#[derive(Resource, Debug)]
+pub struct ThemeHandles {
+    pub dark: Handle<Theme>,
+    pub light: Handle<Theme>,
+}
+
+impl ThemeHandles {
+    fn kind_of(&self, id: AssetId<Theme>) -> Option<ThemeKind> {
+        if id == self.dark.id() {
+            Some(ThemeKind::Dark)
+        } else if id == self.light.id() {
+            Some(ThemeKind::Light)
+        } else {
+            None
+        }
+    }
+}
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<AssetPlugin>() {
+            eprintln!("HotReloadPlugin needs AssetPlugin, nothing will be reloaded");
+            return;
+        }
+
+        app.add_systems(Startup, load_theme_files).add_systems(
+            Update,
+            (reload_layout.after(spawn_loaded_layout), reload_themes).before(recognize_gestures),
+        );
+    }
+}
+
+fn load_theme_files(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ThemeHandles {
+        dark: asset_server.load(DARK_THEME_PATH),
+        light: asset_server.load(LIGHT_THEME_PATH),
+    });
+}
+
+/// Swaps the spawned screen for one built from the modified layout.
+pub fn reload_layout(
+    mut commands: Commands,
+    mut layout_events: MessageReader<AssetEvent<Layout>>,
+    screen: Option<ResMut<ScreenLayout>>,
+    layouts: Res<Assets<Layout>>,
+    config: Res<GuiConfig>,
+    theme: Res<Theme>,
+    mut gui_state: ResMut<GuiState>,
+    mut history: ResMut<History>,
+) {
+    let Some(mut screen) = screen else {
+        return;
+    };
+    let modified = layout_events
+        .read()
+        .any(|event| event.is_modified(&screen.handle));
+    // Before the first load there is nothing to replace yet.
+    let Some(old_root) = screen.root.filter(|_| modified) else {
+        return;
+    };
+    let Some(layout) = layouts.get(&screen.handle) else {
+        return;
+    };
+
+    commands.entity(old_root).despawn();
+    screen.root = Some(spawn_layout(&mut commands, layout, &config.title, &theme));
+
+    // New widgets start out with placeholder text and default roles.
+    gui_state.set_changed();
+    history.set_changed();
+    println!("Layout reloaded");
+}
+
+/// Picks up edited theme files.
+pub fn reload_themes(
+    mut theme_events: MessageReader<AssetEvent<Theme>>,
+    handles: Option<Res<ThemeHandles>>,
+    theme_assets: Res<Assets<Theme>>,
+    mut themes: ResMut<Themes>,
+    mut theme: ResMut<Theme>,
+) {
+    let Some(handles) = handles else {
+        return;
+    };
+
+    for event in theme_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = *event
+        else {
+            continue;
+        };
+        let (Some(kind), Some(loaded)) = (handles.kind_of(id), theme_assets.get(id)) else {
+            continue;
+        };
+        let loaded = Theme {
+            kind,
+            ..loaded.clone()
+        };
+
+        let slot = match kind {
+            ThemeKind::Dark => &mut themes.dark,
+            ThemeKind::Light => &mut themes.light,
+        };
+        if *slot == loaded {
+            continue;
+        }
+        *slot = loaded.clone();
+        if theme.kind == kind {
+            *theme = loaded;
+            println!("Theme reloaded: {:?}", kind);
+        }
+    }
+}
@@ -0,0 +1,480 @@
+//! Declarative screen layouts.
+//!
+//! A [`Layout`] describes the widget tree in a RON file (see
+//! `assets/layouts/main.layout.ron`): labels, buttons and rows, each with its
+//! theme role, action and optional marker component. Layout files are loaded
+//! through Bevy's asset system by [`LayoutLoader`] and turned into entities by
+//! [`spawn_layout`].
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::buttons::{action_button, ButtonAction};
+use crate::theme::{ButtonRole, ButtonRow, ScreenRoot, TextRole, Theme};
+use crate::touch_gestures::TouchGestures;
+use crate::ui::{
+    ClickButton, CounterLabel, DoubleClickButton, InfoButton, MessageLabel, RedoButton,
+    ResetButton, SpecialEffectButton, StatusLabel, ThemeToggleButton, UndoButton,
+};
+
+/// Asset path of the demo app's layout file.
+pub const MAIN_LAYOUT_PATH: &str = "layouts/main.layout.ron";
+
+/// The layout used when no layout file is configured, or when it fails to load.
+pub const BUILTIN_LAYOUT: &str = include_str!("../assets/layouts/main.layout.ron");
+
+/// A screen: the children of the root column, top to bottom.
+#[derive(Asset, TypePath, Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Layout {
+    pub children: Vec<Element>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum Element {
+    Text {
+        /// Required except for the title, which defaults to `GuiPlugin::title`.
+        #[serde(default)]
+        text: Option<String>,
+        role: TextRole,
+        #[serde(default)]
+        marker: Option<Marker>,
+    },
+    Button {
+        label: String,
+        action: ButtonAction,
+        #[serde(default = "default_button_role")]
+        role: ButtonRole,
+        #[serde(default)]
+        marker: Option<Marker>,
+        #[serde(default = "default_button_width")]
+        width: f32,
+        #[serde(default = "default_button_height")]
+        height: f32,
+        #[serde(default)]
+        margin: Option<Margin>,
+        /// Use the theme's large button font size.
+        #[serde(default)]
+        large_text: bool,
+    },
+    /// Lays its children out horizontally.
+    Row { children: Vec<Element> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum Margin {
+    All(f32),
+    Vertical(f32),
+    Horizontal(f32),
+}
+
+impl From<Margin> for UiRect {
+    fn from(margin: Margin) -> Self {
+        match margin {
+            Margin::All(px) => UiRect::all(Val::Px(px)),
+            Margin::Vertical(px) => UiRect::vertical(Val::Px(px)),
+            Margin::Horizontal(px) => UiRect::horizontal(Val::Px(px)),
+        }
+    }
+}
+
+/// Marker components a layout element can be tagged with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum Marker {
+    ClickButton,
+    CounterLabel,
+    StatusLabel,
+    MessageLabel,
+    ResetButton,
+    InfoButton,
+    DoubleClickButton,
+    SpecialEffectButton,
+    UndoButton,
+    RedoButton,
+    ThemeToggleButton,
+}
+
+impl Marker {
+    fn insert(self, entity: &mut EntityCommands) {
+        match self {
+            Marker::ClickButton => entity.insert(ClickButton),
+            Marker::CounterLabel => entity.insert(CounterLabel),
+            Marker::StatusLabel => entity.insert(StatusLabel),
+            Marker::MessageLabel => entity.insert(MessageLabel),
+            Marker::ResetButton => entity.insert(ResetButton),
+            Marker::InfoButton => entity.insert(InfoButton),
+            Marker::DoubleClickButton => entity.insert(DoubleClickButton),
+            Marker::SpecialEffectButton => entity.insert(SpecialEffectButton),
+            Marker::UndoButton => entity.insert(UndoButton),
+            Marker::RedoButton => entity.insert(RedoButton),
+            Marker::ThemeToggleButton => entity.insert(ThemeToggleButton),
+        };
+    }
+}
+
+fn default_button_role() -> ButtonRole {
+    ButtonRole::Neutral
+}
+
+fn default_button_width() -> f32 {
+    140.0
+}
+
+fn default_button_height() -> f32 {
+    40.0
+}
+
+/// Why a layout file was rejected. Line and column numbers are 1-based.
+#[derive(Debug)]
+pub enum LayoutError {
+    Io(std::io::Error),
+    /// The file is not valid RON, or does not match the layout schema.
+    Syntax {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The file parsed but describes an invalid screen.
+    Invalid {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::Io(e) => write!(f, "failed to read layout: {}", e),
+            LayoutError::Syntax {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+            LayoutError::Invalid { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+impl From<std::io::Error> for LayoutError {
+    fn from(e: std::io::Error) -> Self {
+        LayoutError::Io(e)
+    }
+}
+
+impl Layout {
+    /// The parsed [`BUILTIN_LAYOUT`].
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_LAYOUT).expect("built-in layout is valid")
+    }
+
+    /// Parses and validates a layout file.
+    pub fn parse(source: &str) -> Result<Self, LayoutError> {
+        let options = ron::Options::default()
+            .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME);
+        let layout: Layout = options.from_str(source).map_err(|e| LayoutError::Syntax {
+            line: e.position.line,
+            column: e.position.col,
+            message: e.code.to_string(),
+        })?;
+
+        layout.validate(&element_lines(source))?;
+        Ok(layout)
+    }
+
+    /// `lines` holds the line of every element, in the order [`Layout::visit`]
+    /// walks them.
+    fn validate(&self, lines: &[usize]) -> Result<(), LayoutError> {
+        let mut seen_markers = HashMap::new();
+        let mut result = Ok(());
+
+        self.visit(|index, element| {
+            if result.is_err() {
+                return;
+            }
+            let line = lines.get(index).copied().unwrap_or(0);
+            let invalid = |message: String| Err(LayoutError::Invalid { line, message });
+
+            let marker = match element {
+                Element::Text {
+                    text, role, marker, ..
+                } => {
+                    if text.is_none() && *role != TextRole::Title {
+                        result = invalid(format!("{:?} text needs a `text` value", role));
+                    }
+                    *marker
+                }
+                Element::Button {
+                    label,
+                    width,
+                    height,
+                    marker,
+                    ..
+                } => {
+                    if label.trim().is_empty() {
+                        result = invalid("button label is empty".to_string());
+                    } else if *width <= 0.0 || *height <= 0.0 {
+                        result = invalid(format!(
+                            "button size must be positive, got {}x{}",
+                            width, height
+                        ));
+                    }
+                    *marker
+                }
+                Element::Row { children } => {
+                    if children.is_empty() {
+                        result = invalid("row has no children".to_string());
+                    }
+                    None
+                }
+            };
+
+            if let (Some(marker), Ok(())) = (marker, &result) {
+                if let Some(first_line) = seen_markers.insert(marker, line) {
+                    result = invalid(format!(
+                        "marker {:?} is already used on line {}",
+                        marker, first_line
+                    ));
+                }
+            }
+        });
+
+        result
+    }
+
+    /// Calls `f` for every element, depth first, with its pre-order index.
+    pub fn visit(&self, mut f: impl FnMut(usize, &Element)) {
+        fn walk(elements: &[Element], index: &mut usize, f: &mut impl FnMut(usize, &Element)) {
+            for element in elements {
+                f(*index, element);
+                *index += 1;
+                if let Element::Row { children } = element {
+                    walk(children, index, f);
+                }
+            }
+        }
+
+        walk(&self.children, &mut 0, &mut f);
+    }
+}
+
+/// Lines of the `Text(`, `Button(` and `Row(` tags in `source`, in document
+/// order, which matches the pre-order walk of the parsed elements. RON has no
+/// spans for deserialized values, so this is how validation finds lines.
+fn element_lines(source: &str) -> Vec<usize> {
+    let bytes = source.as_bytes();
+    let mut lines = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\n' => line += 1,
+            b'"' => {
+                // Skip the string, honouring escapes.
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    match bytes[i] {
+                        b'\\' => i += 1,
+                        b'\n' => line += 1,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                    if bytes[i] == b'\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                if let Some(hashes) = raw_string_hashes(&bytes[i..]) {
+                    // Skip the raw string. It has no escapes and ends at a
+                    // quote followed by as many `#`s as it started with.
+                    let closing = [b"\"".as_slice(), &b"#".repeat(hashes)].concat();
+                    i += bytes[i..].iter().position(|&b| b == b'"').unwrap() + 1;
+                    while i < bytes.len() && !bytes[i..].starts_with(&closing) {
+                        if bytes[i] == b'\n' {
+                            line += 1;
+                        }
+                        i += 1;
+                    }
+                    i += closing.len();
+                    continue;
+                }
+                let start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                let ident = &source[start..i];
+                let next = source[i..].trim_start().as_bytes().first();
+                if matches!(ident, "Text" | "Button" | "Row") && next == Some(&b'(') {
+                    lines.push(line);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    lines
+}
+
+/// The number of `#`s if `bytes` starts with a raw string, e.g. `r#"`.
+fn raw_string_hashes(bytes: &[u8]) -> Option<usize> {
+    let rest = bytes
+        .strip_prefix(b"b")
+        .unwrap_or(bytes)
+        .strip_prefix(b"r")?;
+    let hashes = rest.iter().take_while(|&&b| b == b'#').count();
+    (rest.get(hashes) == Some(&b'"')).then_some(hashes)
+}
+
+#[derive(Default, TypePath)]
+pub struct LayoutLoader;
+
+impl AssetLoader for LayoutLoader {
+    type Asset = Layout;
+    type Settings = ();
+    type Error = LayoutError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Layout, LayoutError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let source = std::str::from_utf8(&bytes).map_err(|e| {
+            LayoutError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        })?;
+        Layout::parse(source)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["layout.ron"]
+    }
+}
+
+/// Spawns the screen described by `layout` under a new [`ScreenRoot`] and
+/// returns the root entity. The root recognizes [`TouchGestures`] made
+/// anywhere on the screen.
+pub fn spawn_layout(
+    commands: &mut Commands,
+    layout: &Layout,
+    title: &str,
+    theme: &Theme,
+) -> Entity {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(theme.spacing.row_gap),
+                padding: UiRect::all(Val::Px(theme.spacing.padding)),
+                ..default()
+            },
+            BackgroundColor(theme.background),
+            ScreenRoot,
+            TouchGestures::default(),
+        ))
+        .with_children(|parent| {
+            for element in &layout.children {
+                spawn_element(parent, element, title, theme);
+            }
+        })
+        .id()
+}
+
+fn spawn_element(parent: &mut ChildSpawnerCommands, element: &Element, title: &str, theme: &Theme) {
+    match element {
+        Element::Text { text, role, marker } => {
+            let text = text.clone().unwrap_or_else(|| title.to_string());
+            let mut entity = parent.spawn((
+                Text::new(text),
+                TextColor(theme.text),
+                TextFont {
+                    font_size: theme.font_size(*role),
+                    ..default()
+                },
+                *role,
+            ));
+            if let Some(marker) = marker {
+                marker.insert(&mut entity);
+            }
+        }
+        Element::Button {
+            label,
+            action,
+            role,
+            marker,
+            width,
+            height,
+            margin,
+            large_text,
+        } => {
+            let font_size = if *large_text {
+                theme.font_sizes.button_large
+            } else {
+                theme.font_sizes.button
+            };
+            let node = Node {
+                width: Val::Px(*width),
+                height: Val::Px(*height),
+                margin: margin.map(UiRect::from).unwrap_or_default(),
+                ..default()
+            };
+            let mut entity = parent.spawn((
+                action_button(
+                    label.clone(),
+                    action.clone(),
+                    theme.palette(*role),
+                    node,
+                    font_size,
+                ),
+                *role,
+            ));
+            if let Some(marker) = marker {
+                marker.insert(&mut entity);
+            }
+        }
+        Element::Row { children } => {
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(theme.spacing.column_gap),
+                        ..default()
+                    },
+                    ButtonRow,
+                ))
+                .with_children(|row| {
+                    for child in children {
+                        spawn_element(row, child, title, theme);
+                    }
+                });
+        }
+    }
+}
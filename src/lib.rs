@@ -0,0 +1,193 @@
+//! Reusable Bevy UI for the Rust iOS example.
+//!
+//! Add [`GuiPlugin`] to any Bevy `App` that already has windowing and UI
+//! rendering set up (for example via `DefaultPlugins`) to get the demo
+//! screen, its state and the systems that drive it.
+
+// Bevy system signatures routinely trip these lints.
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+use bevy::prelude::*;
+use bevy::window::{AppLifecycle, WindowThemeChanged};
+
+mod assets;
+mod buttons;
+mod dialog;
+mod embed;
+mod ffi;
+mod focus;
+mod gestures;
+mod history;
+#[cfg(all(feature = "hot-reload", not(target_os = "ios")))]
+mod hot_reload;
+mod layout;
+mod persistence;
+mod state;
+mod theme;
+mod touch_gestures;
+mod ui;
+
+pub use assets::{asset_plugin, asset_root, bundle_asset_root, ASSET_DIR};
+pub use buttons::{
+    action_button, dispatch_button_actions, emit_button_actions, emit_gesture_actions,
+    style_buttons, update_click_button_role, update_history_buttons, ActionButton, ButtonAction,
+    ButtonActivated, ButtonPalette, ButtonPress, PressPointer,
+};
+pub use dialog::{
+    close_dialogs, confirm_dialog, CancelButton, ConfirmButton, ConfirmDialog, Confirmation,
+    DialogPanel, Modal,
+};
+#[cfg(target_os = "ios")]
+pub use embed::host_app;
+pub use embed::{EmbedState, EmbeddedApp, HostView};
+#[cfg(target_os = "ios")]
+pub use ffi::rust_gui_start;
+pub use ffi::{
+    apply_host_commands, rust_gui_button_state, rust_gui_click_count, rust_gui_pause,
+    rust_gui_pump, rust_gui_register_state_callback, rust_gui_reset, rust_gui_resize,
+    rust_gui_resume, rust_gui_set_button_state, rust_gui_set_click_count, rust_gui_shutdown,
+    rust_gui_state, rust_gui_unregister_state_callback, start_embedded, RustGuiState,
+    RustGuiStateCallback,
+};
+pub use focus::{navigate_focus, show_focus_ring, Focus, FocusMove, FocusRing, Focusable};
+pub use gestures::{
+    recognize_gestures, Gesture, GestureRecognized, GestureRecognizer, GestureSettings,
+};
+pub use history::{handle_undo_shortcuts, GuiCommand, History, DEFAULT_HISTORY_CAPACITY};
+#[cfg(all(feature = "hot-reload", not(target_os = "ios")))]
+pub use hot_reload::{
+    reload_layout, reload_themes, HotReloadPlugin, ThemeHandles, DARK_THEME_PATH, LIGHT_THEME_PATH,
+};
+pub use layout::{
+    spawn_layout, Element, Layout, LayoutError, LayoutLoader, Margin, Marker, BUILTIN_LAYOUT,
+    MAIN_LAYOUT_PATH,
+};
+pub use persistence::{
+    decode_state, default_data_dir, encode_state, DecodeError, FileStorage, MemoryStorage,
+    PersistencePlugin, StateStorage, StateStore, STATE_VERSION,
+};
+pub use state::GuiState;
+pub use theme::{
+    apply_theme, follow_system_theme, toggle_theme, ButtonRole, ButtonRow, FontSizes, ScreenRoot,
+    Spacing, TextRole, Theme, ThemeError, ThemeKind, ThemeLoader, ThemeSettings, Themes,
+    DARK_THEME, LIGHT_THEME,
+};
+pub use touch_gestures::{
+    recognize_touch_gestures, GesturePhase, SwipeDirection, TouchGesture, TouchGestureRecognized,
+    TouchGestures,
+};
+pub use ui::{
+    setup_ui, spawn_loaded_layout, update_ui_text, ClickButton, CounterLabel, DoubleClickButton,
+    InfoButton, MessageLabel, RedoButton, ResetButton, ScreenLayout, SpecialEffectButton,
+    StatusLabel, ThemeToggleButton, UndoButton,
+};
+
+/// Title of the demo app's window and of the label at the top of its screen.
+pub const APP_TITLE: &str = "Rust GUI on iOS";
+
+/// The demo app's own plugins, added on top of Bevy's by both `main()` and the
+/// embedded app so the two run the same GUI.
+pub fn app_plugins() -> (GuiPlugin, PersistencePlugin) {
+    (
+        GuiPlugin {
+            layout: Some(MAIN_LAYOUT_PATH.to_string()),
+            ..default()
+        },
+        PersistencePlugin::default(),
+    )
+}
+
+/// Plugin that spawns the GUI and registers its state and systems.
+#[derive(Debug, Clone)]
+pub struct GuiPlugin {
+    /// Text shown in the title label at the top of the screen.
+    pub title: String,
+    /// Whether the plugin spawns its own `Camera2d`. Disable this when the
+    /// host app already provides a camera for UI rendering.
+    pub spawn_camera: bool,
+    /// Maximum number of commands that can be undone.
+    pub history_capacity: usize,
+    /// Asset path of a `.layout.ron` file describing the screen. `None` uses
+    /// the built-in layout. Loading a file needs `AssetPlugin` to be added
+    /// before this plugin.
+    pub layout: Option<String>,
+}
+
+impl Default for GuiPlugin {
+    fn default() -> Self {
+        Self {
+            title: APP_TITLE.to_string(),
+            spawn_camera: true,
+            history_capacity: DEFAULT_HISTORY_CAPACITY,
+            layout: None,
+        }
+    }
+}
+
+/// Configuration copied out of [`GuiPlugin`] so startup systems can read it.
+#[derive(Resource, Debug, Clone)]
+pub struct GuiConfig {
+    pub title: String,
+    pub spawn_camera: bool,
+    pub layout: Option<String>,
+}
+
+impl Plugin for GuiPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GuiConfig {
+            title: self.title.clone(),
+            spawn_camera: self.spawn_camera,
+            layout: self.layout.clone(),
+        })
+        .init_resource::<GuiState>()
+        .insert_resource(History::with_capacity(self.history_capacity))
+        .init_resource::<Theme>()
+        .init_resource::<Themes>()
+        .init_resource::<ThemeSettings>()
+        .init_resource::<GestureSettings>()
+        .init_resource::<Focus>()
+        .add_message::<ButtonActivated>()
+        .add_message::<GestureRecognized>()
+        .add_message::<TouchGestureRecognized>()
+        // Normally registered by `WindowPlugin`; added here so the GUI also
+        // runs headless, where it can still be suspended and resumed.
+        .add_message::<WindowThemeChanged>()
+        .add_message::<AppLifecycle>()
+        .add_systems(Startup, setup_ui)
+        .add_systems(
+            Update,
+            (
+                (recognize_gestures, recognize_touch_gestures),
+                (emit_button_actions, emit_gesture_actions, navigate_focus),
+                (
+                    dispatch_button_actions,
+                    close_dialogs,
+                    handle_undo_shortcuts,
+                    apply_host_commands,
+                    follow_system_theme,
+                ),
+                (
+                    update_ui_text,
+                    update_click_button_role,
+                    update_history_buttons,
+                ),
+                apply_theme,
+                (style_buttons, show_focus_ring),
+            )
+                .chain(),
+        );
+
+        if app.is_plugin_added::<AssetPlugin>() {
+            app.init_asset::<Layout>()
+                .init_asset_loader::<LayoutLoader>()
+                .init_asset::<Theme>()
+                .init_asset_loader::<ThemeLoader>()
+                .add_systems(
+                    Update,
+                    spawn_loaded_layout
+                        .run_if(resource_exists::<ScreenLayout>)
+                        .before(recognize_gestures),
+                );
+        }
+    }
+}
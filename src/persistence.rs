@@ -0,0 +1,361 @@
+//! Saving and restoring [`GuiState`] across launches.
+//!
+//! The state is written as versioned JSON through a [`StateStorage`] backend.
+//! [`FileStorage`] is used by the app; [`MemoryStorage`] lets tests run without
+//! touching the file system.
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::window::AppLifecycle;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::GuiState;
+
+/// Version written into every saved file. Bump it when the saved layout
+/// changes and teach [`decode_state`] to migrate the old one.
+pub const STATE_VERSION: u32 = 1;
+
+const APP_DIR_NAME: &str = "rust_ios_hello";
+const STATE_FILE_NAME: &str = "gui_state.json";
+
+/// Somewhere to keep the serialized state.
+pub trait StateStorage: Send + Sync + 'static {
+    /// Returns the previously saved contents, or `None` if nothing was saved yet.
+    fn load(&self) -> io::Result<Option<String>>;
+    fn save(&self, contents: &str) -> io::Result<()>;
+}
+
+/// Stores the state in a single file, replacing it atomically on save.
+#[derive(Debug, Clone)]
+pub struct FileStorage {
+    path: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `gui_state.json` inside [`default_data_dir`].
+    pub fn in_default_location() -> Option<Self> {
+        default_data_dir().map(|dir| Self::new(dir.join(STATE_FILE_NAME)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl StateStorage for FileStorage {
+    fn load(&self) -> io::Result<Option<String>> {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&self, contents: &str) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write next to the target and rename so a crash never leaves a
+        // half-written file behind.
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, contents)?;
+        fs::rename(&tmp_path, &self.path)
+    }
+}
+
+/// In-memory backend. Clones share the same slot, so a test can keep one
+/// handle and inspect what the app wrote.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    contents: Arc<Mutex<Option<String>>>,
+}
+
+impl MemoryStorage {
+    pub fn with_contents(contents: impl Into<String>) -> Self {
+        Self {
+            contents: Arc::new(Mutex::new(Some(contents.into()))),
+        }
+    }
+
+    pub fn contents(&self) -> Option<String> {
+        self.contents.lock().unwrap().clone()
+    }
+}
+
+impl StateStorage for MemoryStorage {
+    fn load(&self) -> io::Result<Option<String>> {
+        Ok(self.contents())
+    }
+
+    fn save(&self, contents: &str) -> io::Result<()> {
+        *self.contents.lock().unwrap() = Some(contents.to_string());
+        Ok(())
+    }
+}
+
+/// Platform data directory for the app.
+///
+/// - iOS: the sandbox `Documents` directory.
+/// - macOS: `~/Library/Application Support/rust_ios_hello`.
+/// - Other Unix: `$XDG_DATA_HOME/rust_ios_hello`, falling back to
+///   `~/.local/share/rust_ios_hello`.
+/// - Windows: `%APPDATA%\rust_ios_hello`.
+pub fn default_data_dir() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+
+    if cfg!(target_os = "ios") {
+        return home.map(|home| home.join("Documents"));
+    }
+    if cfg!(target_os = "macos") {
+        return home.map(|home| {
+            home.join("Library")
+                .join("Application Support")
+                .join(APP_DIR_NAME)
+        });
+    }
+    if cfg!(windows) {
+        return std::env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join(APP_DIR_NAME));
+    }
+
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| home.map(|home| home.join(".local").join("share")))
+        .map(|dir| dir.join(APP_DIR_NAME))
+}
+
+/// On-disk layout of the current [`STATE_VERSION`].
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedState {
+    version: u32,
+    click_count: i32,
+    button_state: bool,
+    message: String,
+}
+
+/// Layout written before files carried a version: no `version` and no `message`.
+#[derive(Debug, Deserialize)]
+struct UnversionedState {
+    click_count: i32,
+    button_state: bool,
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    Json(serde_json::Error),
+    UnsupportedVersion(u64),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Json(e) => write!(f, "invalid state file: {}", e),
+            DecodeError::UnsupportedVersion(v) => {
+                write!(f, "unsupported state file version {}", v)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<serde_json::Error> for DecodeError {
+    fn from(e: serde_json::Error) -> Self {
+        DecodeError::Json(e)
+    }
+}
+
+pub fn encode_state(state: &GuiState) -> String {
+    let saved = SavedState {
+        version: STATE_VERSION,
+        click_count: state.click_count,
+        button_state: state.button_state,
+        message: state.message.clone(),
+    };
+    serde_json::to_string_pretty(&saved).expect("GuiState always serializes")
+}
+
+/// Parses a saved file, migrating older versions to the current [`GuiState`].
+pub fn decode_state(contents: &str) -> Result<GuiState, DecodeError> {
+    let value: serde_json::Value = serde_json::from_str(contents)?;
+
+    match value.get("version").and_then(serde_json::Value::as_u64) {
+        None => {
+            let old: UnversionedState = serde_json::from_value(value)?;
+            Ok(GuiState {
+                click_count: old.click_count,
+                button_state: old.button_state,
+                ..default()
+            })
+        }
+        Some(1) => {
+            let saved: SavedState = serde_json::from_value(value)?;
+            Ok(GuiState {
+                click_count: saved.click_count,
+                button_state: saved.button_state,
+                message: saved.message,
+            })
+        }
+        Some(other) => Err(DecodeError::UnsupportedVersion(other)),
+    }
+}
+
+/// Loads [`GuiState`] at `Startup` and saves it a short while after it changes.
+///
+/// Without this plugin the state lives only as long as the process.
+#[derive(Clone)]
+pub struct PersistencePlugin {
+    pub storage: Arc<dyn StateStorage>,
+    /// How long the state has to stay unchanged before it is written.
+    pub debounce: Duration,
+}
+
+impl PersistencePlugin {
+    pub fn new(storage: impl StateStorage) -> Self {
+        Self {
+            storage: Arc::new(storage),
+            debounce: Duration::from_millis(500),
+        }
+    }
+}
+
+impl Default for PersistencePlugin {
+    /// Uses [`FileStorage`] in the platform data directory, or keeps the state
+    /// in memory if no such directory can be determined.
+    fn default() -> Self {
+        match FileStorage::in_default_location() {
+            Some(storage) => Self::new(storage),
+            None => {
+                eprintln!("No data directory found, GUI state will not be persisted");
+                Self::new(MemoryStorage::default())
+            }
+        }
+    }
+}
+
+/// The storage backend selected by [`PersistencePlugin`].
+#[derive(Resource, Clone)]
+pub struct StateStore(pub Arc<dyn StateStorage>);
+
+/// Tracks what was last written so unchanged state is not saved again.
+#[derive(Resource)]
+struct SaveDebounce {
+    delay: Duration,
+    last_saved: Option<GuiState>,
+    dirty_since: Option<Duration>,
+}
+
+impl Plugin for PersistencePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(StateStore(self.storage.clone()))
+            .insert_resource(SaveDebounce {
+                delay: self.debounce,
+                last_saved: None,
+                dirty_since: None,
+            })
+            .add_systems(Startup, load_state)
+            // After `Update`, so changes count from the frame that made them.
+            .add_systems(PostUpdate, save_state_debounced)
+            .add_systems(Last, (save_state_on_exit, save_state_on_suspend));
+    }
+}
+
+fn load_state(
+    store: Res<StateStore>,
+    mut gui_state: ResMut<GuiState>,
+    mut debounce: ResMut<SaveDebounce>,
+) {
+    let loaded = match store.0.load() {
+        Ok(Some(contents)) => match decode_state(&contents) {
+            Ok(state) => state,
+            Err(e) => {
+                eprintln!("Ignoring saved GUI state: {}", e);
+                GuiState::default()
+            }
+        },
+        Ok(None) => GuiState::default(),
+        Err(e) => {
+            eprintln!("Failed to read saved GUI state: {}", e);
+            GuiState::default()
+        }
+    };
+
+    loaded.sync_globals();
+    debounce.last_saved = Some(loaded.clone());
+    *gui_state = loaded;
+}
+
+fn save_state_debounced(
+    time: Res<Time<Real>>,
+    store: Res<StateStore>,
+    gui_state: Res<GuiState>,
+    mut debounce: ResMut<SaveDebounce>,
+) {
+    let now = time.elapsed();
+
+    if gui_state.is_changed() && debounce.last_saved.as_ref() != Some(&*gui_state) {
+        debounce.dirty_since = Some(now);
+    }
+
+    let Some(dirty_since) = debounce.dirty_since else {
+        return;
+    };
+    if now.saturating_sub(dirty_since) >= debounce.delay
+        && !write_state(&store, &gui_state, &mut debounce)
+    {
+        // Wait another full delay before retrying a failed write.
+        debounce.dirty_since = Some(now);
+    }
+}
+
+fn save_state_on_exit(
+    mut exit_events: MessageReader<AppExit>,
+    store: Res<StateStore>,
+    gui_state: Res<GuiState>,
+    mut debounce: ResMut<SaveDebounce>,
+) {
+    if exit_events.read().next().is_some() && debounce.dirty_since.is_some() {
+        write_state(&store, &gui_state, &mut debounce);
+    }
+}
+
+/// iOS may kill a suspended app without warning, so don't wait for the
+/// debounce delay.
+fn save_state_on_suspend(
+    mut lifecycle_events: MessageReader<AppLifecycle>,
+    store: Res<StateStore>,
+    gui_state: Res<GuiState>,
+    mut debounce: ResMut<SaveDebounce>,
+) {
+    let suspending = lifecycle_events
+        .read()
+        .any(|event| *event == AppLifecycle::WillSuspend);
+    if suspending && debounce.dirty_since.is_some() {
+        write_state(&store, &gui_state, &mut debounce);
+    }
+}
+
+/// Returns whether the write succeeded; on failure the state stays dirty.
+fn write_state(store: &StateStore, gui_state: &GuiState, debounce: &mut SaveDebounce) -> bool {
+    match store.0.save(&encode_state(gui_state)) {
+        Ok(()) => {
+            debounce.last_saved = Some(gui_state.clone());
+            debounce.dirty_since = None;
+            true
+        }
+        Err(e) => {
+            eprintln!("Failed to save GUI state: {}", e);
+            false
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+
+use crate::ffi;
+
+// Resources for Bevy ECS
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct GuiState {
+    pub click_count: i32,
+    pub button_state: bool,
+    pub message: String,
+}
+
+impl Default for GuiState {
+    fn default() -> Self {
+        Self {
+            click_count: 0,
+            button_state: false,
+            message: "Welcome to Rust GUI!".to_string(),
+        }
+    }
+}
+
+impl GuiState {
+    /// Human readable form of `button_state`, as shown in the UI.
+    pub fn state_label(&self) -> &'static str {
+        if self.button_state {
+            "ON"
+        } else {
+            "OFF"
+        }
+    }
+
+    /// Mirrors the state into the globals read by the C API.
+    pub(crate) fn sync_globals(&self) {
+        ffi::publish(self.click_count, self.button_state);
+    }
+}
@@ -0,0 +1,427 @@
+//! Semantic colors, font sizes and spacing for the GUI.
+//!
+//! Themes are described in `.theme.ron` files; the dark and light ones in
+//! `assets/themes` are compiled in. Widgets tagged with [`TextRole`],
+//! [`ButtonRole`] or [`ScreenRoot`] are restyled by [`apply_theme`] whenever
+//! the [`Theme`] resource changes.
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+#[cfg(target_os = "ios")]
+use bevy::ecs::system::NonSendMarker;
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowTheme, WindowThemeChanged};
+use serde::Deserialize;
+use std::fmt;
+
+use crate::buttons::ButtonPalette;
+use crate::dialog::DialogPanel;
+
+/// Built-in dark theme, also the default.
+pub const DARK_THEME: &str = include_str!("../assets/themes/dark.theme.ron");
+/// Built-in light theme.
+pub const LIGHT_THEME: &str = include_str!("../assets/themes/light.theme.ron");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum ThemeKind {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl ThemeKind {
+    pub fn toggled(self) -> Self {
+        match self {
+            ThemeKind::Dark => ThemeKind::Light,
+            ThemeKind::Light => ThemeKind::Dark,
+        }
+    }
+}
+
+impl From<WindowTheme> for ThemeKind {
+    fn from(theme: WindowTheme) -> Self {
+        match theme {
+            WindowTheme::Dark => ThemeKind::Dark,
+            WindowTheme::Light => ThemeKind::Light,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FontSizes {
+    pub title: f32,
+    pub status: f32,
+    pub counter: f32,
+    pub message: f32,
+    /// Label of the large main button.
+    pub button_large: f32,
+    pub button: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spacing {
+    /// Gap between the rows of the screen.
+    pub row_gap: f32,
+    /// Gap between buttons sharing a row.
+    pub column_gap: f32,
+    /// Padding around the whole screen.
+    pub padding: f32,
+}
+
+#[derive(Resource, Asset, TypePath, Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub kind: ThemeKind,
+    pub background: Color,
+    pub text: Color,
+    pub button_text: Color,
+    pub primary: ButtonPalette,
+    /// Used by the main click button while `button_state` is on.
+    pub active: ButtonPalette,
+    pub neutral: ButtonPalette,
+    pub danger: ButtonPalette,
+    /// Outline around the node with keyboard or gamepad focus.
+    pub focus_ring: Color,
+    pub font_sizes: FontSizes,
+    pub spacing: Spacing,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    /// The parsed [`DARK_THEME`].
+    pub fn dark() -> Self {
+        Self::parse(DARK_THEME).expect("built-in dark theme is valid")
+    }
+
+    /// The parsed [`LIGHT_THEME`].
+    pub fn light() -> Self {
+        Self::parse(LIGHT_THEME).expect("built-in light theme is valid")
+    }
+
+    /// Parses a `.theme.ron` file.
+    pub fn parse(source: &str) -> Result<Self, ThemeError> {
+        let file: ThemeFile = ron::from_str(source).map_err(|e| ThemeError::Syntax {
+            line: e.position.line,
+            column: e.position.col,
+            message: e.code.to_string(),
+        })?;
+        Ok(file.into())
+    }
+
+    pub fn palette(&self, role: ButtonRole) -> ButtonPalette {
+        match role {
+            ButtonRole::Primary => self.primary,
+            ButtonRole::Active => self.active,
+            ButtonRole::Neutral => self.neutral,
+            ButtonRole::Danger => self.danger,
+        }
+    }
+
+    pub fn font_size(&self, role: TextRole) -> f32 {
+        match role {
+            TextRole::Title => self.font_sizes.title,
+            TextRole::Status => self.font_sizes.status,
+            TextRole::Counter => self.font_sizes.counter,
+            TextRole::Message => self.font_sizes.message,
+        }
+    }
+}
+
+/// Layout of a `.theme.ron` file. Colors are sRGB `(red, green, blue)`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    kind: ThemeKind,
+    background: Rgb,
+    text: Rgb,
+    button_text: Rgb,
+    primary: PaletteFile,
+    active: PaletteFile,
+    neutral: PaletteFile,
+    danger: PaletteFile,
+    focus_ring: Rgb,
+    font_sizes: FontSizes,
+    spacing: Spacing,
+}
+
+type Rgb = (f32, f32, f32);
+
+fn color((r, g, b): Rgb) -> Color {
+    Color::srgb(r, g, b)
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PaletteFile {
+    normal: Rgb,
+    hovered: Rgb,
+    pressed: Rgb,
+    disabled: Rgb,
+}
+
+impl From<PaletteFile> for ButtonPalette {
+    fn from(file: PaletteFile) -> Self {
+        Self {
+            normal: color(file.normal),
+            hovered: color(file.hovered),
+            pressed: color(file.pressed),
+            disabled: color(file.disabled),
+        }
+    }
+}
+
+impl From<ThemeFile> for Theme {
+    fn from(file: ThemeFile) -> Self {
+        Self {
+            kind: file.kind,
+            background: color(file.background),
+            text: color(file.text),
+            button_text: color(file.button_text),
+            primary: file.primary.into(),
+            active: file.active.into(),
+            neutral: file.neutral.into(),
+            danger: file.danger.into(),
+            focus_ring: color(file.focus_ring),
+            font_sizes: file.font_sizes,
+            spacing: file.spacing,
+        }
+    }
+}
+
+/// Why a theme file was rejected. Line and column numbers are 1-based.
+#[derive(Debug)]
+pub enum ThemeError {
+    Io(std::io::Error),
+    Syntax {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeError::Io(e) => write!(f, "failed to read theme: {}", e),
+            ThemeError::Syntax {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+impl From<std::io::Error> for ThemeError {
+    fn from(e: std::io::Error) -> Self {
+        ThemeError::Io(e)
+    }
+}
+
+#[derive(Default, TypePath)]
+pub struct ThemeLoader;
+
+impl AssetLoader for ThemeLoader {
+    type Asset = Theme;
+    type Settings = ();
+    type Error = ThemeError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Theme, ThemeError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let source = std::str::from_utf8(&bytes)
+            .map_err(|e| ThemeError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))?;
+        Theme::parse(source)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme.ron"]
+    }
+}
+
+/// The dark and light themes the app switches between.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct Themes {
+    pub dark: Theme,
+    pub light: Theme,
+}
+
+impl Default for Themes {
+    fn default() -> Self {
+        Self {
+            dark: Theme::dark(),
+            light: Theme::light(),
+        }
+    }
+}
+
+impl Themes {
+    pub fn get(&self, kind: ThemeKind) -> &Theme {
+        match kind {
+            ThemeKind::Dark => &self.dark,
+            ThemeKind::Light => &self.light,
+        }
+    }
+}
+
+/// Whether the theme tracks the system light/dark appearance.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct ThemeSettings {
+    /// Cleared once the user picks a theme by hand.
+    pub follow_system: bool,
+}
+
+impl Default for ThemeSettings {
+    fn default() -> Self {
+        Self {
+            follow_system: true,
+        }
+    }
+}
+
+/// Which theme palette a button uses.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ButtonRole {
+    Primary,
+    Active,
+    Neutral,
+    Danger,
+}
+
+/// Which theme color and font size a text label uses.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum TextRole {
+    Title,
+    Status,
+    Counter,
+    Message,
+}
+
+/// The root container of the screen; takes the theme background and spacing.
+#[derive(Component)]
+pub struct ScreenRoot;
+
+/// Rows of buttons; take the theme column gap.
+#[derive(Component)]
+pub struct ButtonRow;
+
+/// Switches between the dark and light theme and stops following the system.
+pub fn toggle_theme(theme: &mut Theme, themes: &Themes, settings: &mut ThemeSettings) {
+    settings.follow_system = false;
+    *theme = themes.get(theme.kind.toggled()).clone();
+}
+
+/// The appearance UIKit reports for the app. winit doesn't report it on iOS,
+/// and embedded apps have no winit window at all.
+#[cfg(target_os = "ios")]
+fn uikit_theme() -> Option<WindowTheme> {
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+
+    // UIUserInterfaceStyle: 0 is unspecified, 1 light, 2 dark.
+    let style: isize = unsafe {
+        let traits: *mut Object = msg_send![class!(UITraitCollection), currentTraitCollection];
+        msg_send![traits, userInterfaceStyle]
+    };
+    match style {
+        1 => Some(WindowTheme::Light),
+        2 => Some(WindowTheme::Dark),
+        _ => None,
+    }
+}
+
+/// Switches theme to match the system appearance, unless the user picked one
+/// by hand. The starting appearance is read from the primary window once
+/// winit has created it, and changes arrive as `WindowThemeChanged`. On iOS
+/// both come from UIKit instead, checked every frame.
+pub fn follow_system_theme(
+    mut theme_changes: MessageReader<WindowThemeChanged>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut read_window: Local<bool>,
+    settings: Res<ThemeSettings>,
+    themes: Res<Themes>,
+    mut theme: ResMut<Theme>,
+    // UIKit may only be asked from the main thread.
+    #[cfg(target_os = "ios")] _main_thread: NonSendMarker,
+) {
+    let mut system = theme_changes.read().last().map(|change| change.theme);
+    if !*read_window {
+        if let Some(initial) = windows.single().ok().and_then(|window| window.window_theme) {
+            *read_window = true;
+            system = system.or(Some(initial));
+        }
+    }
+    #[cfg(target_os = "ios")]
+    {
+        system = system.or_else(uikit_theme);
+    }
+
+    let Some(system) = system else {
+        return;
+    };
+    let kind = ThemeKind::from(system);
+    if settings.follow_system && theme.kind != kind {
+        *theme = themes.get(kind).clone();
+    }
+}
+
+/// Restyles themed widgets when the theme changes, and buttons whose
+/// [`ButtonRole`] changed.
+pub fn apply_theme(
+    theme: Res<Theme>,
+    mut root_query: Query<(&mut Node, &mut BackgroundColor), With<ScreenRoot>>,
+    mut panel_query: Query<&mut BackgroundColor, (With<DialogPanel>, Without<ScreenRoot>)>,
+    mut row_query: Query<&mut Node, (With<ButtonRow>, Without<ScreenRoot>)>,
+    mut text_query: Query<(&TextRole, &mut TextColor, &mut TextFont)>,
+    mut button_query: Query<(Ref<ButtonRole>, &mut ButtonPalette, &Children)>,
+    mut label_query: Query<&mut TextColor, Without<TextRole>>,
+) {
+    let theme_changed = theme.is_changed();
+
+    for (role, mut palette, children) in &mut button_query {
+        if !theme_changed && !role.is_changed() {
+            continue;
+        }
+        palette.set_if_neq(theme.palette(*role));
+        let mut labels = label_query.iter_many_mut(children);
+        while let Some(mut color) = labels.fetch_next() {
+            color.0 = theme.button_text;
+        }
+    }
+
+    if !theme_changed {
+        return;
+    }
+
+    for (mut node, mut background) in &mut root_query {
+        node.row_gap = Val::Px(theme.spacing.row_gap);
+        node.padding = UiRect::all(Val::Px(theme.spacing.padding));
+        background.0 = theme.background;
+    }
+
+    for mut background in &mut panel_query {
+        background.0 = theme.background;
+    }
+
+    for mut node in &mut row_query {
+        node.column_gap = Val::Px(theme.spacing.column_gap);
+    }
+
+    for (role, mut color, mut font) in &mut text_query {
+        color.0 = theme.text;
+        font.font_size = theme.font_size(*role);
+    }
+}
@@ -0,0 +1,294 @@
+//! Swipes, pans and two-finger pinches on UI nodes.
+//!
+//! A node with [`TouchGestures`] owns every touch that starts inside it (the
+//! topmost one, where such nodes overlap) and reports what those fingers do
+//! as [`TouchGestureRecognized`] messages. Where [`GestureRecognizer`]
+//! follows a single pointer through `Interaction`, this reads each finger
+//! from `Touches`, so it works for any node and any number of fingers.
+//! Thresholds come from [`GestureSettings`].
+//!
+//! [`GestureRecognizer`]: crate::GestureRecognizer
+
+use bevy::input::touch::{Touch, Touches};
+use bevy::prelude::*;
+use std::time::Duration;
+
+use crate::gestures::GestureSettings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Where a continuous gesture is in its lifetime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GesturePhase {
+    Started,
+    Changed,
+    Ended,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TouchGesture {
+    /// A quick one-finger flick, sent when the finger lifts.
+    Swipe(SwipeDirection),
+    /// One finger dragging. `translation` is from where the finger landed,
+    /// `delta` from the previous pan message; both in logical pixels.
+    Pan {
+        phase: GesturePhase,
+        translation: Vec2,
+        delta: Vec2,
+    },
+    /// Two fingers moving apart or together. `scale` is the distance between
+    /// them relative to when the second one landed, `delta` relative to the
+    /// previous pinch message. `center` is midway between the fingers.
+    Pinch {
+        phase: GesturePhase,
+        scale: f32,
+        delta: f32,
+        center: Vec2,
+    },
+}
+
+/// Sent when a node with [`TouchGestures`] recognizes a gesture.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct TouchGestureRecognized {
+    pub entity: Entity,
+    pub gesture: TouchGesture,
+}
+
+/// Recognizes swipes, pans and pinches made by touches that start on this
+/// node.
+#[derive(Component, Debug, Clone, Default)]
+pub struct TouchGestures {
+    /// At most two; further fingers are ignored.
+    fingers: Vec<Finger>,
+    /// Translation of the pan in progress.
+    pan: Option<Vec2>,
+    /// Scale of the pinch in progress.
+    pinch: Option<f32>,
+    /// Distance between the fingers when the second one landed.
+    pinch_start: f32,
+    /// Cleared once a second finger joins, so the last one lifting from a
+    /// pinch isn't taken for a swipe.
+    single_finger: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Finger {
+    id: u64,
+    start: Vec2,
+    started: Duration,
+    position: Vec2,
+}
+
+impl TouchGestures {
+    fn add_finger(&mut self, touch: &Touch, now: Duration) -> Vec<TouchGesture> {
+        let mut ended = Vec::new();
+        if self.fingers.len() >= 2 {
+            return ended;
+        }
+        if self.fingers.is_empty() {
+            self.single_finger = true;
+        } else {
+            ended.extend(self.end_pan());
+            self.single_finger = false;
+            self.pinch_start = self.fingers[0].position.distance(touch.position());
+        }
+        self.fingers.push(Finger {
+            id: touch.id(),
+            start: touch.position(),
+            started: now,
+            position: touch.position(),
+        });
+        ended
+    }
+
+    fn end_pan(&mut self) -> Option<TouchGesture> {
+        self.pan.take().map(|translation| TouchGesture::Pan {
+            phase: GesturePhase::Ended,
+            translation,
+            delta: Vec2::ZERO,
+        })
+    }
+
+    fn end_pinch(&mut self) -> Option<TouchGesture> {
+        let center = self.center();
+        self.pinch.take().map(|scale| TouchGesture::Pinch {
+            phase: GesturePhase::Ended,
+            scale,
+            delta: 1.0,
+            center,
+        })
+    }
+
+    fn center(&self) -> Vec2 {
+        match &self.fingers[..] {
+            [a, b] => a.position.midpoint(b.position),
+            [a] => a.position,
+            _ => Vec2::ZERO,
+        }
+    }
+
+    /// Gestures made by the fingers' current positions.
+    fn moved(&mut self, settings: &GestureSettings) -> Option<TouchGesture> {
+        match &self.fingers[..] {
+            [finger] => {
+                let translation = finger.position - finger.start;
+                let (phase, delta) = match self.pan {
+                    None if translation.length() > settings.tap_slop => {
+                        (GesturePhase::Started, translation)
+                    }
+                    Some(last) if last != translation => {
+                        (GesturePhase::Changed, translation - last)
+                    }
+                    _ => return None,
+                };
+                self.pan = Some(translation);
+                Some(TouchGesture::Pan {
+                    phase,
+                    translation,
+                    delta,
+                })
+            }
+            [a, b] => {
+                let distance = a.position.distance(b.position);
+                if self.pinch_start <= 0.0 {
+                    return None;
+                }
+                let scale = distance / self.pinch_start;
+                let (phase, delta) = match self.pinch {
+                    None if (distance - self.pinch_start).abs() > settings.tap_slop => {
+                        (GesturePhase::Started, scale)
+                    }
+                    Some(last) if last != scale => (GesturePhase::Changed, scale / last),
+                    _ => return None,
+                };
+                self.pinch = Some(scale);
+                Some(TouchGesture::Pinch {
+                    phase,
+                    scale,
+                    delta,
+                    center: self.center(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Gestures finished by fingers lifting. Canceled fingers end gestures
+    /// without making a swipe.
+    fn lifted(
+        &mut self,
+        lifted: &[(u64, bool)],
+        now: Duration,
+        settings: &GestureSettings,
+    ) -> Vec<TouchGesture> {
+        let mut ended = Vec::new();
+        if lifted.is_empty() {
+            return ended;
+        }
+        ended.extend(self.end_pinch());
+        ended.extend(self.end_pan());
+
+        if let [finger] = &self.fingers[..] {
+            let translation = finger.position - finger.start;
+            let canceled = lifted.iter().any(|&(_, canceled)| canceled);
+            let quick = now.saturating_sub(finger.started) <= settings.swipe_time;
+            if self.single_finger
+                && !canceled
+                && quick
+                && translation.length() >= settings.swipe_distance
+            {
+                ended.push(TouchGesture::Swipe(direction(translation)));
+            }
+        }
+
+        self.fingers
+            .retain(|finger| !lifted.iter().any(|&(id, _)| id == finger.id));
+        // The finger left behind starts a fresh pan from where it is.
+        for finger in &mut self.fingers {
+            finger.start = finger.position;
+        }
+        ended
+    }
+}
+
+/// The main axis of `translation`. Touch positions grow downwards.
+fn direction(translation: Vec2) -> SwipeDirection {
+    if translation.x.abs() >= translation.y.abs() {
+        if translation.x < 0.0 {
+            SwipeDirection::Left
+        } else {
+            SwipeDirection::Right
+        }
+    } else if translation.y < 0.0 {
+        SwipeDirection::Up
+    } else {
+        SwipeDirection::Down
+    }
+}
+
+pub fn recognize_touch_gestures(
+    time: Res<Time>,
+    settings: Res<GestureSettings>,
+    touches: Res<Touches>,
+    mut nodes: Query<(
+        Entity,
+        &ComputedNode,
+        &UiGlobalTransform,
+        &mut TouchGestures,
+    )>,
+    mut recognized: MessageWriter<TouchGestureRecognized>,
+) {
+    let now = time.elapsed();
+    let mut write = |entity, gestures: Vec<TouchGesture>| {
+        for gesture in gestures {
+            recognized.write(TouchGestureRecognized { entity, gesture });
+        }
+    };
+
+    for touch in touches.iter_just_pressed() {
+        // Touches are in logical pixels, layout in physical ones.
+        let target = nodes
+            .iter()
+            .filter(|(_, node, transform, _)| {
+                node.contains_point(**transform, touch.position() / node.inverse_scale_factor)
+            })
+            .max_by_key(|(_, node, _, _)| node.stack_index)
+            .map(|(entity, ..)| entity);
+        if let Some(entity) = target {
+            let (_, _, _, mut gestures) = nodes.get_mut(entity).expect("entity was just queried");
+            let ended = gestures.add_finger(touch, now);
+            write(entity, ended);
+        }
+    }
+
+    for (entity, _, _, mut gestures) in &mut nodes {
+        if gestures.fingers.is_empty() {
+            continue;
+        }
+        let mut lifted = Vec::new();
+        for finger in &mut gestures.fingers {
+            let canceled = touches
+                .iter_just_canceled()
+                .find(|touch| touch.id() == finger.id);
+            match touches.get_pressed(finger.id) {
+                Some(touch) => finger.position = touch.position(),
+                None => {
+                    if let Some(touch) = touches.get_released(finger.id).or(canceled) {
+                        finger.position = touch.position();
+                    }
+                    lifted.push((finger.id, touches.get_released(finger.id).is_none()));
+                }
+            }
+        }
+
+        let mut gestures_made: Vec<TouchGesture> = gestures.moved(&settings).into_iter().collect();
+        gestures_made.extend(gestures.lifted(&lifted, now, &settings));
+        write(entity, gestures_made);
+    }
+}
@@ -0,0 +1,166 @@
+use bevy::prelude::*;
+
+use bevy::asset::LoadState;
+
+use crate::history::History;
+use crate::layout::{spawn_layout, Layout};
+use crate::theme::Theme;
+use crate::{GuiConfig, GuiState};
+
+// Marker components for UI elements, so tests and host apps can find them
+#[derive(Component)]
+pub struct ClickButton;
+
+#[derive(Component)]
+pub struct CounterLabel;
+
+#[derive(Component)]
+pub struct StatusLabel;
+
+#[derive(Component)]
+pub struct MessageLabel;
+
+#[derive(Component)]
+pub struct ResetButton;
+
+#[derive(Component)]
+pub struct InfoButton;
+
+#[derive(Component)]
+pub struct DoubleClickButton;
+
+#[derive(Component)]
+pub struct SpecialEffectButton;
+
+#[derive(Component)]
+pub struct UndoButton;
+
+#[derive(Component)]
+pub struct RedoButton;
+
+#[derive(Component)]
+pub struct ThemeToggleButton;
+
+/// The screen built from a layout file, once it has loaded.
+#[derive(Resource, Debug)]
+pub struct ScreenLayout {
+    pub handle: Handle<Layout>,
+    /// Root entity of the spawned screen, `None` while the file is loading.
+    pub root: Option<Entity>,
+}
+
+pub fn setup_ui(
+    mut commands: Commands,
+    config: Res<GuiConfig>,
+    theme: Res<Theme>,
+    gui_state: Res<GuiState>,
+    asset_server: Option<Res<AssetServer>>,
+) {
+    // Let the host read the initial state before anything is clicked
+    gui_state.sync_globals();
+
+    // Camera
+    if config.spawn_camera {
+        commands.spawn(Camera2d);
+    }
+
+    match (&config.layout, asset_server) {
+        (Some(path), Some(asset_server)) => {
+            commands.insert_resource(ScreenLayout {
+                handle: asset_server.load(path),
+                root: None,
+            });
+        }
+        (Some(path), None) => {
+            eprintln!(
+                "No AssetServer available, using the built-in layout instead of {}",
+                path
+            );
+            spawn_layout(&mut commands, &Layout::builtin(), &config.title, &theme);
+        }
+        (None, _) => {
+            spawn_layout(&mut commands, &Layout::builtin(), &config.title, &theme);
+        }
+    }
+}
+
+/// Spawns the screen once the configured layout file has loaded, falling
+/// back to the built-in layout if it fails to load.
+pub fn spawn_loaded_layout(
+    mut commands: Commands,
+    mut screen: ResMut<ScreenLayout>,
+    config: Res<GuiConfig>,
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+    layouts: Res<Assets<Layout>>,
+    mut gui_state: ResMut<GuiState>,
+    mut history: ResMut<History>,
+) {
+    if screen.root.is_some() {
+        return;
+    }
+
+    let layout = match asset_server.load_state(&screen.handle) {
+        LoadState::Loaded => match layouts.get(&screen.handle) {
+            Some(layout) => layout.clone(),
+            None => return,
+        },
+        LoadState::Failed(e) => {
+            eprintln!("Failed to load layout, using the built-in one: {}", e);
+            Layout::builtin()
+        }
+        LoadState::NotLoaded | LoadState::Loading => return,
+    };
+
+    screen.root = Some(spawn_layout(&mut commands, &layout, &config.title, &theme));
+
+    // The widgets start out with placeholder text and default roles, and the
+    // state they show may have been restored long before they arrived.
+    gui_state.set_changed();
+    history.set_changed();
+}
+
+pub fn update_ui_text(
+    gui_state: Res<GuiState>,
+    mut counter_query: Query<
+        &mut Text,
+        (
+            With<CounterLabel>,
+            Without<StatusLabel>,
+            Without<MessageLabel>,
+        ),
+    >,
+    mut status_query: Query<
+        &mut Text,
+        (
+            With<StatusLabel>,
+            Without<CounterLabel>,
+            Without<MessageLabel>,
+        ),
+    >,
+    mut message_query: Query<
+        &mut Text,
+        (
+            With<MessageLabel>,
+            Without<CounterLabel>,
+            Without<StatusLabel>,
+        ),
+    >,
+) {
+    if gui_state.is_changed() {
+        // Update counter
+        for mut text in &mut counter_query {
+            **text = format!("Clicks: {}", gui_state.click_count);
+        }
+
+        // Update status
+        for mut text in &mut status_query {
+            **text = format!("Button State: {}", gui_state.state_label());
+        }
+
+        // Update message
+        for mut text in &mut message_query {
+            **text = gui_state.message.clone();
+        }
+    }
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use bevy::prelude::*;
+use common::Harness;
+use ios_bundle::{assemble, BundleSpec, IosMetadata};
+use rust_ios_hello::{
+    asset_root, bundle_asset_root, ClickButton, GuiPlugin, ScreenLayout, MAIN_LAYOUT_PATH,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+/// Every file below `dir`, relative to it, sorted.
+fn tree(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path.strip_prefix(dir).unwrap().to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Bundles this package's `assets/` the way `cargo xtask bundle` does,
+/// returning the path of the executable inside the bundle.
+fn bundle_app(dir: &Path) -> PathBuf {
+    let root = Path::new(MANIFEST_DIR);
+    let manifest = fs::read_to_string(root.join("Cargo.toml")).unwrap();
+    let mut metadata = IosMetadata::from_package_manifest(&manifest).unwrap();
+    // Icons aren't what this is about, and scaling them is slow in debug.
+    metadata.icon = None;
+
+    let binary = dir.join("rust_ios_hello");
+    fs::write(&binary, "binary").unwrap();
+    let spec = BundleSpec {
+        resources: vec![metadata.assets_resource(root)],
+        metadata,
+        manifest_dir: root.to_path_buf(),
+        executable: binary,
+    };
+    let bundle = dir.join("RustApp.app");
+    assemble(&spec, &bundle, &dir.join("stamps"), &mut |_| {}).unwrap();
+    bundle.join(&spec.metadata.executable)
+}
+
+#[test]
+fn desktop_reads_the_package_assets() {
+    assert_eq!(asset_root(), "assets");
+}
+
+#[test]
+fn bundle_assets_sit_next_to_the_executable() {
+    assert_eq!(
+        bundle_asset_root(Path::new(
+            "/private/var/containers/Bundle/Application/X/RustApp.app/rust-ios-test"
+        )),
+        Path::new("/private/var/containers/Bundle/Application/X/RustApp.app/assets")
+    );
+}
+
+#[test]
+fn bundle_contains_every_asset() {
+    let dir = tempfile::tempdir().unwrap();
+    let executable = bundle_app(dir.path());
+
+    let bundled = bundle_asset_root(&executable);
+    assert_eq!(
+        tree(&bundled),
+        tree(&Path::new(MANIFEST_DIR).join("assets"))
+    );
+    for path in [
+        "layouts/main.layout.ron",
+        "themes/dark.theme.ron",
+        "themes/light.theme.ron",
+    ] {
+        assert!(bundled.join(path).is_file(), "{} is not bundled", path);
+    }
+}
+
+#[test]
+fn layout_loads_from_the_bundle() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = bundle_asset_root(&bundle_app(dir.path()));
+
+    let mut harness = Harness::build(
+        GuiPlugin {
+            layout: Some(MAIN_LAYOUT_PATH.to_string()),
+            ..default()
+        },
+        |app| {
+            app.add_plugins(AssetPlugin {
+                file_path: root.to_str().unwrap().to_string(),
+                ..default()
+            });
+        },
+    );
+    for _ in 0..100 {
+        if harness
+            .app
+            .world()
+            .resource::<ScreenLayout>()
+            .root
+            .is_some()
+        {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        harness.step();
+    }
+
+    assert!(
+        harness
+            .app
+            .world()
+            .resource::<ScreenLayout>()
+            .root
+            .is_some(),
+        "layouts/main.layout.ron did not load from {}",
+        root.display()
+    );
+    harness.entity::<ClickButton>();
+}
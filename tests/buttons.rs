@@ -0,0 +1,403 @@
+mod common;
+
+use bevy::input::touch::TouchPhase;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use common::Harness;
+use rust_ios_hello::{
+    action_button, ActionButton, ButtonAction, ButtonActivated, ClickButton, ConfirmButton,
+    CounterLabel, DoubleClickButton, GuiState, InfoButton, MessageLabel, ResetButton,
+    SpecialEffectButton, StatusLabel, Theme, UndoButton,
+};
+use std::time::Duration;
+
+#[test]
+fn starts_with_default_state_and_labels() {
+    let mut harness = Harness::new();
+
+    assert_eq!(*harness.state(), GuiState::default());
+    assert_eq!(harness.text::<CounterLabel>(), "Clicks: 0");
+    assert_eq!(harness.text::<StatusLabel>(), "Button State: OFF");
+    assert_eq!(harness.text::<MessageLabel>(), "Welcome to Rust GUI!");
+}
+
+#[test]
+fn click_button_increments_and_toggles() {
+    let mut harness = Harness::new();
+
+    harness.click::<ClickButton>();
+    assert_eq!(harness.state().click_count, 1);
+    assert!(harness.state().button_state);
+    assert_eq!(harness.text::<CounterLabel>(), "Clicks: 1");
+    assert_eq!(harness.text::<StatusLabel>(), "Button State: ON");
+    assert_eq!(
+        harness.text::<MessageLabel>(),
+        "Button clicked for the first time!"
+    );
+
+    harness.click::<ClickButton>();
+    assert_eq!(harness.state().click_count, 2);
+    assert!(!harness.state().button_state);
+    assert_eq!(
+        harness.text::<MessageLabel>(),
+        "Button clicked 2 times! Current state: OFF"
+    );
+}
+
+#[test]
+fn press_activates_once_on_release() {
+    let mut harness = Harness::new();
+    let center = harness.center::<ClickButton>();
+
+    harness.move_cursor(Some(center));
+    harness.mouse_button(ButtonState::Pressed);
+    harness.step();
+    harness.step();
+    harness.step();
+    assert_eq!(harness.state().click_count, 0);
+
+    harness.mouse_button(ButtonState::Released);
+    harness.step();
+    harness.step();
+    assert_eq!(harness.state().click_count, 1);
+}
+
+/// Puts finger `id` down at `position`.
+fn touch_down(harness: &mut Harness, id: u64, position: Vec2) {
+    harness.touch(id, TouchPhase::Started, position);
+    harness.step();
+}
+
+/// Lifts finger `id` at `position`. Bevy UI clears `Pressed` from every
+/// button when any finger lifts.
+fn touch_up(harness: &mut Harness, id: u64, position: Vec2) {
+    harness.touch(id, TouchPhase::Moved, position);
+    harness.touch(id, TouchPhase::Ended, position);
+    harness.step();
+}
+
+/// A point in the top left corner, clear of every button.
+const EMPTY: Vec2 = Vec2::new(5.0, 5.0);
+
+#[test]
+fn touch_up_inside_activates() {
+    let mut harness = Harness::new();
+    let click = harness.entity::<ClickButton>();
+    let center = harness.center::<ClickButton>();
+
+    touch_down(&mut harness, 0, center + Vec2::new(-50.0, -10.0));
+    assert_eq!(harness.state().click_count, 0);
+    assert_eq!(
+        background(&mut harness, click),
+        theme(&harness).primary.pressed
+    );
+
+    touch_up(&mut harness, 0, center + Vec2::new(-40.0, 10.0));
+    assert_eq!(harness.state().click_count, 1);
+}
+
+#[test]
+fn dragging_off_cancels_the_press() {
+    let mut harness = Harness::new();
+    let reset = harness.entity::<ResetButton>();
+    let center = harness.center::<ResetButton>();
+    let off = Vec2::new(EMPTY.x, center.y);
+    harness.click::<ClickButton>();
+    let before = harness.state().clone();
+
+    touch_down(&mut harness, 0, center);
+    assert_eq!(
+        background(&mut harness, reset),
+        theme(&harness).danger.pressed
+    );
+
+    // Bevy keeps the button `Pressed`; it still looks released.
+    harness.touch(0, TouchPhase::Moved, off);
+    harness.step();
+    assert_eq!(
+        background(&mut harness, reset),
+        theme(&harness).danger.normal
+    );
+
+    touch_up(&mut harness, 0, off);
+    assert_eq!(*harness.state(), before);
+}
+
+#[test]
+fn dragging_back_on_resumes_the_press() {
+    let mut harness = Harness::new();
+    let click = harness.entity::<ClickButton>();
+    let bounds = harness.bounds::<ClickButton>();
+
+    touch_down(&mut harness, 0, bounds.center());
+    harness.touch(
+        0,
+        TouchPhase::Moved,
+        Vec2::new(bounds.max.x + 40.0, bounds.center().y),
+    );
+    harness.step();
+    harness.touch(0, TouchPhase::Moved, bounds.center() + Vec2::X * 50.0);
+    harness.step();
+    assert_eq!(
+        background(&mut harness, click),
+        theme(&harness).primary.pressed
+    );
+
+    touch_up(&mut harness, 0, bounds.center() + Vec2::X * 50.0);
+    assert_eq!(harness.state().click_count, 1);
+}
+
+#[test]
+fn another_finger_lifting_does_not_release_the_press() {
+    let mut harness = Harness::new();
+    let center = harness.center::<ClickButton>();
+
+    touch_down(&mut harness, 0, center);
+    touch_down(&mut harness, 1, EMPTY);
+    touch_up(&mut harness, 1, EMPTY);
+    assert_eq!(harness.state().click_count, 0);
+
+    touch_up(&mut harness, 0, center);
+    assert_eq!(harness.state().click_count, 1);
+}
+
+#[test]
+fn second_finger_on_the_button_does_not_take_over() {
+    let mut harness = Harness::new();
+    let bounds = harness.bounds::<ClickButton>();
+    let (first, second) = (
+        bounds.center() - Vec2::X * 50.0,
+        bounds.center() + Vec2::X * 50.0,
+    );
+    let below = Vec2::new(first.x, bounds.max.y + 100.0);
+
+    touch_down(&mut harness, 0, first);
+    touch_down(&mut harness, 1, second);
+    harness.touch(0, TouchPhase::Moved, below);
+    harness.step();
+    // The finger that pressed the button lifts outside it.
+    touch_up(&mut harness, 0, below);
+    touch_up(&mut harness, 1, second);
+
+    assert_eq!(harness.state().click_count, 0);
+}
+
+#[test]
+fn canceled_touch_does_not_activate() {
+    let mut harness = Harness::new();
+    let center = harness.center::<ClickButton>();
+
+    touch_down(&mut harness, 0, center);
+    harness.touch(0, TouchPhase::Canceled, center);
+    harness.step();
+
+    assert_eq!(harness.state().click_count, 0);
+}
+
+#[test]
+fn double_click_button_needs_a_double_click() {
+    let mut harness = Harness::new();
+
+    harness.click::<DoubleClickButton>();
+    assert_eq!(*harness.state(), GuiState::default());
+
+    harness.click::<DoubleClickButton>();
+    assert_eq!(harness.state().click_count, 2);
+    assert!(harness.state().button_state);
+    assert_eq!(harness.text::<CounterLabel>(), "Clicks: 2");
+    assert_eq!(
+        harness.text::<MessageLabel>(),
+        "Double click! Total: 2 clicks"
+    );
+}
+
+#[test]
+fn special_effect_message_depends_on_count() {
+    let mut harness = Harness::new();
+
+    harness.click::<SpecialEffectButton>();
+    assert_eq!(harness.text::<MessageLabel>(), "Just getting started!");
+
+    for _ in 0..3 {
+        harness.double_click::<DoubleClickButton>();
+    }
+    harness.click::<SpecialEffectButton>();
+    assert_eq!(harness.text::<MessageLabel>(), "Good job! Keep clicking!");
+
+    for _ in 0..3 {
+        harness.double_click::<DoubleClickButton>();
+    }
+    harness.click::<SpecialEffectButton>();
+    assert_eq!(
+        harness.text::<MessageLabel>(),
+        "Amazing! You're a clicking master!"
+    );
+}
+
+#[test]
+fn slow_clicks_are_not_a_double_click() {
+    let mut harness = Harness::new();
+
+    harness.click::<DoubleClickButton>();
+    harness.advance(Duration::from_millis(400));
+    harness.click::<DoubleClickButton>();
+
+    assert_eq!(*harness.state(), GuiState::default());
+}
+
+#[test]
+fn info_button_leaves_state_untouched() {
+    let mut harness = Harness::new();
+
+    harness.click::<ClickButton>();
+    let before = harness.state().clone();
+    harness.click::<InfoButton>();
+
+    assert_eq!(*harness.state(), before);
+}
+
+#[test]
+fn reset_button_restores_defaults() {
+    let mut harness = Harness::new();
+
+    harness.click::<ClickButton>();
+    harness.double_click::<DoubleClickButton>();
+    harness.click::<ResetButton>();
+    harness.click::<ConfirmButton>();
+
+    assert_eq!(*harness.state(), GuiState::default());
+    assert_eq!(harness.text::<CounterLabel>(), "Clicks: 0");
+    assert_eq!(harness.text::<StatusLabel>(), "Button State: OFF");
+    assert_eq!(harness.text::<MessageLabel>(), "Welcome to Rust GUI!");
+}
+
+/// The theme the GUI is drawn with, so tests follow the theme files.
+fn theme(harness: &Harness) -> Theme {
+    harness.app.world().resource::<Theme>().clone()
+}
+
+fn background(harness: &mut Harness, entity: Entity) -> Color {
+    harness
+        .app
+        .world()
+        .get::<BackgroundColor>(entity)
+        .unwrap()
+        .0
+}
+
+#[test]
+fn buttons_are_styled_from_their_palette() {
+    let mut harness = Harness::new();
+    let reset = harness.entity::<ResetButton>();
+
+    let center = harness.center::<ResetButton>();
+    harness.move_cursor(Some(center));
+    harness.step();
+    assert_eq!(
+        background(&mut harness, reset),
+        theme(&harness).danger.hovered
+    );
+
+    harness.move_cursor(None);
+    harness.step();
+    assert_eq!(
+        background(&mut harness, reset),
+        theme(&harness).danger.normal
+    );
+}
+
+#[test]
+fn click_button_palette_follows_button_state() {
+    let mut harness = Harness::new();
+    let click = harness.entity::<ClickButton>();
+
+    harness.click::<ClickButton>();
+    assert_eq!(
+        background(&mut harness, click),
+        theme(&harness).active.normal
+    );
+
+    harness.click::<ClickButton>();
+    assert_eq!(
+        background(&mut harness, click),
+        theme(&harness).primary.normal
+    );
+}
+
+#[test]
+fn disabled_button_ignores_presses() {
+    let mut harness = Harness::new();
+    let click = harness.entity::<ClickButton>();
+    harness
+        .app
+        .world_mut()
+        .get_mut::<ActionButton>(click)
+        .unwrap()
+        .disabled = true;
+    harness.step();
+    assert_eq!(
+        background(&mut harness, click),
+        theme(&harness).primary.disabled
+    );
+
+    harness.click::<ClickButton>();
+
+    assert_eq!(harness.state().click_count, 0);
+}
+
+#[test]
+fn undo_is_disabled_until_something_can_be_undone() {
+    let mut harness = Harness::new();
+    let undo = harness.entity::<UndoButton>();
+    let is_disabled = |harness: &Harness| {
+        harness
+            .app
+            .world()
+            .get::<ActionButton>(undo)
+            .unwrap()
+            .disabled
+    };
+
+    assert!(is_disabled(&harness));
+    harness.click::<ClickButton>();
+    assert!(!is_disabled(&harness));
+}
+
+#[derive(Component)]
+struct ExtraButton;
+
+#[derive(Resource, Default)]
+struct Activations(Vec<ButtonAction>);
+
+#[test]
+fn spawned_button_sends_its_action() {
+    let mut harness = Harness::with(|app| {
+        // In `PostUpdate`, so everything the GUI sent this frame is seen.
+        app.init_resource::<Activations>().add_systems(
+            PostUpdate,
+            |mut activated: MessageReader<ButtonActivated>, mut seen: ResMut<Activations>| {
+                seen.0.extend(activated.read().map(|a| a.action.clone()));
+            },
+        );
+    });
+    let palette = theme(&harness).neutral;
+    harness.app.world_mut().spawn((
+        action_button(
+            "Extra",
+            ButtonAction::Custom("extra".to_string()),
+            palette,
+            Node::default(),
+            16.0,
+        ),
+        ExtraButton,
+    ));
+    harness.step();
+
+    harness.click::<ExtraButton>();
+
+    assert_eq!(
+        harness.app.world().resource::<Activations>().0,
+        vec![ButtonAction::Custom("extra".to_string())]
+    );
+    assert_eq!(*harness.state(), GuiState::default());
+}
@@ -0,0 +1,271 @@
+//! Headless test harness for `GuiPlugin`.
+//!
+//! Builds an `App` with `MinimalPlugins`, `InputPlugin` and Bevy's UI layout,
+//! but no renderer or OS window: the primary `Window` is a plain entity of
+//! [`WINDOW_SIZE`] that the GUI camera targets. Nodes get their real size and
+//! position from the layout pass, and `Interaction` is worked out by Bevy
+//! from the mouse cursor and touches the harness sends, so the button systems
+//! can be driven on a plain Linux CI box. The clock advances by exactly one
+//! [`FRAME`] per step.
+
+#![allow(dead_code)]
+
+use bevy::camera::{CameraPlugin, CameraUpdateSystems};
+use bevy::image::{ImagePlugin, TextureAtlasPlugin};
+use bevy::input::gamepad::{
+    GamepadConnection, GamepadConnectionEvent, RawGamepadButtonChangedEvent, RawGamepadEvent,
+};
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::mouse::MouseButtonInput;
+use bevy::input::touch::{TouchInput, TouchPhase};
+use bevy::input::{ButtonState, InputPlugin};
+use bevy::mesh::MeshPlugin;
+use bevy::prelude::*;
+use bevy::render::camera::camera_system;
+use bevy::render::texture::ManualTextureViews;
+use bevy::text::TextPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy::ui::{UiGlobalTransform, UiPlugin};
+use bevy::window::{ExitCondition, PrimaryWindow, WindowResolution};
+use rust_ios_hello::{GuiPlugin, GuiState};
+use std::time::Duration;
+
+/// How far `Time` advances per [`Harness::step`], unless a test sets its own
+/// `TimeUpdateStrategy`.
+pub const FRAME: Duration = Duration::from_millis(16);
+
+/// The finger [`Harness::click_at`] taps with, kept clear of the ids tests
+/// use for their own touches.
+const TAP_FINGER: u64 = 99;
+
+/// Logical size of the primary window.
+pub const WINDOW_SIZE: Vec2 = Vec2::new(400.0, 800.0);
+
+pub struct Harness {
+    pub app: App,
+}
+
+impl Harness {
+    /// Builds the app and runs the first frame so `Startup` has spawned the UI.
+    pub fn new() -> Self {
+        Self::with(|_| {})
+    }
+
+    /// Like [`Harness::new`], but lets the test add plugins or resources
+    /// before the first frame runs.
+    pub fn with(configure: impl FnOnce(&mut App)) -> Self {
+        Self::build(GuiPlugin::default(), configure)
+    }
+
+    /// Builds the app with a custom `GuiPlugin`. `configure` runs before the
+    /// plugin is added, so plugins it adds (e.g. `AssetPlugin`) are visible to
+    /// `GuiPlugin::build`. Without a camera from `gui` or `configure`, nothing
+    /// is laid out.
+    pub fn build(gui: GuiPlugin, configure: impl FnOnce(&mut App)) -> Self {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            InputPlugin,
+            TransformPlugin,
+            WindowPlugin {
+                primary_window: Some(Window {
+                    resolution: WindowResolution::new(WINDOW_SIZE.x as u32, WINDOW_SIZE.y as u32),
+                    ..default()
+                }),
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            },
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME));
+        configure(&mut app);
+        if !app.is_plugin_added::<AssetPlugin>() {
+            app.add_plugins(AssetPlugin::default());
+        }
+        // The parts of `DefaultPlugins` the UI layout needs. Cameras work
+        // out their target size in `bevy_render`, which is left out, so its
+        // system is added on its own.
+        app.add_plugins((
+            ImagePlugin::default(),
+            TextureAtlasPlugin,
+            TextPlugin,
+            MeshPlugin,
+            CameraPlugin,
+            UiPlugin,
+        ))
+        .init_resource::<ManualTextureViews>()
+        .add_systems(PostUpdate, camera_system.in_set(CameraUpdateSystems));
+        app.add_plugins(gui);
+        app.update();
+        Self { app }
+    }
+
+    /// Advances the app by one frame.
+    pub fn step(&mut self) {
+        self.app.update();
+    }
+
+    /// Steps whole frames until at least `duration` has passed.
+    pub fn advance(&mut self, duration: Duration) {
+        let frames = duration.as_nanos().div_ceil(FRAME.as_nanos());
+        for _ in 0..frames {
+            self.step();
+        }
+    }
+
+    pub fn window(&mut self) -> Entity {
+        self.entity::<PrimaryWindow>()
+    }
+
+    /// The laid-out bounds of `entity`, in logical pixels.
+    pub fn bounds_of(&self, entity: Entity) -> Rect {
+        let world = self.app.world();
+        let node = world.get::<ComputedNode>(entity).expect("entity is a node");
+        let transform = world.get::<UiGlobalTransform>(entity).unwrap();
+        let scale = node.inverse_scale_factor;
+        Rect::from_center_size(transform.translation * scale, node.size * scale)
+    }
+
+    /// The laid-out bounds of the single entity tagged `M`.
+    pub fn bounds<M: Component>(&mut self) -> Rect {
+        let entity = self.entity::<M>();
+        self.bounds_of(entity)
+    }
+
+    /// Where the middle of the entity tagged `M` is, in logical pixels.
+    pub fn center<M: Component>(&mut self) -> Vec2 {
+        self.bounds::<M>().center()
+    }
+
+    /// Moves the mouse cursor to `position`, or out of the window for `None`,
+    /// to be seen on the next step.
+    pub fn move_cursor(&mut self, position: Option<Vec2>) {
+        let window = self.window();
+        self.app
+            .world_mut()
+            .get_mut::<Window>(window)
+            .unwrap()
+            .set_cursor_position(position);
+    }
+
+    /// Presses or releases the left mouse button, to be seen on the next
+    /// step.
+    pub fn mouse_button(&mut self, state: ButtonState) {
+        let window = self.window();
+        self.app.world_mut().write_message(MouseButtonInput {
+            button: MouseButton::Left,
+            state,
+            window,
+        });
+    }
+
+    /// Taps `position` with a finger, the way a click arrives on iOS: puts it
+    /// down, steps a frame, then lifts it and steps again. Unlike a mouse
+    /// click, this leaves nothing hovered.
+    pub fn click_at(&mut self, position: Vec2) {
+        self.touch(TAP_FINGER, TouchPhase::Started, position);
+        self.step();
+        self.touch(TAP_FINGER, TouchPhase::Ended, position);
+        self.step();
+    }
+
+    /// The single entity carrying marker `M`.
+    pub fn entity<M: Component>(&mut self) -> Entity {
+        self.app
+            .world_mut()
+            .query_filtered::<Entity, With<M>>()
+            .single(self.app.world())
+            .expect("exactly one entity with the requested marker")
+    }
+
+    /// Clicks the middle of the button tagged `M`.
+    pub fn click<M: Component>(&mut self) {
+        let center = self.center::<M>();
+        self.click_at(center);
+    }
+
+    /// Clicks the button tagged `M` twice in quick succession.
+    pub fn double_click<M: Component>(&mut self) {
+        self.click::<M>();
+        self.click::<M>();
+    }
+
+    /// Sends a touch event for finger `id`, to be seen on the next step.
+    pub fn touch(&mut self, id: u64, phase: TouchPhase, position: Vec2) {
+        let window = self.window();
+        self.app.world_mut().write_message(TouchInput {
+            phase,
+            position,
+            window,
+            force: None,
+            id,
+        });
+    }
+
+    fn send_key(&mut self, key_code: KeyCode, state: ButtonState) {
+        self.app.world_mut().write_message(KeyboardInput {
+            key_code,
+            logical_key: Key::Unidentified(bevy::input::keyboard::NativeKey::Unidentified),
+            state,
+            text: None,
+            repeat: false,
+            window: Entity::PLACEHOLDER,
+        });
+    }
+
+    /// Presses `modifiers` and `key` together for one frame, then releases
+    /// them all.
+    pub fn shortcut(&mut self, modifiers: &[KeyCode], key: KeyCode) {
+        for &modifier in modifiers {
+            self.send_key(modifier, ButtonState::Pressed);
+        }
+        self.send_key(key, ButtonState::Pressed);
+        self.step();
+        for &code in modifiers.iter().chain([&key]) {
+            self.send_key(code, ButtonState::Released);
+        }
+        self.step();
+    }
+
+    /// Connects a gamepad and steps a frame so it is ready for
+    /// [`Harness::press_gamepad_button`].
+    pub fn connect_gamepad(&mut self) -> Entity {
+        let gamepad = self.app.world_mut().spawn_empty().id();
+        self.app
+            .world_mut()
+            .write_message(GamepadConnectionEvent::new(
+                gamepad,
+                GamepadConnection::Connected {
+                    name: "Test pad".to_string(),
+                    vendor_id: None,
+                    product_id: None,
+                },
+            ));
+        self.step();
+        gamepad
+    }
+
+    /// Presses `button` on `gamepad` for one frame, then releases it.
+    pub fn press_gamepad_button(&mut self, gamepad: Entity, button: GamepadButton) {
+        for value in [1.0, 0.0] {
+            self.app.world_mut().write_message(RawGamepadEvent::Button(
+                RawGamepadButtonChangedEvent::new(gamepad, button, value),
+            ));
+            self.step();
+        }
+    }
+
+    pub fn state(&self) -> &GuiState {
+        self.app.world().resource::<GuiState>()
+    }
+
+    /// Text of the single label tagged `M`.
+    pub fn text<M: Component>(&mut self) -> String {
+        let entity = self.entity::<M>();
+        self.app
+            .world()
+            .get::<Text>(entity)
+            .expect("marker entity is a text label")
+            .0
+            .clone()
+    }
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use bevy::input::touch::TouchPhase;
+use bevy::prelude::*;
+use common::Harness;
+use rust_ios_hello::{
+    ButtonAction, ButtonActivated, CancelButton, ClickButton, ConfirmButton, ConfirmDialog,
+    CounterLabel, DialogPanel, Focus, GuiState, ResetButton, Theme, UndoButton,
+};
+
+/// A harness with some clicks to lose and the Reset dialog open.
+fn asking_to_reset() -> (Harness, GuiState) {
+    let mut harness = Harness::new();
+    harness.click::<ClickButton>();
+    harness.click::<ClickButton>();
+    let before = harness.state().clone();
+    harness.click::<ResetButton>();
+    assert!(dialog_open(&mut harness));
+    (harness, before)
+}
+
+fn dialog_open(harness: &mut Harness) -> bool {
+    harness
+        .app
+        .world_mut()
+        .query_filtered::<(), With<ConfirmDialog>>()
+        .iter(harness.app.world())
+        .next()
+        .is_some()
+}
+
+/// Checks the dialog closed without touching the state or history.
+fn assert_dismissed(harness: &mut Harness, before: &GuiState) {
+    assert!(!dialog_open(harness));
+    assert_eq!(harness.state(), before);
+    assert_eq!(harness.text::<CounterLabel>(), "Clicks: 2");
+    // Nothing was added to the history: undo takes back the last click.
+    harness.click::<UndoButton>();
+    assert_eq!(harness.state().click_count, 1);
+}
+
+fn focused(harness: &Harness) -> Option<Entity> {
+    harness.app.world().resource::<Focus>().0
+}
+
+#[test]
+fn reset_asks_first() {
+    let (mut harness, before) = asking_to_reset();
+    assert_eq!(*harness.state(), before);
+
+    harness.click::<ConfirmButton>();
+    assert!(!dialog_open(&mut harness));
+    assert_eq!(*harness.state(), GuiState::default());
+    assert_eq!(harness.text::<CounterLabel>(), "Clicks: 0");
+}
+
+#[test]
+fn cancel_leaves_the_state_untouched() {
+    let (mut harness, before) = asking_to_reset();
+    harness.click::<CancelButton>();
+    assert_dismissed(&mut harness, &before);
+}
+
+#[test]
+fn escape_dismisses() {
+    let (mut harness, before) = asking_to_reset();
+    harness.shortcut(&[], KeyCode::Escape);
+    assert_dismissed(&mut harness, &before);
+}
+
+#[test]
+fn gamepad_back_dismisses() {
+    let (mut harness, before) = asking_to_reset();
+    let gamepad = harness.connect_gamepad();
+    harness.press_gamepad_button(gamepad, GamepadButton::East);
+    assert_dismissed(&mut harness, &before);
+}
+
+#[test]
+fn tapping_the_backdrop_dismisses() {
+    let (mut harness, before) = asking_to_reset();
+    let corner = harness.bounds::<ConfirmDialog>().min + Vec2::splat(5.0);
+    harness.click_at(corner);
+    assert_dismissed(&mut harness, &before);
+}
+
+#[test]
+fn tapping_the_panel_keeps_the_dialog_open() {
+    let (mut harness, before) = asking_to_reset();
+    let panel = harness.bounds::<DialogPanel>();
+    assert!(
+        panel.min.cmpgt(Vec2::ZERO).all(),
+        "the backdrop surrounds it"
+    );
+
+    // The padding, and the title above the buttons.
+    harness.click_at(panel.min + Vec2::splat(4.0));
+    harness.click_at(Vec2::new(panel.center().x, panel.min.y + 30.0));
+    assert!(dialog_open(&mut harness));
+    assert_eq!(*harness.state(), before);
+}
+
+#[test]
+fn buttons_behind_the_dialog_are_blocked() {
+    let (mut harness, before) = asking_to_reset();
+    harness.shortcut(&[], KeyCode::Tab);
+    harness.shortcut(&[], KeyCode::Tab);
+    harness.shortcut(&[], KeyCode::Tab);
+    let cancel = harness.entity::<CancelButton>();
+    assert_eq!(
+        focused(&harness),
+        Some(cancel),
+        "focus stays inside the dialog"
+    );
+
+    // The tap lands on the backdrop, which only closes the dialog; the button
+    // behind it never sees the press.
+    let reset = harness.entity::<ResetButton>();
+    let center = harness.center::<ResetButton>();
+    assert!(!harness.bounds::<DialogPanel>().contains(center));
+    harness.touch(0, TouchPhase::Started, center);
+    harness.step();
+    assert_eq!(
+        harness.app.world().get::<Interaction>(reset),
+        Some(&Interaction::None)
+    );
+    harness.touch(0, TouchPhase::Ended, center);
+    harness.step();
+    assert_dismissed(&mut harness, &before);
+}
+
+#[test]
+fn two_resets_in_one_frame_open_one_dialog() {
+    let mut harness = Harness::new();
+    harness.click::<ClickButton>();
+    let before = harness.state().clone();
+    let reset = harness.entity::<ResetButton>();
+    for _ in 0..2 {
+        harness.app.world_mut().write_message(ButtonActivated {
+            entity: reset,
+            action: ButtonAction::Reset,
+        });
+    }
+    harness.step();
+
+    let dialogs = harness
+        .app
+        .world_mut()
+        .query_filtered::<(), With<ConfirmDialog>>()
+        .iter(harness.app.world())
+        .count();
+    assert_eq!(dialogs, 1);
+    harness.click::<CancelButton>();
+    assert!(!dialog_open(&mut harness));
+    assert_eq!(*harness.state(), before);
+}
+
+#[test]
+fn keyboard_focus_moves_into_the_dialog() {
+    let mut harness = Harness::new();
+    harness.click::<ClickButton>();
+    let before = harness.state().clone();
+    let reset = harness.entity::<ResetButton>();
+    harness.app.world_mut().resource_mut::<Focus>().0 = Some(reset);
+
+    harness.shortcut(&[], KeyCode::Enter);
+    assert!(dialog_open(&mut harness));
+    // Cancel comes first, so pressing Enter again is the safe choice.
+    let cancel = harness.entity::<CancelButton>();
+    assert_eq!(focused(&harness), Some(cancel));
+    harness.shortcut(&[], KeyCode::Enter);
+    assert!(!dialog_open(&mut harness));
+    assert_eq!(*harness.state(), before);
+}
+
+#[test]
+fn dialog_follows_the_theme() {
+    let (mut harness, _) = asking_to_reset();
+    harness.app.world_mut().insert_resource(Theme::light());
+    harness.step();
+    let panel = harness.entity::<DialogPanel>();
+    assert_eq!(
+        harness.app.world().get::<BackgroundColor>(panel).unwrap().0,
+        Theme::light().background
+    );
+}
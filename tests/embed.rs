@@ -0,0 +1,136 @@
+use bevy::diagnostic::FrameCount;
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+use rust_ios_hello::{
+    decode_state, rust_gui_pause, rust_gui_pump, rust_gui_resume, rust_gui_shutdown,
+    start_embedded, ClickButton, EmbedState, EmbeddedApp, GuiPlugin, GuiState, MemoryStorage,
+    PersistencePlugin,
+};
+use std::time::Duration;
+
+/// A headless app as a host would embed it. The long debounce means anything
+/// saved was saved by the lifecycle, not the timer.
+fn headless_app(storage: &MemoryStorage) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, InputPlugin)).add_plugins((
+        GuiPlugin {
+            spawn_camera: false,
+            ..default()
+        },
+        PersistencePlugin {
+            debounce: Duration::from_secs(3600),
+            ..PersistencePlugin::new(storage.clone())
+        },
+    ));
+    app
+}
+
+fn frames(embedded: &EmbeddedApp) -> u32 {
+    embedded.app().world().resource::<FrameCount>().0
+}
+
+fn set_click_interaction(embedded: &mut EmbeddedApp, interaction: Interaction) {
+    let world = embedded.app_mut().world_mut();
+    let mut buttons = world.query_filtered::<&mut Interaction, With<ClickButton>>();
+    for mut current in buttons.iter_mut(world) {
+        *current = interaction;
+    }
+}
+
+/// Presses and releases the click button, one frame each.
+fn click(embedded: &mut EmbeddedApp) {
+    set_click_interaction(embedded, Interaction::Pressed);
+    embedded.pump();
+    set_click_interaction(embedded, Interaction::None);
+    embedded.pump();
+}
+
+fn click_count(embedded: &EmbeddedApp) -> i32 {
+    embedded.app().world().resource::<GuiState>().click_count
+}
+
+fn saved_clicks(storage: &MemoryStorage) -> Option<i32> {
+    storage
+        .contents()
+        .map(|contents| decode_state(&contents).unwrap().click_count)
+}
+
+#[test]
+fn start_runs_startup_and_pump_runs_frames() {
+    let mut embedded = EmbeddedApp::new(headless_app(&MemoryStorage::default()));
+    assert_eq!(embedded.state(), EmbedState::Running);
+    let started = frames(&embedded);
+
+    set_click_interaction(&mut embedded, Interaction::Pressed);
+    assert!(embedded.pump());
+    set_click_interaction(&mut embedded, Interaction::None);
+    assert!(embedded.pump());
+
+    assert_eq!(frames(&embedded), started + 2);
+    assert_eq!(click_count(&embedded), 1);
+}
+
+#[test]
+fn paused_app_skips_frames_and_saves() {
+    let storage = MemoryStorage::default();
+    let mut embedded = EmbeddedApp::new(headless_app(&storage));
+    click(&mut embedded);
+
+    embedded.pause();
+    assert_eq!(embedded.state(), EmbedState::Paused);
+    assert_eq!(saved_clicks(&storage), Some(1));
+
+    let paused_at = frames(&embedded);
+    assert!(embedded.pump());
+    assert!(embedded.pump());
+    assert_eq!(frames(&embedded), paused_at);
+
+    embedded.resume();
+    assert_eq!(embedded.state(), EmbedState::Running);
+    embedded.pump();
+    assert!(frames(&embedded) > paused_at);
+}
+
+#[test]
+fn shutdown_saves_pending_state() {
+    let storage = MemoryStorage::default();
+    let mut embedded = EmbeddedApp::new(headless_app(&storage));
+    click(&mut embedded);
+    assert_eq!(saved_clicks(&storage), None);
+
+    assert_eq!(embedded.shutdown(), AppExit::Success);
+    assert_eq!(saved_clicks(&storage), Some(1));
+}
+
+#[test]
+fn pump_stops_when_the_app_exits() {
+    let mut embedded = EmbeddedApp::new(headless_app(&MemoryStorage::default()));
+
+    embedded
+        .app_mut()
+        .world_mut()
+        .write_message(AppExit::error());
+    assert!(!embedded.pump());
+    assert!(!embedded.pump());
+
+    assert_eq!(embedded.shutdown(), AppExit::error());
+}
+
+#[test]
+fn c_lifecycle_drives_the_installed_app() {
+    // Each test runs on its own thread, and the embedded app is per thread.
+    assert!(!rust_gui_pump());
+    assert_eq!(rust_gui_shutdown(), 0);
+
+    let storage = MemoryStorage::default();
+    assert!(start_embedded(headless_app(&storage)));
+    assert!(!start_embedded(headless_app(&storage)));
+
+    assert!(rust_gui_pump());
+    rust_gui_pause();
+    rust_gui_resume();
+    assert!(rust_gui_pump());
+
+    assert_eq!(rust_gui_shutdown(), 0);
+    assert!(!rust_gui_pump());
+}
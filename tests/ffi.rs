@@ -0,0 +1,181 @@
+mod common;
+
+use common::Harness;
+use rust_ios_hello::{
+    rust_gui_button_state, rust_gui_click_count, rust_gui_register_state_callback, rust_gui_reset,
+    rust_gui_set_button_state, rust_gui_set_click_count, rust_gui_state,
+    rust_gui_unregister_state_callback, ClickButton, CounterLabel, RustGuiState, UndoButton,
+};
+use std::ffi::c_void;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+/// The C API is process-wide, so tests touching it take turns.
+static FFI_LOCK: Mutex<()> = Mutex::new(());
+
+fn ffi_harness() -> (MutexGuard<'static, ()>, Harness) {
+    let guard = FFI_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    (guard, Harness::new())
+}
+
+extern "C" fn record(state: RustGuiState, user_data: *mut c_void) {
+    let seen = unsafe { &*(user_data as *const Mutex<Vec<RustGuiState>>) };
+    seen.lock().unwrap().push(state);
+}
+
+#[test]
+fn getters_follow_the_app() {
+    let (_guard, mut harness) = ffi_harness();
+    // A fresh app publishes its initial state at startup.
+    assert_eq!(
+        rust_gui_state(),
+        RustGuiState {
+            click_count: 0,
+            button_state: false
+        }
+    );
+
+    harness.click::<ClickButton>();
+
+    assert_eq!(rust_gui_click_count(), 1);
+    assert!(rust_gui_button_state());
+}
+
+#[test]
+fn setters_are_applied_on_the_next_frame_and_undoable() {
+    let (_guard, mut harness) = ffi_harness();
+
+    rust_gui_set_click_count(41);
+    rust_gui_set_button_state(true);
+    assert_eq!(rust_gui_click_count(), 0, "not applied yet");
+    harness.step();
+    assert_eq!(
+        rust_gui_state(),
+        RustGuiState {
+            click_count: 41,
+            button_state: true
+        }
+    );
+
+    assert_eq!(harness.state().click_count, 41);
+    assert!(harness.state().button_state);
+    assert_eq!(harness.text::<CounterLabel>(), "Clicks: 41");
+
+    harness.click::<UndoButton>();
+    assert!(!harness.state().button_state);
+    assert!(!rust_gui_button_state());
+}
+
+#[test]
+fn reset_restores_defaults() {
+    let (_guard, mut harness) = ffi_harness();
+    harness.click::<ClickButton>();
+
+    rust_gui_reset();
+    harness.step();
+
+    assert_eq!(harness.state().click_count, 0);
+    assert_eq!(rust_gui_click_count(), 0);
+    assert!(!rust_gui_button_state());
+}
+
+#[test]
+fn callbacks_see_each_change_until_unregistered() {
+    let (_guard, mut harness) = ffi_harness();
+    let seen = Box::new(Mutex::new(Vec::new()));
+    let user_data = &*seen as *const Mutex<Vec<RustGuiState>> as *mut c_void;
+
+    let token = rust_gui_register_state_callback(Some(record), user_data);
+    assert_ne!(token, 0);
+
+    harness.click::<ClickButton>();
+    rust_gui_set_click_count(7);
+    assert_eq!(seen.lock().unwrap().len(), 1, "setters notify once applied");
+    harness.step();
+
+    assert!(rust_gui_unregister_state_callback(token));
+    assert!(!rust_gui_unregister_state_callback(token));
+    harness.click::<ClickButton>();
+
+    assert_eq!(
+        *seen.lock().unwrap(),
+        [
+            RustGuiState {
+                click_count: 1,
+                button_state: true
+            },
+            RustGuiState {
+                click_count: 7,
+                button_state: true
+            },
+        ]
+    );
+}
+
+#[test]
+fn negative_counts_keep_the_button_state() {
+    let (_guard, mut harness) = ffi_harness();
+    harness.click::<ClickButton>();
+    rust_gui_set_click_count(-3);
+    harness.step();
+    assert_eq!(
+        rust_gui_state(),
+        RustGuiState {
+            click_count: -3,
+            button_state: true
+        }
+    );
+}
+
+#[test]
+fn null_callback_is_rejected() {
+    assert_eq!(
+        rust_gui_register_state_callback(None, std::ptr::null_mut()),
+        0
+    );
+}
+
+/// `include/rust_ios_hello.h`, as cbindgen generates it from `src/ffi.rs`.
+fn c_header() -> String {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        header: Some("/* Generated by cbindgen from src/ffi.rs; do not edit. */".to_string()),
+        include_guard: Some("RUST_IOS_HELLO_H".to_string()),
+        cpp_compat: true,
+        style: cbindgen::Style::Both,
+        documentation_style: cbindgen::DocumentationStyle::C99,
+        ..Default::default()
+    };
+    let mut header = Vec::new();
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(root.join("src/ffi.rs"))
+        .generate()
+        .expect("src/ffi.rs is understood by cbindgen")
+        .write(&mut header);
+    String::from_utf8(header).unwrap()
+}
+
+#[test]
+fn header_is_up_to_date() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/rust_ios_hello.h");
+    if std::env::var_os("UPDATE_HEADER").is_some() {
+        std::fs::write(&path, c_header()).unwrap();
+    }
+    let header = std::fs::read_to_string(&path).expect("run with UPDATE_HEADER=1 to generate it");
+
+    assert_eq!(header, c_header(), "run with UPDATE_HEADER=1 to regenerate");
+
+    let source = include_str!("../src/ffi.rs");
+    for line in source.lines() {
+        if let Some(rest) = line.strip_prefix("pub extern \"C\" fn ") {
+            let name = &rest[..rest.find('(').unwrap()];
+            assert!(
+                header.contains(&format!(" {}(", name)),
+                "{} is missing from the header",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use bevy::input::touch::TouchPhase;
+use bevy::prelude::*;
+use common::Harness;
+use rust_ios_hello::{
+    ActionButton, ClickButton, DoubleClickButton, Focus, InfoButton, ResetButton,
+    SpecialEffectButton, Theme, ThemeToggleButton, UndoButton,
+};
+
+fn focused(harness: &Harness) -> Option<Entity> {
+    harness.app.world().resource::<Focus>().0
+}
+
+fn press(harness: &mut Harness, key: KeyCode) {
+    harness.shortcut(&[], key);
+}
+
+#[test]
+fn tab_walks_enabled_buttons_in_reading_order() {
+    let mut harness = Harness::new();
+    // Undo and Redo are disabled while there's nothing to undo.
+    let expected = [
+        harness.entity::<ClickButton>(),
+        harness.entity::<InfoButton>(),
+        harness.entity::<DoubleClickButton>(),
+        harness.entity::<SpecialEffectButton>(),
+        harness.entity::<ThemeToggleButton>(),
+        harness.entity::<ResetButton>(),
+        harness.entity::<ClickButton>(),
+    ];
+    for entity in expected {
+        press(&mut harness, KeyCode::Tab);
+        assert_eq!(focused(&harness), Some(entity));
+    }
+}
+
+#[test]
+fn shift_tab_walks_backwards() {
+    let mut harness = Harness::new();
+    harness.shortcut(&[KeyCode::ShiftLeft], KeyCode::Tab);
+    assert_eq!(focused(&harness), Some(harness.entity::<ResetButton>()));
+    harness.shortcut(&[KeyCode::ShiftLeft], KeyCode::Tab);
+    assert_eq!(
+        focused(&harness),
+        Some(harness.entity::<ThemeToggleButton>())
+    );
+}
+
+#[test]
+fn arrows_move_to_the_nearest_button_in_that_direction() {
+    let mut harness = Harness::new();
+    press(&mut harness, KeyCode::ArrowDown);
+    assert_eq!(focused(&harness), Some(harness.entity::<ClickButton>()));
+
+    // Info and Double Click are equally close; ties go to reading order.
+    let moves = [
+        (KeyCode::ArrowDown, harness.entity::<InfoButton>()),
+        (KeyCode::ArrowRight, harness.entity::<DoubleClickButton>()),
+        (KeyCode::ArrowRight, harness.entity::<DoubleClickButton>()),
+        (KeyCode::ArrowDown, harness.entity::<SpecialEffectButton>()),
+        // Straight past the disabled Undo/Redo row.
+        (KeyCode::ArrowDown, harness.entity::<ThemeToggleButton>()),
+        (KeyCode::ArrowUp, harness.entity::<SpecialEffectButton>()),
+    ];
+    for (key, entity) in moves {
+        press(&mut harness, key);
+        assert_eq!(focused(&harness), Some(entity), "after {:?}", key);
+    }
+}
+
+#[test]
+fn enter_and_space_activate_the_focused_button() {
+    let mut harness = Harness::new();
+    press(&mut harness, KeyCode::Enter);
+    assert_eq!(harness.state().click_count, 0, "nothing is focused yet");
+
+    press(&mut harness, KeyCode::Tab);
+    press(&mut harness, KeyCode::Enter);
+    assert_eq!(harness.state().click_count, 1);
+    press(&mut harness, KeyCode::Space);
+    assert_eq!(harness.state().click_count, 2);
+
+    // Now there's something to undo, so Undo can be activated too.
+    let undo = harness.entity::<UndoButton>();
+    harness.app.world_mut().resource_mut::<Focus>().0 = Some(undo);
+    press(&mut harness, KeyCode::Enter);
+    assert_eq!(harness.state().click_count, 1);
+}
+
+#[test]
+fn gesture_buttons_activate_from_the_keyboard() {
+    let mut harness = Harness::new();
+    let double_click = harness.entity::<DoubleClickButton>();
+    harness.app.world_mut().resource_mut::<Focus>().0 = Some(double_click);
+    press(&mut harness, KeyCode::Enter);
+    assert_eq!(harness.state().click_count, 2);
+}
+
+#[test]
+fn disabled_buttons_do_not_activate() {
+    let mut harness = Harness::new();
+    let click = harness.entity::<ClickButton>();
+    harness.app.world_mut().resource_mut::<Focus>().0 = Some(click);
+    harness
+        .app
+        .world_mut()
+        .get_mut::<ActionButton>(click)
+        .unwrap()
+        .disabled = true;
+    press(&mut harness, KeyCode::Enter);
+    assert_eq!(harness.state().click_count, 0);
+}
+
+#[test]
+fn gamepad_dpad_moves_focus_and_a_activates() {
+    let mut harness = Harness::new();
+    let gamepad = harness.connect_gamepad();
+
+    harness.press_gamepad_button(gamepad, GamepadButton::DPadDown);
+    assert_eq!(focused(&harness), Some(harness.entity::<ClickButton>()));
+    harness.press_gamepad_button(gamepad, GamepadButton::South);
+    assert_eq!(harness.state().click_count, 1);
+
+    harness.press_gamepad_button(gamepad, GamepadButton::DPadDown);
+    harness.press_gamepad_button(gamepad, GamepadButton::DPadRight);
+    assert_eq!(
+        focused(&harness),
+        Some(harness.entity::<DoubleClickButton>())
+    );
+}
+
+#[test]
+fn focused_button_gets_the_theme_focus_ring() {
+    let mut harness = Harness::new();
+    press(&mut harness, KeyCode::Tab);
+    let click = harness.entity::<ClickButton>();
+    let ring =
+        |harness: &Harness, entity| harness.app.world().get::<Outline>(entity).map(|o| o.color);
+    assert_eq!(ring(&harness, click), Some(Theme::dark().focus_ring));
+
+    press(&mut harness, KeyCode::Tab);
+    assert_eq!(ring(&harness, click), None);
+    let info = harness.entity::<InfoButton>();
+    assert_eq!(ring(&harness, info), Some(Theme::dark().focus_ring));
+
+    harness.app.world_mut().insert_resource(Theme::light());
+    harness.step();
+    assert_eq!(ring(&harness, info), Some(Theme::light().focus_ring));
+}
+
+#[test]
+fn touching_the_screen_hides_focus() {
+    let mut harness = Harness::new();
+    press(&mut harness, KeyCode::Tab);
+    assert!(focused(&harness).is_some());
+
+    harness.touch(0, TouchPhase::Started, Vec2::new(5.0, 5.0));
+    harness.step();
+    assert_eq!(focused(&harness), None);
+    let click = harness.entity::<ClickButton>();
+    assert!(harness.app.world().get::<Outline>(click).is_none());
+}
@@ -0,0 +1,235 @@
+mod common;
+
+use bevy::input::touch::TouchPhase;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use common::Harness;
+use rust_ios_hello::{Gesture, GestureRecognized, GestureRecognizer, GestureSettings};
+use std::time::Duration;
+
+#[derive(Component)]
+struct Target;
+
+#[derive(Resource, Default)]
+struct Recognized(Vec<Gesture>);
+
+/// A harness with one plain 300x200 node in the top left corner that
+/// recognizes gestures, recording what it recognizes.
+fn harness_with(settings: Option<GestureSettings>) -> Harness {
+    let mut harness = Harness::with(|app| {
+        // In `PostUpdate`, so everything the GUI sent this frame is seen.
+        app.init_resource::<Recognized>().add_systems(
+            PostUpdate,
+            |mut recognized: MessageReader<GestureRecognized>,
+             targets: Query<(), With<Target>>,
+             mut seen: ResMut<Recognized>| {
+                seen.0.extend(
+                    recognized
+                        .read()
+                        .filter(|r| targets.contains(r.entity))
+                        .map(|r| r.gesture),
+                );
+            },
+        );
+        if let Some(settings) = settings {
+            app.insert_resource(settings);
+        }
+    });
+    harness.app.world_mut().spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Px(300.0),
+            height: Val::Px(200.0),
+            ..default()
+        },
+        // Above the screen.
+        GlobalZIndex(1),
+        GestureRecognizer::default(),
+        Target,
+    ));
+    harness.step();
+    harness
+}
+
+fn recognized(harness: &mut Harness) -> Vec<Gesture> {
+    std::mem::take(&mut harness.app.world_mut().resource_mut::<Recognized>().0)
+}
+
+/// Holds the mouse button down over the target.
+fn press(harness: &mut Harness) {
+    let center = harness.center::<Target>();
+    harness.move_cursor(Some(center));
+    harness.mouse_button(ButtonState::Pressed);
+    harness.step();
+}
+
+fn release(harness: &mut Harness) {
+    harness.mouse_button(ButtonState::Released);
+    harness.step();
+}
+
+/// A tap with a finger that lands at `down` and lifts at `up`.
+fn touch_tap(harness: &mut Harness, down: Vec2, up: Vec2) {
+    harness.touch(0, TouchPhase::Started, down);
+    harness.step();
+    // Like winit, report the last position as a move before lifting.
+    harness.touch(0, TouchPhase::Moved, up);
+    harness.touch(0, TouchPhase::Ended, up);
+    harness.step();
+}
+
+#[test]
+fn press_and_release_is_a_tap() {
+    let mut harness = harness_with(None);
+
+    harness.click::<Target>();
+
+    assert_eq!(recognized(&mut harness), [Gesture::Tap]);
+}
+
+#[test]
+fn two_quick_taps_are_a_double_tap() {
+    let mut harness = harness_with(None);
+
+    harness.double_click::<Target>();
+    assert_eq!(
+        recognized(&mut harness),
+        [Gesture::Tap, Gesture::Tap, Gesture::DoubleTap]
+    );
+
+    // A third tap starts a new pair instead of completing another one.
+    harness.click::<Target>();
+    assert_eq!(recognized(&mut harness), [Gesture::Tap]);
+}
+
+#[test]
+fn taps_further_apart_than_the_interval_are_separate() {
+    let mut harness = harness_with(None);
+
+    harness.click::<Target>();
+    harness.advance(Duration::from_millis(350));
+    harness.click::<Target>();
+
+    assert_eq!(recognized(&mut harness), [Gesture::Tap, Gesture::Tap]);
+}
+
+#[test]
+fn double_tap_interval_is_configurable() {
+    let mut harness = harness_with(Some(GestureSettings {
+        double_tap_interval: Duration::from_secs(1),
+        ..default()
+    }));
+
+    harness.click::<Target>();
+    harness.advance(Duration::from_millis(350));
+    harness.click::<Target>();
+
+    assert_eq!(
+        recognized(&mut harness),
+        [Gesture::Tap, Gesture::Tap, Gesture::DoubleTap]
+    );
+}
+
+#[test]
+fn double_tap_needs_both_taps_in_the_same_place() {
+    let mut harness = harness_with(None);
+
+    touch_tap(
+        &mut harness,
+        Vec2::new(100.0, 100.0),
+        Vec2::new(100.0, 100.0),
+    );
+    touch_tap(
+        &mut harness,
+        Vec2::new(120.0, 110.0),
+        Vec2::new(120.0, 110.0),
+    );
+    assert_eq!(
+        recognized(&mut harness),
+        [Gesture::Tap, Gesture::Tap, Gesture::DoubleTap]
+    );
+
+    touch_tap(
+        &mut harness,
+        Vec2::new(100.0, 100.0),
+        Vec2::new(100.0, 100.0),
+    );
+    touch_tap(
+        &mut harness,
+        Vec2::new(200.0, 100.0),
+        Vec2::new(200.0, 100.0),
+    );
+    assert_eq!(recognized(&mut harness), [Gesture::Tap, Gesture::Tap]);
+}
+
+#[test]
+fn moving_during_a_press_is_not_a_tap() {
+    let mut harness = harness_with(None);
+
+    touch_tap(
+        &mut harness,
+        Vec2::new(100.0, 100.0),
+        Vec2::new(104.0, 103.0),
+    );
+    assert_eq!(recognized(&mut harness), [Gesture::Tap]);
+
+    harness.advance(Duration::from_millis(400));
+    touch_tap(
+        &mut harness,
+        Vec2::new(100.0, 100.0),
+        Vec2::new(130.0, 100.0),
+    );
+    assert_eq!(recognized(&mut harness), []);
+}
+
+#[test]
+fn holding_is_a_long_press_not_a_tap() {
+    let mut harness = harness_with(Some(GestureSettings {
+        repeat_delay: Duration::from_secs(10),
+        ..default()
+    }));
+
+    press(&mut harness);
+    harness.advance(Duration::from_millis(400));
+    assert_eq!(recognized(&mut harness), []);
+
+    harness.advance(Duration::from_millis(200));
+    assert_eq!(recognized(&mut harness), [Gesture::LongPress]);
+
+    harness.advance(Duration::from_millis(500));
+    release(&mut harness);
+    assert_eq!(recognized(&mut harness), []);
+}
+
+#[test]
+fn holding_repeats_until_released() {
+    let mut harness = harness_with(Some(GestureSettings {
+        long_press: Duration::from_secs(10),
+        ..default()
+    }));
+
+    press(&mut harness);
+    harness.advance(Duration::from_millis(750));
+    assert_eq!(
+        recognized(&mut harness),
+        [Gesture::Repeat(1), Gesture::Repeat(2), Gesture::Repeat(3)]
+    );
+
+    release(&mut harness);
+    harness.advance(Duration::from_millis(500));
+    assert_eq!(recognized(&mut harness), [Gesture::Tap]);
+}
+
+#[test]
+fn dragging_a_held_press_stops_the_repeats() {
+    let mut harness = harness_with(None);
+
+    harness.touch(0, TouchPhase::Started, Vec2::new(50.0, 50.0));
+    harness.step();
+    harness.touch(0, TouchPhase::Moved, Vec2::new(50.0, 90.0));
+    harness.advance(Duration::from_secs(1));
+    harness.touch(0, TouchPhase::Ended, Vec2::new(50.0, 90.0));
+    harness.step();
+
+    assert_eq!(recognized(&mut harness), []);
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use bevy::prelude::KeyCode;
+use common::Harness;
+use rust_ios_hello::{
+    ClickButton, ConfirmButton, CounterLabel, DoubleClickButton, GuiCommand, GuiState, History,
+    RedoButton, ResetButton, UndoButton,
+};
+
+#[test]
+fn undo_restores_previous_state_and_redo_reapplies() {
+    let mut history = History::default();
+    let mut state = GuiState::default();
+
+    history.execute(GuiCommand::Click, &mut state);
+    history.execute(GuiCommand::DoubleClick, &mut state);
+    let after = state.clone();
+
+    assert_eq!(history.undo(&mut state), Some(GuiCommand::DoubleClick));
+    assert_eq!(state.click_count, 1);
+    assert_eq!(history.undo(&mut state), Some(GuiCommand::Click));
+    assert_eq!(state, GuiState::default());
+    assert_eq!(history.undo(&mut state), None);
+
+    history.redo(&mut state);
+    history.redo(&mut state);
+    assert_eq!(state, after);
+    assert!(!history.can_redo());
+}
+
+#[test]
+fn new_command_clears_redo() {
+    let mut history = History::default();
+    let mut state = GuiState::default();
+
+    history.execute(GuiCommand::Click, &mut state);
+    history.undo(&mut state);
+    history.execute(GuiCommand::DoubleClick, &mut state);
+
+    assert!(!history.can_redo());
+    assert_eq!(history.redo(&mut state), None);
+}
+
+#[test]
+fn capacity_drops_oldest_commands() {
+    let mut history = History::with_capacity(2);
+    let mut state = GuiState::default();
+
+    for _ in 0..3 {
+        history.execute(GuiCommand::Click, &mut state);
+    }
+
+    assert!(history.undo(&mut state).is_some());
+    assert!(history.undo(&mut state).is_some());
+    assert_eq!(history.undo(&mut state), None);
+    assert_eq!(state.click_count, 1);
+}
+
+#[test]
+fn reset_can_be_undone_with_button() {
+    let mut harness = Harness::new();
+
+    harness.click::<ClickButton>();
+    harness.double_click::<DoubleClickButton>();
+    let before_reset = harness.state().clone();
+    harness.click::<ResetButton>();
+    harness.click::<ConfirmButton>();
+    assert_eq!(*harness.state(), GuiState::default());
+
+    harness.click::<UndoButton>();
+    assert_eq!(*harness.state(), before_reset);
+    assert_eq!(harness.text::<CounterLabel>(), "Clicks: 3");
+
+    harness.click::<RedoButton>();
+    assert_eq!(*harness.state(), GuiState::default());
+}
+
+#[test]
+fn keyboard_shortcuts_undo_and_redo() {
+    let mut harness = Harness::new();
+
+    harness.click::<ClickButton>();
+    harness.shortcut(&[KeyCode::ControlLeft], KeyCode::KeyZ);
+    assert_eq!(harness.state().click_count, 0);
+
+    harness.shortcut(&[KeyCode::SuperLeft, KeyCode::ShiftLeft], KeyCode::KeyZ);
+    assert_eq!(harness.state().click_count, 1);
+}
+
+#[test]
+fn z_without_modifier_does_nothing() {
+    let mut harness = Harness::new();
+
+    harness.click::<ClickButton>();
+    harness.shortcut(&[], KeyCode::KeyZ);
+
+    assert_eq!(harness.state().click_count, 1);
+}
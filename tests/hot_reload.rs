@@ -0,0 +1,173 @@
+#![cfg(all(feature = "hot-reload", not(target_os = "ios")))]
+
+mod common;
+
+use bevy::prelude::*;
+use common::Harness;
+use rust_ios_hello::{
+    ActionButton, ButtonAction, ButtonRole, ClickButton, CounterLabel, Element, GuiPlugin,
+    HotReloadPlugin, Layout, ScreenLayout, ScreenRoot, Theme, ThemeHandles, UndoButton,
+    BUILTIN_LAYOUT, DARK_THEME, LIGHT_THEME,
+};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+fn write_assets(dir: &Path) {
+    fs::create_dir_all(dir.join("layouts")).unwrap();
+    fs::create_dir_all(dir.join("themes")).unwrap();
+    fs::write(dir.join("layouts/main.layout.ron"), BUILTIN_LAYOUT).unwrap();
+    fs::write(dir.join("themes/dark.theme.ron"), DARK_THEME).unwrap();
+    fs::write(dir.join("themes/light.theme.ron"), LIGHT_THEME).unwrap();
+}
+
+/// Steps until the layout and both theme files have loaded.
+fn hot_reload_harness(dir: &Path) -> Harness {
+    let mut harness = Harness::build(
+        GuiPlugin {
+            layout: Some("layouts/main.layout.ron".to_string()),
+            ..default()
+        },
+        |app| {
+            app.add_plugins(AssetPlugin {
+                file_path: dir.to_str().unwrap().to_string(),
+                ..default()
+            })
+            .add_plugins(HotReloadPlugin);
+        },
+    );
+
+    for _ in 0..200 {
+        let world = harness.app.world();
+        let laid_out = world.resource::<ScreenLayout>().root.is_some();
+        let themes_loaded = world.get_resource::<ThemeHandles>().is_some_and(|handles| {
+            let themes = world.resource::<Assets<Theme>>();
+            themes.contains(&handles.dark) && themes.contains(&handles.light)
+        });
+        if laid_out && themes_loaded {
+            return harness;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+        harness.step();
+    }
+    panic!("assets did not load");
+}
+
+fn edit_layout(harness: &mut Harness, edit: impl FnOnce(&mut Layout)) {
+    let handle = harness
+        .app
+        .world()
+        .resource::<ScreenLayout>()
+        .handle
+        .clone();
+    let mut layouts = harness.app.world_mut().resource_mut::<Assets<Layout>>();
+    edit(layouts.get_mut(&handle).unwrap());
+    settle(harness);
+}
+
+/// Asset events are sent after `Update`, so edits are seen a frame later.
+fn settle(harness: &mut Harness) {
+    harness.step();
+    harness.step();
+}
+
+#[test]
+fn layout_change_rebuilds_screen_and_keeps_state() {
+    let dir = tempfile::tempdir().unwrap();
+    write_assets(dir.path());
+    let mut harness = hot_reload_harness(dir.path());
+
+    harness.click::<ClickButton>();
+    let old_root = harness.entity::<ScreenRoot>();
+
+    edit_layout(&mut harness, |layout| {
+        layout.children.retain(|element| {
+            !matches!(
+                element,
+                Element::Button {
+                    action: ButtonAction::SpecialEffect,
+                    ..
+                }
+            )
+        });
+    });
+
+    let new_root = harness.entity::<ScreenRoot>();
+    assert_ne!(old_root, new_root);
+    assert!(harness.app.world().get_entity(old_root).is_err());
+    assert!(!harness
+        .app
+        .world_mut()
+        .query::<&ActionButton>()
+        .iter(harness.app.world())
+        .any(|button| button.action == ButtonAction::SpecialEffect));
+
+    // The rebuilt widgets reflect the state from before the reload.
+    assert_eq!(harness.state().click_count, 1);
+    assert_eq!(harness.text::<CounterLabel>(), "Clicks: 1");
+    let click = harness.entity::<ClickButton>();
+    assert_eq!(
+        harness.app.world().get::<ButtonRole>(click),
+        Some(&ButtonRole::Active)
+    );
+    let undo = harness.entity::<UndoButton>();
+    assert!(
+        !harness
+            .app
+            .world()
+            .get::<ActionButton>(undo)
+            .unwrap()
+            .disabled
+    );
+}
+
+#[test]
+fn theme_change_restyles_current_theme() {
+    let dir = tempfile::tempdir().unwrap();
+    write_assets(dir.path());
+    let mut harness = hot_reload_harness(dir.path());
+    let root = harness.entity::<ScreenRoot>();
+    let purple = Color::srgb(0.3, 0.0, 0.3);
+
+    let handle = harness.app.world().resource::<ThemeHandles>().dark.clone();
+    harness
+        .app
+        .world_mut()
+        .resource_mut::<Assets<Theme>>()
+        .get_mut(&handle)
+        .unwrap()
+        .background = purple;
+    settle(&mut harness);
+
+    assert_eq!(harness.app.world().resource::<Theme>().background, purple);
+    assert_eq!(
+        harness.app.world().get::<BackgroundColor>(root).unwrap().0,
+        purple
+    );
+}
+
+#[test]
+fn edited_theme_file_is_picked_up() {
+    let dir = tempfile::tempdir().unwrap();
+    write_assets(dir.path());
+    let mut harness = hot_reload_harness(dir.path());
+    let root = harness.entity::<ScreenRoot>();
+
+    let edited = DARK_THEME.replacen(
+        "background: (0.15, 0.15, 0.15)",
+        "background: (0.0, 0.0, 0.5)",
+        1,
+    );
+    assert_ne!(edited, DARK_THEME);
+    fs::write(dir.path().join("themes/dark.theme.ron"), edited).unwrap();
+
+    let expected = Color::srgb(0.0, 0.0, 0.5);
+    for _ in 0..500 {
+        if harness.app.world().get::<BackgroundColor>(root).unwrap().0 == expected {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+        harness.step();
+    }
+    panic!("theme file change was not picked up");
+}
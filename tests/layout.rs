@@ -0,0 +1,232 @@
+mod common;
+
+use bevy::prelude::*;
+use common::Harness;
+use rust_ios_hello::{
+    ActionButton, ButtonAction, ButtonRole, ClickButton, CounterLabel, Element, GuiPlugin,
+    GuiState, Layout, LayoutError, ResetButton, ScreenLayout, StatusLabel, UndoButton,
+};
+use std::fs;
+use std::path::Path;
+
+fn invalid_line(source: &str) -> (usize, String) {
+    match Layout::parse(source) {
+        Err(LayoutError::Invalid { line, message }) => (line, message),
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+}
+
+#[test]
+fn builtin_layout_parses() {
+    let layout = Layout::builtin();
+    let mut buttons = 0;
+    layout.visit(|_, element| {
+        if matches!(element, Element::Button { .. }) {
+            buttons += 1;
+        }
+    });
+
+    assert_eq!(buttons, 8);
+}
+
+#[test]
+fn syntax_errors_report_line_and_column() {
+    let source = "(\n    children: [\n        Text(text: \"Hi\", role: Title),\n        Button(label: \"Go\", action: Jump),\n    ],\n)\n";
+
+    match Layout::parse(source) {
+        Err(LayoutError::Syntax { line, column, .. }) => {
+            assert_eq!(line, 4);
+            assert!(column > 1);
+        }
+        other => panic!("expected a syntax error, got {:?}", other),
+    }
+}
+
+#[test]
+fn unknown_fields_are_rejected() {
+    let source = "(children: [\n    Text(text: \"Hi\", role: Title, colour: Red),\n])";
+
+    assert!(matches!(
+        Layout::parse(source),
+        Err(LayoutError::Syntax { line: 2, .. })
+    ));
+}
+
+#[test]
+fn validation_errors_point_at_element() {
+    let source = r#"(
+    children: [
+        Text(role: Title),
+        // "Button(" in a comment or string must not throw off line numbers
+        Row(children: [
+            Button(label: "A", action: Info, marker: InfoButton),
+            Button(label: "B (Button(", action: Info, marker: InfoButton),
+        ]),
+    ],
+)"#;
+
+    let (line, message) = invalid_line(source);
+    assert_eq!(line, 7);
+    assert!(message.contains("already used on line 6"), "{}", message);
+}
+
+#[test]
+fn raw_strings_do_not_shift_lines() {
+    let source = r##"(
+    children: [
+        Text(text: r#"Say "Button(" "#, role: Title),
+        Text(text: r"(
+", role: Status),
+        Row(children: []),
+    ],
+)"##;
+
+    let (line, message) = invalid_line(source);
+    assert_eq!(line, 6);
+    assert_eq!(message, "row has no children");
+}
+
+#[test]
+fn text_without_value_is_invalid() {
+    let (line, _) =
+        invalid_line("(children: [\n    Text(role: Title),\n    Text(role: Counter),\n])");
+
+    assert_eq!(line, 3);
+}
+
+#[test]
+fn empty_row_is_invalid() {
+    let (line, message) = invalid_line("(children: [\n\n    Row(children: []),\n])");
+
+    assert_eq!(line, 3);
+    assert_eq!(message, "row has no children");
+}
+
+fn write_layout(dir: &Path, source: &str) {
+    fs::create_dir_all(dir.join("layouts")).unwrap();
+    fs::write(dir.join("layouts").join("test.layout.ron"), source).unwrap();
+}
+
+/// A harness whose screen comes from `test.layout.ron` in `dir`, starting
+/// from `state` as if it had been restored from disk.
+fn harness_with_layout_file(dir: &Path, state: GuiState) -> Harness {
+    let mut harness = Harness::build(
+        GuiPlugin {
+            layout: Some("layouts/test.layout.ron".to_string()),
+            ..default()
+        },
+        |app| {
+            app.add_plugins(AssetPlugin {
+                file_path: dir.to_str().unwrap().to_string(),
+                ..default()
+            })
+            .insert_resource(state);
+        },
+    );
+
+    for _ in 0..100 {
+        if harness
+            .app
+            .world()
+            .resource::<ScreenLayout>()
+            .root
+            .is_some()
+        {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        harness.step();
+    }
+    harness
+}
+
+#[test]
+fn layout_file_is_loaded_as_asset() {
+    let dir = tempfile::tempdir().unwrap();
+    write_layout(
+        dir.path(),
+        r#"(children: [
+            Text(text: "Clicks: 0", role: Counter, marker: CounterLabel),
+            Button(label: "Tap", action: Click, role: Primary, marker: ClickButton),
+        ])"#,
+    );
+    let mut harness = harness_with_layout_file(dir.path(), GuiState::default());
+
+    let click = harness.entity::<ClickButton>();
+    assert_eq!(
+        harness
+            .app
+            .world()
+            .get::<ActionButton>(click)
+            .unwrap()
+            .action,
+        ButtonAction::Click
+    );
+    assert_eq!(
+        harness
+            .app
+            .world_mut()
+            .query_filtered::<(), With<ResetButton>>()
+            .iter(harness.app.world())
+            .count(),
+        0
+    );
+
+    harness.click::<ClickButton>();
+    assert_eq!(harness.text::<CounterLabel>(), "Clicks: 1");
+}
+
+#[test]
+fn invalid_layout_file_falls_back_to_builtin() {
+    let dir = tempfile::tempdir().unwrap();
+    write_layout(dir.path(), "(children: [ Text(role: Counter) ])");
+    let mut harness = harness_with_layout_file(dir.path(), GuiState::default());
+
+    // The built-in layout has a reset button, the broken file would not.
+    harness.entity::<ResetButton>();
+}
+
+#[test]
+fn loaded_layout_shows_the_current_state() {
+    let dir = tempfile::tempdir().unwrap();
+    write_layout(
+        dir.path(),
+        r#"(children: [
+            Text(text: "Clicks: 0", role: Counter, marker: CounterLabel),
+            Text(text: "Button State: OFF", role: Status, marker: StatusLabel),
+            Button(label: "Tap", action: Click, role: Primary, marker: ClickButton),
+            Button(label: "Undo", action: Undo, marker: UndoButton),
+        ])"#,
+    );
+    let restored = GuiState {
+        click_count: 7,
+        button_state: true,
+        ..default()
+    };
+    let mut harness = harness_with_layout_file(dir.path(), restored);
+    harness.step();
+
+    // Spawn the screen again, as when the file finishes loading a few frames
+    // after the state was restored.
+    let world = harness.app.world_mut();
+    let root = world.resource_mut::<ScreenLayout>().root.take().unwrap();
+    world.despawn(root);
+    harness.step();
+
+    assert_eq!(harness.text::<CounterLabel>(), "Clicks: 7");
+    assert_eq!(harness.text::<StatusLabel>(), "Button State: ON");
+    let click = harness.entity::<ClickButton>();
+    assert_eq!(
+        harness.app.world().get::<ButtonRole>(click),
+        Some(&ButtonRole::Active)
+    );
+    let undo = harness.entity::<UndoButton>();
+    assert!(
+        harness
+            .app
+            .world()
+            .get::<ActionButton>(undo)
+            .unwrap()
+            .disabled
+    );
+}
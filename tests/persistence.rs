@@ -0,0 +1,127 @@
+mod common;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use common::Harness;
+use rust_ios_hello::{
+    decode_state, encode_state, ClickButton, CounterLabel, DecodeError, FileStorage, GuiState,
+    MemoryStorage, PersistencePlugin, StateStorage,
+};
+use std::time::Duration;
+
+/// Harness whose clock advances by 100ms per frame, with a 300ms debounce.
+fn harness_with(storage: MemoryStorage) -> Harness {
+    Harness::with(|app| {
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            100,
+        )))
+        .add_plugins(PersistencePlugin {
+            debounce: Duration::from_millis(300),
+            ..PersistencePlugin::new(storage)
+        });
+    })
+}
+
+fn saved_state(storage: &MemoryStorage) -> GuiState {
+    decode_state(&storage.contents().expect("state was saved")).unwrap()
+}
+
+#[test]
+fn round_trips_through_encoding() {
+    let state = GuiState {
+        click_count: 7,
+        button_state: true,
+        message: "quote \" and newline \n".to_string(),
+    };
+
+    assert_eq!(decode_state(&encode_state(&state)).unwrap(), state);
+}
+
+#[test]
+fn migrates_unversioned_file() {
+    let state = decode_state(r#"{ "click_count": 4, "button_state": true }"#).unwrap();
+
+    assert_eq!(state.click_count, 4);
+    assert!(state.button_state);
+    assert_eq!(state.message, GuiState::default().message);
+}
+
+#[test]
+fn rejects_future_version() {
+    let result = decode_state(r#"{ "version": 99, "click_count": 1 }"#);
+
+    assert!(matches!(result, Err(DecodeError::UnsupportedVersion(99))));
+}
+
+#[test]
+fn loads_saved_state_at_startup() {
+    let saved = GuiState {
+        click_count: 12,
+        button_state: true,
+        message: "Saved".to_string(),
+    };
+    let mut harness = harness_with(MemoryStorage::with_contents(encode_state(&saved)));
+
+    assert_eq!(*harness.state(), saved);
+    assert_eq!(harness.text::<CounterLabel>(), "Clicks: 12");
+}
+
+#[test]
+fn corrupt_file_falls_back_to_default() {
+    let harness = harness_with(MemoryStorage::with_contents("{ not json"));
+
+    assert_eq!(*harness.state(), GuiState::default());
+}
+
+#[test]
+fn saves_only_after_debounce() {
+    let storage = MemoryStorage::default();
+    let mut harness = harness_with(storage.clone());
+
+    harness.click::<ClickButton>();
+    assert_eq!(storage.contents(), None);
+
+    for _ in 0..3 {
+        harness.step();
+    }
+    assert_eq!(saved_state(&storage).click_count, 1);
+}
+
+#[test]
+fn rapid_changes_restart_debounce() {
+    let storage = MemoryStorage::default();
+    let mut harness = harness_with(storage.clone());
+
+    for _ in 0..4 {
+        harness.click::<ClickButton>();
+    }
+    assert_eq!(storage.contents(), None);
+
+    for _ in 0..3 {
+        harness.step();
+    }
+    assert_eq!(saved_state(&storage).click_count, 4);
+}
+
+#[test]
+fn flushes_pending_save_on_exit() {
+    let storage = MemoryStorage::default();
+    let mut harness = harness_with(storage.clone());
+
+    harness.click::<ClickButton>();
+    harness.app.world_mut().write_message(AppExit::Success);
+    harness.step();
+
+    assert_eq!(saved_state(&storage).click_count, 1);
+}
+
+#[test]
+fn file_storage_round_trips_in_temp_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = FileStorage::new(dir.path().join("nested").join("gui_state.json"));
+
+    assert_eq!(storage.load().unwrap(), None);
+    storage.save("contents").unwrap();
+    assert_eq!(storage.load().unwrap().as_deref(), Some("contents"));
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy::window::{WindowTheme, WindowThemeChanged};
+use common::Harness;
+use rust_ios_hello::{
+    ClickButton, CounterLabel, ResetButton, ScreenRoot, Theme, ThemeError, ThemeKind,
+    ThemeSettings, ThemeToggleButton, DARK_THEME, LIGHT_THEME,
+};
+
+fn background(harness: &mut Harness, entity: Entity) -> Color {
+    harness
+        .app
+        .world()
+        .get::<BackgroundColor>(entity)
+        .unwrap()
+        .0
+}
+
+fn text_color(harness: &mut Harness, entity: Entity) -> Color {
+    harness.app.world().get::<TextColor>(entity).unwrap().0
+}
+
+fn system_theme_changed(harness: &mut Harness, theme: WindowTheme) {
+    harness.app.world_mut().write_message(WindowThemeChanged {
+        window: Entity::PLACEHOLDER,
+        theme,
+    });
+    harness.step();
+}
+
+#[test]
+fn widgets_start_with_dark_theme() {
+    let mut harness = Harness::new();
+    let root = harness.entity::<ScreenRoot>();
+    let counter = harness.entity::<CounterLabel>();
+
+    assert_eq!(background(&mut harness, root), Theme::dark().background);
+    assert_eq!(text_color(&mut harness, counter), Theme::dark().text);
+}
+
+#[test]
+fn toggle_button_restyles_everything() {
+    let mut harness = Harness::new();
+    let light = Theme::light();
+    let root = harness.entity::<ScreenRoot>();
+    let counter = harness.entity::<CounterLabel>();
+    let reset = harness.entity::<ResetButton>();
+    let click = harness.entity::<ClickButton>();
+
+    harness.click::<ThemeToggleButton>();
+
+    assert_eq!(
+        harness.app.world().resource::<Theme>().kind,
+        ThemeKind::Light
+    );
+    assert_eq!(background(&mut harness, root), light.background);
+    assert_eq!(text_color(&mut harness, counter), light.text);
+    assert_eq!(background(&mut harness, reset), light.danger.normal);
+    assert_eq!(background(&mut harness, click), light.primary.normal);
+
+    harness.click::<ClickButton>();
+    assert_eq!(background(&mut harness, click), light.active.normal);
+
+    harness.click::<ThemeToggleButton>();
+    assert_eq!(background(&mut harness, root), Theme::dark().background);
+    assert_eq!(background(&mut harness, click), Theme::dark().active.normal);
+}
+
+#[test]
+fn follows_system_appearance() {
+    let mut harness = Harness::new();
+    let root = harness.entity::<ScreenRoot>();
+
+    system_theme_changed(&mut harness, WindowTheme::Light);
+    assert_eq!(background(&mut harness, root), Theme::light().background);
+
+    system_theme_changed(&mut harness, WindowTheme::Dark);
+    assert_eq!(background(&mut harness, root), Theme::dark().background);
+}
+
+#[test]
+fn starts_in_the_window_appearance() {
+    let mut harness = Harness::new();
+    // What winit reports once it has created the window.
+    let window = harness.window();
+    harness
+        .app
+        .world_mut()
+        .get_mut::<Window>(window)
+        .unwrap()
+        .window_theme = Some(WindowTheme::Light);
+    harness.step();
+    assert_eq!(
+        harness.app.world().resource::<Theme>().kind,
+        ThemeKind::Light
+    );
+
+    system_theme_changed(&mut harness, WindowTheme::Dark);
+    assert_eq!(
+        harness.app.world().resource::<Theme>().kind,
+        ThemeKind::Dark
+    );
+}
+
+#[test]
+fn manual_choice_overrides_system_appearance() {
+    let mut harness = Harness::new();
+
+    harness.click::<ThemeToggleButton>();
+    assert!(
+        !harness
+            .app
+            .world()
+            .resource::<ThemeSettings>()
+            .follow_system
+    );
+
+    system_theme_changed(&mut harness, WindowTheme::Dark);
+    assert_eq!(
+        harness.app.world().resource::<Theme>().kind,
+        ThemeKind::Light
+    );
+}
+
+#[test]
+fn builtin_theme_files_parse() {
+    assert_eq!(Theme::parse(DARK_THEME).unwrap().kind, ThemeKind::Dark);
+    assert_eq!(Theme::parse(LIGHT_THEME).unwrap().kind, ThemeKind::Light);
+}
+
+#[test]
+fn theme_syntax_errors_report_line() {
+    let source = DARK_THEME.replacen("background:", "backdrop:", 1);
+    let line = DARK_THEME
+        .lines()
+        .position(|line| line.contains("background:"))
+        .unwrap()
+        + 1;
+
+    match Theme::parse(&source) {
+        Err(ThemeError::Syntax { line: actual, .. }) => assert_eq!(actual, line),
+        other => panic!("expected a syntax error, got {:?}", other),
+    }
+}
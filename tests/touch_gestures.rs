@@ -0,0 +1,349 @@
+mod common;
+
+use bevy::input::touch::TouchPhase;
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowResolution};
+use common::Harness;
+use rust_ios_hello::{
+    GesturePhase, ScreenRoot, SwipeDirection, TouchGesture, TouchGestureRecognized, TouchGestures,
+};
+use std::time::Duration;
+
+#[derive(Resource, Default)]
+struct Recognized(Vec<(Entity, TouchGesture)>);
+
+/// A harness whose screen root fills the 400x800 window.
+fn harness() -> (Harness, Entity) {
+    harness_with(|_| {})
+}
+
+/// Like [`harness`], but lets the test adjust the app before the first frame.
+fn harness_with(configure: impl FnOnce(&mut App)) -> (Harness, Entity) {
+    let mut harness = Harness::with(|app| {
+        configure(app);
+        // In `PostUpdate`, so everything the GUI sent this frame is seen.
+        app.init_resource::<Recognized>().add_systems(
+            PostUpdate,
+            |mut recognized: MessageReader<TouchGestureRecognized>,
+             mut seen: ResMut<Recognized>| {
+                seen.0
+                    .extend(recognized.read().map(|r| (r.entity, r.gesture)));
+            },
+        );
+    });
+    let root = harness.entity::<ScreenRoot>();
+    (harness, root)
+}
+
+fn recognized(harness: &mut Harness) -> Vec<(Entity, TouchGesture)> {
+    std::mem::take(&mut harness.app.world_mut().resource_mut::<Recognized>().0)
+}
+
+/// Only the gestures, for tests with a single recognizing node.
+fn gestures(harness: &mut Harness) -> Vec<TouchGesture> {
+    recognized(harness).into_iter().map(|(_, g)| g).collect()
+}
+
+fn finger(harness: &mut Harness, id: u64, phase: TouchPhase, position: Vec2) {
+    harness.touch(id, phase, position);
+    harness.step();
+}
+
+/// Puts a finger down at `from`, moves it to `to` over three frames and
+/// lifts it.
+fn flick(harness: &mut Harness, from: Vec2, to: Vec2) {
+    finger(harness, 0, TouchPhase::Started, from);
+    for i in 1..=3 {
+        finger(harness, 0, TouchPhase::Moved, from.lerp(to, i as f32 / 3.0));
+    }
+    finger(harness, 0, TouchPhase::Ended, to);
+}
+
+fn pan(phase: GesturePhase, translation: Vec2, delta: Vec2) -> TouchGesture {
+    TouchGesture::Pan {
+        phase,
+        translation,
+        delta,
+    }
+}
+
+#[test]
+fn quick_flicks_are_swipes_in_their_direction() {
+    let (mut harness, root) = harness();
+    let start = Vec2::new(200.0, 400.0);
+
+    for (offset, direction) in [
+        (Vec2::new(-120.0, 10.0), SwipeDirection::Left),
+        (Vec2::new(120.0, -20.0), SwipeDirection::Right),
+        (Vec2::new(15.0, -150.0), SwipeDirection::Up),
+        (Vec2::new(0.0, 90.0), SwipeDirection::Down),
+    ] {
+        flick(&mut harness, start, start + offset);
+        let recognized = recognized(&mut harness);
+        assert_eq!(
+            recognized.last(),
+            Some(&(root, TouchGesture::Swipe(direction))),
+            "{:?}",
+            offset
+        );
+        assert!(recognized.iter().all(|(entity, _)| *entity == root));
+    }
+}
+
+#[test]
+fn slow_drags_are_pans() {
+    let (mut harness, _) = harness();
+    let start = Vec2::new(100.0, 100.0);
+
+    finger(&mut harness, 0, TouchPhase::Started, start);
+    finger(
+        &mut harness,
+        0,
+        TouchPhase::Moved,
+        start + Vec2::new(30.0, 0.0),
+    );
+    harness.advance(Duration::from_millis(600));
+    finger(
+        &mut harness,
+        0,
+        TouchPhase::Moved,
+        start + Vec2::new(60.0, 5.0),
+    );
+    finger(
+        &mut harness,
+        0,
+        TouchPhase::Ended,
+        start + Vec2::new(60.0, 5.0),
+    );
+
+    assert_eq!(
+        gestures(&mut harness),
+        [
+            pan(
+                GesturePhase::Started,
+                Vec2::new(30.0, 0.0),
+                Vec2::new(30.0, 0.0)
+            ),
+            pan(
+                GesturePhase::Changed,
+                Vec2::new(60.0, 5.0),
+                Vec2::new(30.0, 5.0)
+            ),
+            pan(GesturePhase::Ended, Vec2::new(60.0, 5.0), Vec2::ZERO),
+        ]
+    );
+}
+
+#[test]
+fn small_movements_are_ignored() {
+    let (mut harness, _) = harness();
+
+    flick(
+        &mut harness,
+        Vec2::new(100.0, 100.0),
+        Vec2::new(106.0, 104.0),
+    );
+
+    assert_eq!(gestures(&mut harness), []);
+}
+
+#[test]
+fn two_fingers_pinch() {
+    let (mut harness, _) = harness();
+
+    finger(
+        &mut harness,
+        0,
+        TouchPhase::Started,
+        Vec2::new(100.0, 400.0),
+    );
+    finger(
+        &mut harness,
+        1,
+        TouchPhase::Started,
+        Vec2::new(300.0, 400.0),
+    );
+    finger(&mut harness, 1, TouchPhase::Moved, Vec2::new(500.0, 400.0));
+    finger(&mut harness, 0, TouchPhase::Moved, Vec2::new(0.0, 400.0));
+    finger(&mut harness, 1, TouchPhase::Ended, Vec2::new(500.0, 400.0));
+
+    assert_eq!(
+        gestures(&mut harness),
+        [
+            TouchGesture::Pinch {
+                phase: GesturePhase::Started,
+                scale: 2.0,
+                delta: 2.0,
+                center: Vec2::new(300.0, 400.0),
+            },
+            TouchGesture::Pinch {
+                phase: GesturePhase::Changed,
+                scale: 2.5,
+                delta: 1.25,
+                center: Vec2::new(250.0, 400.0),
+            },
+            TouchGesture::Pinch {
+                phase: GesturePhase::Ended,
+                scale: 2.5,
+                delta: 1.0,
+                center: Vec2::new(250.0, 400.0),
+            },
+        ]
+    );
+
+    // The finger left behind pans from where it is, and lifting it after a
+    // pinch is never a swipe.
+    finger(&mut harness, 0, TouchPhase::Moved, Vec2::new(100.0, 400.0));
+    finger(&mut harness, 0, TouchPhase::Ended, Vec2::new(100.0, 400.0));
+    assert_eq!(
+        gestures(&mut harness),
+        [
+            pan(
+                GesturePhase::Started,
+                Vec2::new(100.0, 0.0),
+                Vec2::new(100.0, 0.0)
+            ),
+            pan(GesturePhase::Ended, Vec2::new(100.0, 0.0), Vec2::ZERO),
+        ]
+    );
+}
+
+#[test]
+fn second_finger_ends_a_pan() {
+    let (mut harness, _) = harness();
+
+    finger(
+        &mut harness,
+        0,
+        TouchPhase::Started,
+        Vec2::new(100.0, 400.0),
+    );
+    finger(&mut harness, 0, TouchPhase::Moved, Vec2::new(150.0, 400.0));
+    finger(
+        &mut harness,
+        1,
+        TouchPhase::Started,
+        Vec2::new(300.0, 400.0),
+    );
+
+    assert_eq!(
+        gestures(&mut harness),
+        [
+            pan(
+                GesturePhase::Started,
+                Vec2::new(50.0, 0.0),
+                Vec2::new(50.0, 0.0)
+            ),
+            pan(GesturePhase::Ended, Vec2::new(50.0, 0.0), Vec2::ZERO),
+        ]
+    );
+}
+
+#[test]
+fn canceled_touches_end_without_a_swipe() {
+    let (mut harness, _) = harness();
+
+    finger(
+        &mut harness,
+        0,
+        TouchPhase::Started,
+        Vec2::new(100.0, 100.0),
+    );
+    finger(&mut harness, 0, TouchPhase::Moved, Vec2::new(250.0, 100.0));
+    finger(
+        &mut harness,
+        0,
+        TouchPhase::Canceled,
+        Vec2::new(250.0, 100.0),
+    );
+
+    assert_eq!(
+        gestures(&mut harness),
+        [
+            pan(
+                GesturePhase::Started,
+                Vec2::new(150.0, 0.0),
+                Vec2::new(150.0, 0.0)
+            ),
+            pan(GesturePhase::Ended, Vec2::new(150.0, 0.0), Vec2::ZERO),
+        ]
+    );
+}
+
+#[test]
+fn touches_go_to_the_topmost_node_under_them() {
+    let (mut harness, root) = harness();
+    let panel = harness
+        .app
+        .world_mut()
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Px(100.0),
+                height: Val::Px(100.0),
+                ..default()
+            },
+            GlobalZIndex(1),
+            TouchGestures::default(),
+        ))
+        .id();
+    // A 100x100 panel in the top left corner, drawn above the root.
+    harness.step();
+
+    flick(&mut harness, Vec2::new(50.0, 50.0), Vec2::new(50.0, 200.0));
+    assert_eq!(
+        recognized(&mut harness).last(),
+        Some(&(panel, TouchGesture::Swipe(SwipeDirection::Down)))
+    );
+
+    flick(
+        &mut harness,
+        Vec2::new(200.0, 300.0),
+        Vec2::new(50.0, 300.0),
+    );
+    assert_eq!(
+        recognized(&mut harness).last(),
+        Some(&(root, TouchGesture::Swipe(SwipeDirection::Left)))
+    );
+
+    // Off screen.
+    flick(
+        &mut harness,
+        Vec2::new(500.0, 300.0),
+        Vec2::new(800.0, 300.0),
+    );
+    assert_eq!(recognized(&mut harness), []);
+}
+
+#[test]
+fn layout_scale_factor_is_respected() {
+    // At 2x, the 400x800 point screen is 800x1600 physical pixels.
+    let (mut harness, root) = harness_with(|app| {
+        let mut window = app
+            .world_mut()
+            .query_filtered::<&mut Window, With<PrimaryWindow>>()
+            .single_mut(app.world_mut())
+            .unwrap();
+        window.resolution = WindowResolution::new(800, 1600).with_scale_factor_override(2.0);
+    });
+    assert_eq!(
+        harness
+            .app
+            .world()
+            .get::<ComputedNode>(root)
+            .unwrap()
+            .inverse_scale_factor,
+        0.5
+    );
+
+    flick(
+        &mut harness,
+        Vec2::new(350.0, 700.0),
+        Vec2::new(200.0, 700.0),
+    );
+
+    assert_eq!(
+        recognized(&mut harness).last(),
+        Some(&(root, TouchGesture::Swipe(SwipeDirection::Left)))
+    );
+}
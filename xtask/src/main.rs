@@ -0,0 +1,545 @@
+//! `cargo xtask bundle`: builds the app, assembles `RustApp.app` and, when
+//! asked to, installs and launches it on a simulator. Bundles for device
+//! targets are signed first, and deployed to a connected device. Several
+//! simulator targets are joined into one fat binary with `lipo`.
+//!
+//! `cargo xtask xcframework`: builds the static library for the device and
+//! both simulator architectures and wraps it, with its C header, in an
+//! XCFramework for Xcode projects.
+//!
+//! `cargo xtask symbolicate`: turns the app's frames in a crash report into
+//! Rust functions, files and lines, using the debug symbols `bundle` archives
+//! under `target/symbols` for release builds.
+//!
+//! Runs after compilation, so the output always contains what was just
+//! built. The layouts themselves live in `ios_bundle`.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+use std::time::SystemTime;
+
+use ios_bundle::{
+    archive_dsym, assemble, create_xcframework, deploy_requested, deploy_step,
+    find_or_extract_dsym, install_and_launch, is_simulator_target, lipo, sign_bundle,
+    symbolicate_report, AppleTarget, BuildUuid, BundleSpec, CrashReport, DeviceQuery, DeviceTool,
+    IosMetadata, LibrarySlice, SigningConfig, Step, Symbolicator, SystemRunner, XcframeworkSpec,
+};
+
+const USAGE: &str = "\
+Usage: cargo xtask bundle [options]
+       cargo xtask xcframework [options]
+       cargo xtask symbolicate [options] <crash report>
+       cargo xtask symbolicate [options] --uuid <uuid> <address>...
+
+bundle: builds rust_ios_hello and assembles the iOS app bundle.
+
+Options:
+    --target <triple>   Target to build for [default: aarch64-apple-ios-sim].
+                        Repeat it with aarch64-apple-ios-sim and
+                        x86_64-apple-ios for a simulator binary that runs on
+                        Apple Silicon and Intel Macs
+    --release           Build and bundle the release profile
+    --no-build          Bundle the existing binary without running cargo build
+    --executable <path> Bundle this binary instead of building one; lets this
+                        command be used as a cargo runner
+    --out <path>        Where to put the bundle [default: RustApp.app]
+    --symbols           Archive the binary's debug symbols under
+                        target/symbols/<build UUID>; always done with
+                        --release
+    --deploy            Install and launch the bundle; also enabled by
+                        IOS_DEPLOY=1. Simulators are picked with IOS_SIMULATOR
+                        and IOS_SIMULATOR_RUNTIME, devices with IOS_DEVICE
+    -h, --help          Print this message
+
+Device targets are signed with IOS_SIGNING_IDENTITY and
+IOS_PROVISIONING_PROFILE, or the matching package.metadata.ios keys.
+
+xcframework: builds the static library and wraps it and its C header in an
+XCFramework.
+
+Options:
+    --target <triple>   Target to include; repeatable [default:
+                        aarch64-apple-ios, aarch64-apple-ios-sim,
+                        x86_64-apple-ios]
+    --release           Build the release profile
+    --no-build          Package the libraries that are already built
+    --out <path>        Where to put the XCFramework
+                        [default: target/RustIosHello.xcframework]
+
+symbolicate: prints a crash report's backtraces with the app's frames
+resolved to Rust functions, files and lines. Takes .ips and .crash reports.
+
+Options:
+    --symbols <dir>       Archive written by bundle [default: target/symbols]
+    --uuid <uuid>         Look up bare addresses in this build instead of
+                          reading a crash report
+    --load-address <addr> Where the build was loaded, for --uuid; addresses
+                          are as linked without it
+";
+
+const DEFAULT_TARGET: &str = "aarch64-apple-ios-sim";
+const XCFRAMEWORK_TARGETS: &[&str] = &[
+    "aarch64-apple-ios",
+    "aarch64-apple-ios-sim",
+    "x86_64-apple-ios",
+];
+const BINARY: &str = "rust_ios_hello";
+const LIBRARY: &str = "librust_ios_hello.a";
+const HEADER: &str = "include/rust_ios_hello.h";
+/// Clang module Swift code imports the C API as.
+const MODULE: &str = "RustIosHello";
+
+#[derive(Debug)]
+struct BundleArgs {
+    /// One target, or several simulator targets for a fat binary.
+    targets: Vec<String>,
+    release: bool,
+    build: bool,
+    executable: Option<PathBuf>,
+    out: Option<PathBuf>,
+    deploy: bool,
+    /// Archive debug symbols; release builds always do.
+    symbols: bool,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("bundle") => parse_bundle_args(&args[1..]).and_then(|args| bundle(&args)),
+        Some("xcframework") => {
+            parse_xcframework_args(&args[1..]).and_then(|args| xcframework(&args))
+        }
+        Some("symbolicate") => {
+            parse_symbolicate_args(&args[1..]).and_then(|args| symbolicate(&args))
+        }
+        Some("-h" | "--help") => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Some(other) => Err(format!("unknown task {:?}\n\n{}", other, USAGE)),
+        None => Err(USAGE.to_string()),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_bundle_args(args: &[String]) -> Result<BundleArgs, String> {
+    let mut parsed = BundleArgs {
+        targets: Vec::new(),
+        release: false,
+        build: true,
+        executable: None,
+        out: None,
+        deploy: deploy_requested(|key| env::var(key).ok()),
+        symbols: false,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--target" => {
+                parsed
+                    .targets
+                    .push(args.next().ok_or("--target needs a value")?.clone());
+            }
+            "--release" => parsed.release = true,
+            "--no-build" => parsed.build = false,
+            "--deploy" => parsed.deploy = true,
+            "--symbols" => parsed.symbols = true,
+            "--executable" => {
+                parsed.executable = Some(args.next().ok_or("--executable needs a value")?.into());
+                parsed.build = false;
+            }
+            "--out" => {
+                parsed.out = Some(args.next().ok_or("--out needs a value")?.into());
+            }
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                std::process::exit(0);
+            }
+            other => return Err(format!("unknown option {:?}\n\n{}", other, USAGE)),
+        }
+    }
+    if parsed.targets.is_empty() {
+        parsed.targets.push(DEFAULT_TARGET.to_string());
+    }
+    if parsed.targets.len() > 1 {
+        if parsed.executable.is_some() {
+            return Err("--executable takes a single --target".to_string());
+        }
+        if let Some(device) = parsed.targets.iter().find(|t| !is_simulator_target(t)) {
+            return Err(format!(
+                "only simulator targets can share a bundle, {} is not one",
+                device
+            ));
+        }
+    }
+    Ok(parsed)
+}
+
+#[derive(Debug)]
+struct XcframeworkArgs {
+    targets: Vec<String>,
+    release: bool,
+    build: bool,
+    out: Option<PathBuf>,
+}
+
+fn parse_xcframework_args(args: &[String]) -> Result<XcframeworkArgs, String> {
+    let mut parsed = XcframeworkArgs {
+        targets: Vec::new(),
+        release: false,
+        build: true,
+        out: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--target" => {
+                parsed
+                    .targets
+                    .push(args.next().ok_or("--target needs a value")?.clone());
+            }
+            "--release" => parsed.release = true,
+            "--no-build" => parsed.build = false,
+            "--out" => {
+                parsed.out = Some(args.next().ok_or("--out needs a value")?.into());
+            }
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                std::process::exit(0);
+            }
+            other => return Err(format!("unknown option {:?}\n\n{}", other, USAGE)),
+        }
+    }
+    if parsed.targets.is_empty() {
+        parsed.targets = XCFRAMEWORK_TARGETS.iter().map(|t| t.to_string()).collect();
+    }
+    Ok(parsed)
+}
+
+#[derive(Debug)]
+struct SymbolicateArgs {
+    symbols: Option<PathBuf>,
+    /// A crash report, or the build to look `addresses` up in.
+    report: Option<PathBuf>,
+    uuid: Option<BuildUuid>,
+    load_address: Option<u64>,
+    addresses: Vec<u64>,
+}
+
+fn parse_address(text: &str) -> Result<u64, String> {
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    u64::from_str_radix(digits, 16).map_err(|_| format!("{:?} is not a hex address", text))
+}
+
+fn parse_symbolicate_args(args: &[String]) -> Result<SymbolicateArgs, String> {
+    let mut parsed = SymbolicateArgs {
+        symbols: None,
+        report: None,
+        uuid: None,
+        load_address: None,
+        addresses: Vec::new(),
+    };
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--symbols" => {
+                parsed.symbols = Some(args.next().ok_or("--symbols needs a value")?.into());
+            }
+            "--uuid" => {
+                let value = args.next().ok_or("--uuid needs a value")?;
+                parsed.uuid = Some(
+                    BuildUuid::parse(value)
+                        .ok_or_else(|| format!("{:?} is not a build UUID", value))?,
+                );
+            }
+            "--load-address" => {
+                let value = args.next().ok_or("--load-address needs a value")?;
+                parsed.load_address = Some(parse_address(value)?);
+            }
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                std::process::exit(0);
+            }
+            other if other.starts_with('-') => {
+                return Err(format!("unknown option {:?}\n\n{}", other, USAGE))
+            }
+            other => positional.push(other),
+        }
+    }
+    if parsed.uuid.is_some() {
+        if positional.is_empty() {
+            return Err("--uuid needs at least one address".to_string());
+        }
+        parsed.addresses = positional
+            .into_iter()
+            .map(parse_address)
+            .collect::<Result<_, _>>()?;
+    } else {
+        if parsed.load_address.is_some() {
+            return Err("--load-address goes with --uuid".to_string());
+        }
+        match positional[..] {
+            [report] => parsed.report = Some(report.into()),
+            _ => return Err(format!("symbolicate takes one crash report\n\n{}", USAGE)),
+        }
+    }
+    Ok(parsed)
+}
+
+/// The workspace root, one level above this crate.
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask lives inside the workspace")
+        .to_path_buf()
+}
+
+fn target_dir(root: &Path) -> PathBuf {
+    env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| root.join("target"))
+}
+
+/// Runs `cargo build <what> --target <target>` in the workspace.
+fn cargo_build(root: &Path, what: &[&str], target: &str, release: bool) -> Result<(), String> {
+    let profile = if release { "release" } else { "debug" };
+    println!("==> cargo build --target {} ({})", target, profile);
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let mut command = Command::new(cargo);
+    command
+        .current_dir(root)
+        .arg("build")
+        .args(what)
+        .args(["--target", target]);
+    if release {
+        command.arg("--release");
+    }
+    let status = command
+        .status()
+        .map_err(|e| format!("cannot run cargo: {}", e))?;
+    if !status.success() {
+        return Err(format!("cargo build failed ({})", status));
+    }
+    Ok(())
+}
+
+fn bundle(args: &BundleArgs) -> Result<(), String> {
+    let root = workspace_root();
+    let profile = if args.release { "release" } else { "debug" };
+    // Deployment and signing only care whether it's a simulator build, which
+    // all targets agree on.
+    let target = &args.targets[0];
+
+    if args.build {
+        for target in &args.targets {
+            cargo_build(&root, &["--bin", BINARY], target, args.release)?;
+        }
+    }
+
+    let manifest_path = root.join("Cargo.toml");
+    let manifest = fs::read_to_string(&manifest_path)
+        .map_err(|e| format!("cannot read {}: {}", manifest_path.display(), e))?;
+    let metadata = IosMetadata::from_package_manifest(&manifest).map_err(|e| e.to_string())?;
+
+    let executable = match &args.executable {
+        Some(executable) => executable.clone(),
+        None if args.targets.len() == 1 => {
+            target_dir(&root).join(target).join(profile).join(BINARY)
+        }
+        None => {
+            let fat = target_dir(&root)
+                .join("universal-sim")
+                .join(profile)
+                .join(BINARY);
+            let slices: Vec<PathBuf> = args
+                .targets
+                .iter()
+                .map(|target| target_dir(&root).join(target).join(profile).join(BINARY))
+                .collect();
+            lipo(&mut SystemRunner, &slices, &fat).map_err(|e| e.to_string())?;
+            print_step(&Step {
+                name: "lipo",
+                detail: format!("{} -> {}", args.targets.join(" + "), fat.display()),
+            });
+            fat
+        }
+    };
+    // Stamps and signing files live next to the binary, outside the bundle.
+    let work_dir = executable.parent().unwrap_or(Path::new(".")).join("bundle");
+    let spec = BundleSpec {
+        metadata: metadata.clone(),
+        manifest_dir: root.clone(),
+        executable,
+        resources: vec![metadata.assets_resource(&root)],
+    };
+    let bundle_dir = args.out.clone().unwrap_or_else(|| root.join("RustApp.app"));
+
+    assemble(&spec, &bundle_dir, &work_dir, &mut print_step).map_err(|e| e.to_string())?;
+    println!("Bundle ready at {}", bundle_dir.display());
+
+    // Release builds are the ones testers crash, so their symbols are
+    // always kept.
+    if args.release || args.symbols {
+        let dsym = find_or_extract_dsym(
+            &mut SystemRunner,
+            &spec.executable,
+            &work_dir,
+            &mut print_step,
+        )
+        .map_err(|e| e.to_string())?;
+        archive_dsym(&dsym, &target_dir(&root).join("symbols"), &mut print_step)
+            .map_err(|e| e.to_string())?;
+    }
+
+    if !is_simulator_target(target) {
+        return device(args, &root, &metadata, &bundle_dir, &work_dir);
+    }
+
+    let report = deploy_step(
+        args.deploy,
+        target,
+        &mut SystemRunner,
+        &DeviceQuery::new(&metadata, |key| env::var(key).ok()),
+        &bundle_dir,
+        &metadata.bundle_id,
+    );
+    println!("==> deploy: {}", report);
+    if report.is_failure() {
+        return Err("deploy failed".to_string());
+    }
+    Ok(())
+}
+
+fn xcframework(args: &XcframeworkArgs) -> Result<(), String> {
+    let root = workspace_root();
+    let profile = if args.release { "release" } else { "debug" };
+
+    let mut builds = Vec::new();
+    for triple in &args.targets {
+        let target =
+            AppleTarget::parse(triple).ok_or_else(|| format!("{} is not an iOS target", triple))?;
+        if args.build {
+            cargo_build(&root, &["--lib"], triple, args.release)?;
+        }
+        builds.push((
+            target,
+            target_dir(&root).join(triple).join(profile).join(LIBRARY),
+        ));
+    }
+
+    let spec = XcframeworkSpec {
+        library_name: LIBRARY.to_string(),
+        headers: vec![root.join(HEADER)],
+        module_name: Some(MODULE.to_string()),
+        slices: LibrarySlice::group(&builds).map_err(|e| e.to_string())?,
+    };
+    let out = args
+        .out
+        .clone()
+        .unwrap_or_else(|| target_dir(&root).join(format!("{}.xcframework", MODULE)));
+    create_xcframework(&mut SystemRunner, &spec, &out, &mut print_step)
+        .map_err(|e| e.to_string())?;
+    println!("XCFramework ready at {}", out.display());
+    Ok(())
+}
+
+fn symbolicate(args: &SymbolicateArgs) -> Result<(), String> {
+    let archive = args
+        .symbols
+        .clone()
+        .unwrap_or_else(|| target_dir(&workspace_root()).join("symbols"));
+    let mut symbolicator = Symbolicator::new(&archive);
+
+    if let Some(path) = &args.report {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        let report = CrashReport::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let symbolicated =
+            symbolicate_report(&report, &mut symbolicator).map_err(|e| e.to_string())?;
+        print!("{}", symbolicated);
+        return Ok(());
+    }
+
+    let uuid = args
+        .uuid
+        .expect("parse_symbolicate_args wants a report or a UUID");
+    let info = symbolicator
+        .debug_info(uuid)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("no symbols for {} in {}", uuid, archive.display()))?;
+    let load_address = args.load_address.unwrap_or(info.text.0);
+    for &address in &args.addresses {
+        let frames = info.lookup_loaded(load_address, address);
+        if frames.is_empty() {
+            println!("{:#018x} ??", address);
+        }
+        for frame in frames {
+            let inlined = if frame.inlined { " [inlined]" } else { "" };
+            println!("{:#018x} {}{}", address, frame, inlined);
+        }
+    }
+    Ok(())
+}
+
+fn print_step(step: &Step) {
+    println!("==> {}: {}", step.name, step.detail);
+}
+
+/// Signs the bundle for a device and, with `--deploy`, installs and launches
+/// it. Without signing configured, plain bundling still succeeds.
+fn device(
+    args: &BundleArgs,
+    root: &Path,
+    metadata: &IosMetadata,
+    bundle_dir: &Path,
+    work_dir: &Path,
+) -> Result<(), String> {
+    let config = match SigningConfig::new(metadata, root, |key| env::var(key).ok()) {
+        Ok(config) => config,
+        Err(e) if !args.deploy => {
+            println!("==> codesign: skipped ({})", e);
+            return Ok(());
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let mut runner = SystemRunner;
+    sign_bundle(
+        &mut runner,
+        &config,
+        &metadata.bundle_id,
+        bundle_dir,
+        work_dir,
+        SystemTime::now(),
+        &mut print_step,
+    )
+    .map_err(|e| e.to_string())?;
+
+    if !args.deploy {
+        println!("==> deploy: skipped (pass --deploy or set IOS_DEPLOY=1 to install on a device)");
+        return Ok(());
+    }
+    let tool = DeviceTool::detect(&mut runner).map_err(|e| e.to_string())?;
+    install_and_launch(
+        &mut runner,
+        tool,
+        config.device.as_deref(),
+        bundle_dir,
+        &metadata.bundle_id,
+        &mut print_step,
+    )
+    .map_err(|e| e.to_string())
+}